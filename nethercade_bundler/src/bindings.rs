@@ -0,0 +1,277 @@
+use std::fmt::Write;
+
+use nethercade_core::{Asset, AssetData, Pipeline};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Language {
+    Rust,
+    C,
+    Zig,
+}
+
+/// Asset kinds, numbered by position for the `ASSET_KIND_*` constants.
+const KINDS: [&str; 4] = ["texture", "mesh", "sound", "raw"];
+
+enum Value {
+    Int(i64),
+    Bool(bool),
+}
+
+/// Constants for one asset, named `<NAME>_<SUFFIX>`.
+struct Entry {
+    name: String,
+    description: String,
+    values: Vec<(&'static str, Value)>,
+}
+
+/// Source for a module of constants describing every asset, so games refer
+/// to assets by ids checked at compile time.
+pub fn generate(assets: &[Asset], language: Language) -> Result<String, String> {
+    let mut entries = Vec::<Entry>::with_capacity(assets.len());
+    for (id, asset) in assets.iter().enumerate() {
+        let name = identifier(&asset.name);
+        if let Some(other) = entries.iter().find(|entry| entry.name == name) {
+            return Err(format!(
+                "Assets {:?} and {:?} both generate the constant {name}",
+                other.description, asset.name
+            ));
+        }
+
+        entries.push(Entry {
+            description: asset.name.clone(),
+            values: values(id, &asset.data),
+            name,
+        });
+    }
+
+    let mut out = String::new();
+    writeln!(out, "// Generated by the Nethercade bundler, do not edit.").unwrap();
+    if language == Language::C {
+        writeln!(out, "#pragma once").unwrap();
+    }
+    writeln!(out).unwrap();
+
+    for (value, kind) in KINDS.iter().enumerate() {
+        write_constant(
+            &mut out,
+            language,
+            &format!("ASSET_KIND_{}", kind.to_ascii_uppercase()),
+            &Value::Int(value as i64),
+        );
+    }
+
+    for entry in entries {
+        writeln!(out).unwrap();
+        writeln!(out, "// {}", entry.description).unwrap();
+        for (suffix, value) in &entry.values {
+            write_constant(
+                &mut out,
+                language,
+                &format!("{}_{suffix}", entry.name),
+                value,
+            );
+        }
+    }
+
+    Ok(out)
+}
+
+fn values(id: usize, data: &AssetData) -> Vec<(&'static str, Value)> {
+    let kind = KINDS
+        .iter()
+        .position(|kind| *kind == data.kind_name())
+        .unwrap();
+    let mut values = vec![
+        ("ID", Value::Int(id as i64)),
+        ("KIND", Value::Int(kind as i64)),
+    ];
+
+    match data {
+        AssetData::Texture {
+            width,
+            height,
+            has_alpha,
+            ..
+        } => values.extend([
+            ("WIDTH", Value::Int(*width as i64)),
+            ("HEIGHT", Value::Int(*height as i64)),
+            ("HAS_ALPHA", Value::Bool(*has_alpha)),
+        ]),
+        AssetData::Mesh {
+            pipeline,
+            vertices,
+            indices,
+        } => {
+            let vertex_count = Pipeline::from_id(*pipeline)
+                .map_or(0, |pipeline| vertices.len() / pipeline.floats_per_vertex());
+            values.extend([
+                ("PIPELINE", Value::Int(*pipeline as i64)),
+                ("VERTEX_COUNT", Value::Int(vertex_count as i64)),
+                (
+                    "INDEX_COUNT",
+                    Value::Int(indices.as_ref().map_or(0, Vec::len) as i64),
+                ),
+            ]);
+        }
+        AssetData::Sound {
+            channels,
+            sample_rate,
+            samples,
+            ..
+        } => values.extend([
+            ("CHANNELS", Value::Int(*channels as i64)),
+            ("SAMPLE_RATE", Value::Int(*sample_rate as i64)),
+            (
+                "FRAMES",
+                Value::Int((samples.len() / (*channels).max(1) as usize) as i64),
+            ),
+        ]),
+        AssetData::Raw(bytes) => values.extend([("LEN", Value::Int(bytes.len() as i64))]),
+    }
+
+    values
+}
+
+fn write_constant(out: &mut String, language: Language, name: &str, value: &Value) {
+    match (language, value) {
+        (Language::Rust | Language::Zig, Value::Int(value)) => {
+            writeln!(out, "pub const {name}: i32 = {value};")
+        }
+        (Language::Rust | Language::Zig, Value::Bool(value)) => {
+            writeln!(out, "pub const {name}: bool = {value};")
+        }
+        (Language::C, Value::Int(value)) => writeln!(out, "#define {name} {value}"),
+        (Language::C, Value::Bool(value)) => writeln!(out, "#define {name} {}", *value as i32),
+    }
+    .unwrap();
+}
+
+/// Upper snake case, with anything but letters and digits turned into `_`.
+fn identifier(name: &str) -> String {
+    let mut identifier = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets() -> Vec<Asset> {
+        vec![
+            Asset {
+                name: String::from("player.png"),
+                data: AssetData::Texture {
+                    width: 16,
+                    height: 8,
+                    has_alpha: true,
+                    pixels: vec![0; 16 * 8 * 4],
+                },
+            },
+            Asset {
+                name: String::from("1up"),
+                data: AssetData::Raw(vec![0; 3]),
+            },
+        ]
+    }
+
+    /// The constants every language gets, in its syntax.
+    fn expected(header: &str, constant: fn(&str, &str) -> String) -> String {
+        let mut expected =
+            format!("// Generated by the Nethercade bundler, do not edit.\n{header}\n");
+        for (kind, value) in [
+            ("TEXTURE", "0"),
+            ("MESH", "1"),
+            ("SOUND", "2"),
+            ("RAW", "3"),
+        ] {
+            expected += &constant(&format!("ASSET_KIND_{kind}"), value);
+        }
+        expected += "\n// player.png\n";
+        for (name, value) in [
+            ("ID", "0"),
+            ("KIND", "0"),
+            ("WIDTH", "16"),
+            ("HEIGHT", "8"),
+            ("HAS_ALPHA", "true"),
+        ] {
+            expected += &constant(&format!("PLAYER_PNG_{name}"), value);
+        }
+        expected += "\n// 1up\n";
+        for (name, value) in [("ID", "1"), ("KIND", "3"), ("LEN", "3")] {
+            expected += &constant(&format!("_1UP_{name}"), value);
+        }
+        expected
+    }
+
+    #[test]
+    fn generates_rust() {
+        let constant = |name: &str, value: &str| {
+            let ty = if value == "true" { "bool" } else { "i32" };
+            format!("pub const {name}: {ty} = {value};\n")
+        };
+        assert_eq!(
+            generate(&assets(), Language::Rust).unwrap(),
+            expected("", constant)
+        );
+    }
+
+    #[test]
+    fn generates_c() {
+        let constant = |name: &str, value: &str| {
+            let value = if value == "true" { "1" } else { value };
+            format!("#define {name} {value}\n")
+        };
+        assert_eq!(
+            generate(&assets(), Language::C).unwrap(),
+            expected("#pragma once\n", constant)
+        );
+    }
+
+    #[test]
+    fn generates_zig() {
+        let constant = |name: &str, value: &str| {
+            let ty = if value == "true" { "bool" } else { "i32" };
+            format!("pub const {name}: {ty} = {value};\n")
+        };
+        assert_eq!(
+            generate(&assets(), Language::Zig).unwrap(),
+            expected("", constant)
+        );
+    }
+
+    #[test]
+    fn rejects_colliding_names() {
+        let mut assets = assets();
+        assets[0].name = String::from("a-b");
+        assets[1].name = String::from("a_b");
+
+        let error = generate(&assets, Language::Rust).unwrap_err();
+        assert_eq!(
+            error,
+            "Assets \"a-b\" and \"a_b\" both generate the constant A_B"
+        );
+    }
+
+    #[test]
+    fn makes_valid_identifiers() {
+        assert_eq!(identifier("player.png"), "PLAYER_PNG");
+        assert_eq!(identifier("1up"), "_1UP");
+        assert_eq!(identifier("_hidden"), "__HIDDEN");
+        assert_eq!(identifier("héro"), "H_RO");
+    }
+}
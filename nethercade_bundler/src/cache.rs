@@ -0,0 +1,67 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+};
+
+use nethercade_core::Asset;
+
+/// Contents of the source files, and a hash of the import settings.
+type Key = (blake3::Hash, u64);
+
+/// Assets imported by earlier builds, keyed by the contents of their source
+/// files and their import settings, so a rebuild only imports what changed.
+#[derive(Default)]
+pub struct AssetCache {
+    entries: HashMap<Key, Vec<Asset>>,
+    /// Entries used by the current build, which replace `entries` once it
+    /// finishes.
+    used: HashMap<Key, Vec<Asset>>,
+    reused: usize,
+}
+
+impl AssetCache {
+    /// Runs `import` unless an earlier build imported the same files, the
+    /// source and any it refers to, with the same settings. Failed imports
+    /// aren't cached.
+    pub fn import(
+        &mut self,
+        paths: &[impl AsRef<Path>],
+        settings: &impl Hash,
+        import: impl FnOnce() -> Result<Vec<Asset>, String>,
+    ) -> Result<Vec<Asset>, String> {
+        let mut contents = blake3::Hasher::new();
+        for path in paths {
+            // Let the importer report unreadable files
+            let Ok(bytes) = std::fs::read(path) else {
+                return import();
+            };
+            contents.update(blake3::hash(&bytes).as_bytes());
+        }
+
+        let mut hasher = DefaultHasher::new();
+        settings.hash(&mut hasher);
+        let key = (contents.finalize(), hasher.finish());
+
+        let cached = self
+            .entries
+            .remove(&key)
+            .or_else(|| self.used.get(&key).cloned());
+        let assets = match cached {
+            Some(assets) => {
+                self.reused += 1;
+                assets
+            }
+            None => import()?,
+        };
+        self.used.insert(key, assets.clone());
+        Ok(assets)
+    }
+
+    /// Forgets entries the finished build didn't use, returning how many
+    /// imports it skipped.
+    pub fn finish_build(&mut self) -> usize {
+        self.entries = std::mem::take(&mut self.used);
+        std::mem::take(&mut self.reused)
+    }
+}
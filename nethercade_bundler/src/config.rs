@@ -1,13 +1,468 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use nethercade_core::{FrameRate, Resolution};
-use serde::Deserialize;
+use nethercade_core::{FrameRate, Pipeline, Resolution};
 
-#[derive(Debug, Deserialize)]
+use crate::{bindings::Language, mesh, sound::Compression, wasm::DebugInfoMode};
+use serde::{Deserialize, Serialize};
+use sonic_rs::{JsonContainerTrait, JsonType, JsonValueTrait};
+
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resolution: Option<Resolution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub frame_rate: Option<FrameRate>,
     pub wasm_path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub output_file: Option<PathBuf>,
+    #[serde(default)]
+    pub wasm: WasmConfig,
+    #[serde(default)]
+    pub metadata: MetadataConfig,
+    #[serde(default)]
+    pub assets: Vec<AssetConfig>,
+    #[serde(default)]
+    pub textures: Vec<TextureConfig>,
+    #[serde(default)]
+    pub meshes: Vec<MeshConfig>,
+    #[serde(default)]
+    pub sounds: Vec<SoundConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningConfig>,
+    /// Source files listing the assets, written next to the ROM.
+    #[serde(default)]
+    pub bindings: Vec<BindingsConfig>,
+}
+
+/// Top level key holding a config's named profiles.
+const PROFILES_KEY: &str = "profiles";
+
+impl Config {
+    /// Reads a TOML or JSON config, by extension, with a profile applied on
+    /// top. The `release` or `debug` profile is used when none is named,
+    /// if the config has it. Then `${NAME}` in strings is replaced with
+    /// environment variables, and relative paths are made relative to the
+    /// config's directory. JSON nulls are treated as missing keys.
+    pub fn read(path: &Path, profile: Option<&str>, release: bool) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {path:?}: {e}"))?;
+        let mut value = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            text.parse::<toml::Table>()
+                .map(toml::Value::Table)
+                .map_err(|e| format!("Failed to parse config {path:?}: {e}"))?
+        } else {
+            let json = sonic_rs::from_str::<sonic_rs::Value>(&text)
+                .map_err(|e| format!("Failed to parse config {path:?}: {e}"))?;
+            json_to_toml(&json).ok_or_else(|| format!("Config {path:?} must hold an object"))?
+        };
+
+        let toml::Value::Table(table) = &mut value else {
+            return Err(format!("Config {path:?} must hold an object"));
+        };
+        let profiles = table.remove(PROFILES_KEY);
+        let name = profile.unwrap_or(if release { "release" } else { "debug" });
+        match profiles.as_ref().and_then(|profiles| profiles.get(name)) {
+            Some(overrides) => merge(&mut value, overrides.clone()),
+            None if profile.is_some() => {
+                let defined = profiles
+                    .as_ref()
+                    .and_then(toml::Value::as_table)
+                    .map(|profiles| profiles.keys().cloned().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                return Err(format!(
+                    "Profile {name:?} isn't defined, the config has [{}]",
+                    defined.join(", ")
+                ));
+            }
+            None => (),
+        }
+        expand_env(&mut value)?;
+
+        let mut config = value
+            .try_into::<Self>()
+            .map_err(|e| format!("Failed to parse config {path:?}: {e}"))?;
+        config.resolve_paths(path.parent().unwrap_or(Path::new("")));
+        Ok(config)
+    }
+
+    /// Joins relative paths onto `base`. The ROM goes next to the config
+    /// unless told otherwise.
+    fn resolve_paths(&mut self, base: &Path) {
+        let resolve = |path: &mut PathBuf| {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        };
+
+        if self.output_file.is_none() {
+            self.output_file = self.wasm_path.file_name().map(PathBuf::from);
+        }
+        resolve(&mut self.wasm_path);
+        self.output_file.iter_mut().for_each(resolve);
+        self.metadata.icon_path.iter_mut().for_each(resolve);
+        self.metadata.cover_path.iter_mut().for_each(resolve);
+        self.assets
+            .iter_mut()
+            .for_each(|asset| resolve(&mut asset.path));
+        self.textures
+            .iter_mut()
+            .for_each(|texture| resolve(&mut texture.path));
+        self.meshes
+            .iter_mut()
+            .for_each(|mesh| resolve(&mut mesh.path));
+        self.sounds
+            .iter_mut()
+            .for_each(|sound| resolve(&mut sound.path));
+        self.signing
+            .iter_mut()
+            .for_each(|signing| resolve(&mut signing.key_path));
+        self.bindings
+            .iter_mut()
+            .for_each(|bindings| resolve(&mut bindings.path));
+    }
+
+    /// Every file the build reads, besides the config itself.
+    pub fn source_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.wasm_path.clone()];
+        paths.extend(self.metadata.icon_path.clone());
+        paths.extend(self.metadata.cover_path.clone());
+        paths.extend(self.assets.iter().map(|asset| asset.path.clone()));
+        paths.extend(self.textures.iter().map(|texture| texture.path.clone()));
+        paths.extend(self.meshes.iter().flat_map(MeshConfig::source_paths));
+        paths.extend(self.sounds.iter().map(|sound| sound.path.clone()));
+        paths
+    }
+}
+
+/// Overrides `base` with a profile, merging tables key by key and replacing
+/// anything else, arrays included.
+fn merge(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Converts parsed JSON, leaving out nulls, which TOML has no value for.
+fn json_to_toml(value: &sonic_rs::Value) -> Option<toml::Value> {
+    Some(match value.get_type() {
+        JsonType::Null => return None,
+        JsonType::Boolean => toml::Value::Boolean(value.as_bool()?),
+        JsonType::Number => match value.as_i64() {
+            Some(integer) => toml::Value::Integer(integer),
+            None => toml::Value::Float(value.as_f64()?),
+        },
+        JsonType::String => toml::Value::String(value.as_str()?.to_owned()),
+        JsonType::Array => {
+            toml::Value::Array(value.as_array()?.iter().filter_map(json_to_toml).collect())
+        }
+        JsonType::Object => toml::Value::Table(
+            value
+                .as_object()?
+                .iter()
+                .filter_map(|(key, value)| Some((key.to_owned(), json_to_toml(value)?)))
+                .collect(),
+        ),
+    })
+}
+
+fn expand_env(value: &mut toml::Value) -> Result<(), String> {
+    match value {
+        toml::Value::String(text) => *text = expand_env_vars(text)?,
+        toml::Value::Array(values) => values.iter_mut().try_for_each(expand_env)?,
+        toml::Value::Table(table) => table
+            .iter_mut()
+            .try_for_each(|(_, value)| expand_env(value))?,
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Replaces every `${NAME}` with the environment variable `NAME`, failing if
+/// it isn't set. `$${` is kept as a literal `${`.
+fn expand_env_vars(text: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if let Some(before) = rest[..start].strip_suffix('$') {
+            expanded.push_str(before);
+            expanded.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unclosed ${{ in {text:?}"))?;
+        let name = &after[..end];
+        let value = std::env::var(name)
+            .map_err(|e| format!("Environment variable {name} in {text:?}: {e}"))?;
+        expanded.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Post-processing applied to the wasm module before it's bundled.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmConfig {
+    /// Removes custom sections which aren't debug info, like `producers`.
+    /// Defaults to on for release builds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip: Option<bool>,
+    /// Stubs out functions nothing can call. Drops DWARF, which wouldn't
+    /// match the new code.
+    #[serde(default)]
+    pub optimize: bool,
+    /// What happens to the name section and DWARF. Defaults to `keep`, or
+    /// `strip` for release builds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<DebugInfoMode>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataConfig {
+    /// Defaults to the name of the wasm file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Semantic version, like "1.0.0". Defaults to "0.1.0".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_players: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_players: Option<u8>,
+    #[serde(default)]
+    pub netplay: bool,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    /// Square image shown in game lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_path: Option<PathBuf>,
+    /// Wide image shown when a game is selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_path: Option<PathBuf>,
+}
+
+/// A file bundled into the ROM as is, which the game looks up by `name`.
+#[derive(Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetConfig {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// An image imported as a texture asset.
+#[derive(Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextureConfig {
+    pub name: String,
+    pub path: PathBuf,
+    /// Store RGBA instead of RGB. Defaults to whether the image has alpha.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<bool>,
+    /// Scale up to the next power of two in each dimension.
+    #[serde(default)]
+    pub power_of_two: bool,
+    /// Reduce every channel to this many bits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantize_bits: Option<u8>,
+}
+
+/// A glTF or OBJ model imported as one or more mesh assets.
+#[derive(Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshConfig {
+    pub name: String,
+    pub path: PathBuf,
+    /// Defaults to the pipeline matching the model's attributes. Any but
+    /// `quad2d`, which the console only uses for 2D drawing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<Pipeline>,
+    /// Replace the model's normals with smooth ones.
+    #[serde(default)]
+    pub compute_normals: bool,
+}
+
+impl MeshConfig {
+    /// The model and the buffer files it refers to.
+    pub fn source_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.path.clone()];
+        paths.extend(mesh::dependencies(&self.path));
+        paths
+    }
+}
+
+/// A WAV, OGG or FLAC file imported as a sound asset.
+#[derive(Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoundConfig {
+    pub name: String,
+    pub path: PathBuf,
+    /// 1 or 2. Defaults to the file's channels, downmixed to stereo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    /// Loop start in frames of the file. Read from the file when neither
+    /// loop point is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loop_start: Option<u32>,
+    /// Exclusive loop end in frames of the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loop_end: Option<u32>,
+}
+
+/// Signs the ROM so consoles trusting the key will run it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningConfig {
+    /// Secret key written by `bundle keygen`.
+    pub key_path: PathBuf,
+    /// Shown by consoles which don't have a name for the key.
+    pub signer: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingsConfig {
+    pub language: Language,
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `text` to a config called `name` in a directory of its own,
+    /// returning the config's path.
+    fn write_config(name: &str, text: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nethercade_config_{}_{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn merges_tables_and_replaces_arrays() {
+        let mut base = toml::toml! {
+            wasm = { strip = false, optimize = false }
+            genres = ["puzzle", "arcade"]
+        }
+        .into();
+        let overrides = toml::toml! {
+            wasm = { strip = true }
+            genres = ["racing"]
+        }
+        .into();
+        merge(&mut base, overrides);
+
+        let expected: toml::Value = toml::toml! {
+            wasm = { strip = true, optimize = false }
+            genres = ["racing"]
+        }
+        .into();
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn applies_profiles() {
+        let path = write_config(
+            "profiles.toml",
+            r#"
+            wasmPath = "game.wasm"
+            [metadata]
+            title = "Game"
+            [profiles.release.wasm]
+            strip = true
+            [profiles.demo.metadata]
+            title = "Demo"
+            "#,
+        );
+
+        let config = Config::read(&path, None, true).unwrap();
+        assert_eq!(config.wasm.strip, Some(true));
+        assert_eq!(config.metadata.title.as_deref(), Some("Game"));
+
+        let config = Config::read(&path, Some("demo"), true).unwrap();
+        assert_eq!(config.wasm.strip, None);
+        assert_eq!(config.metadata.title.as_deref(), Some("Demo"));
+
+        let error = Config::read(&path, Some("full"), false).unwrap_err();
+        assert_eq!(
+            error,
+            "Profile \"full\" isn't defined, the config has [demo, release]"
+        );
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        // Cargo sets this for the tests it runs
+        let name = std::env::var("CARGO_PKG_NAME").unwrap();
+        assert_eq!(
+            expand_env_vars("${CARGO_PKG_NAME}/out").unwrap(),
+            format!("{name}/out")
+        );
+        assert_eq!(
+            expand_env_vars("a ${CARGO_PKG_NAME} b $${CARGO_PKG_NAME}").unwrap(),
+            format!("a {name} b ${{CARGO_PKG_NAME}}")
+        );
+        assert_eq!(expand_env_vars("$ {} $").unwrap(), "$ {} $");
+
+        let error = expand_env_vars("${CARGO_PKG_NAME").unwrap_err();
+        assert!(error.starts_with("Unclosed ${"), "{error}");
+        let error = expand_env_vars("${NETHERCADE_UNSET_VARIABLE}").unwrap_err();
+        assert!(
+            error.starts_with("Environment variable NETHERCADE_UNSET_VARIABLE"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn resolves_relative_paths() {
+        let absolute = std::env::temp_dir().join("icon.png");
+        let path = write_config(
+            "paths.json",
+            &format!(
+                r#"{{
+                    "wasmPath": "build/game.wasm",
+                    "outputFile": null,
+                    "metadata": {{ "title": "Game", "iconPath": {:?} }},
+                    "textures": [{{ "name": "player", "path": "art/player.png" }}]
+                }}"#,
+                absolute.to_str().unwrap()
+            ),
+        );
+        let dir = path.parent().unwrap();
+
+        let config = Config::read(&path, None, false).unwrap();
+        assert_eq!(config.wasm_path, dir.join("build/game.wasm"));
+        assert_eq!(config.output_file, Some(dir.join("game.wasm")));
+        assert_eq!(config.metadata.icon_path, Some(absolute));
+        assert_eq!(config.textures[0].path, dir.join("art/player.png"));
+    }
 }
@@ -0,0 +1,235 @@
+use std::{path::Path, process::ExitCode};
+
+use nethercade_core::{AssetData, Pipeline, Rom, RomContainer, RomImage, SoundSamples, key_to_hex};
+use wasmparser::{ExternalKind, TypeRef};
+
+use crate::validate::{self, format_size};
+
+/// Prints what a `.nzrom` holds: its container header and sections, the
+/// settings and metadata, assets, and the imports and exports of its code.
+pub fn run(path: &Path) -> ExitCode {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            println!("Failed to read {path:?}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("{path:?}, {}", format_size(bytes.len() as u64));
+
+    let rom = if RomContainer::is_container(&bytes) {
+        match print_container(&bytes) {
+            Some(rom) => rom,
+            None => return ExitCode::FAILURE,
+        }
+    } else {
+        println!("Format:      legacy, from before the container format");
+        match Rom::decode(&bytes) {
+            Ok(rom) => rom,
+            Err(e) => {
+                println!("Failed to load ROM: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+
+    print_rom(&rom);
+    print_assets(&rom);
+    print_code(&rom);
+    ExitCode::SUCCESS
+}
+
+/// Prints the header and section table, then loads the ROM. The table is
+/// printed even when the sections don't load, to help find out why.
+fn print_container(bytes: &[u8]) -> Option<Rom> {
+    let table = match RomContainer::read_table(bytes) {
+        Ok(table) => table,
+        Err(e) => {
+            println!("Failed to read container: {e}");
+            return None;
+        }
+    };
+    println!(
+        "Format:      container version {}, readable from version {}",
+        table.version, table.min_reader_version
+    );
+
+    let container = RomContainer::decode(bytes);
+    println!("Sections:");
+    println!(
+        "  {:<12} {:>10} {:>10} {:>10}  checksum",
+        "kind", "offset", "stored", "size"
+    );
+    for entry in &table.entries {
+        let size = match &container {
+            Ok(container) => container
+                .section(entry.kind)
+                .map_or_else(String::new, |data| format_size(data.len() as u64)),
+            Err(_) => String::from("?"),
+        };
+        println!(
+            "  {:<12} {:>10} {:>10} {:>10}  {}{}",
+            format!("{:?}", entry.kind),
+            entry.offset,
+            format_size(entry.stored_length),
+            size,
+            entry.checksum.to_hex(),
+            if entry.compressed { " (zstd)" } else { "" }
+        );
+    }
+
+    let rom = container.and_then(|container| Rom::from_container(&container));
+    match rom {
+        Ok(rom) => Some(rom),
+        Err(e) => {
+            println!("Failed to load ROM: {e}");
+            None
+        }
+    }
+}
+
+fn print_rom(rom: &Rom) {
+    let metadata = &rom.metadata;
+    let (width, height) = rom.resolution.dimensions();
+    let image = |image: &Option<RomImage>| match image {
+        Some(image) => format!("{}x{}", image.width, image.height),
+        None => String::from("none"),
+    };
+
+    println!("Title:       {}", metadata.title);
+    println!("Author:      {}", metadata.author);
+    println!("Version:     {}", metadata.version);
+    println!("Description: {}", metadata.description);
+    println!(
+        "Players:     {}{}",
+        metadata.players_label(),
+        if metadata.netplay { ", netplay" } else { "" }
+    );
+    println!("Genres:      {}", metadata.genres.join(", "));
+    println!("Icon:        {}", image(&metadata.icon));
+    println!("Cover:       {}", image(&metadata.cover));
+    println!("Resolution:  {:?} ({width}x{height})", rom.resolution);
+    println!(
+        "Frame rate:  {} ({} fps)",
+        rom.frame_rate.as_str(),
+        rom.frame_rate.frames_per_second()
+    );
+    println!("Code hash:   {}", rom.hash());
+    match &rom.signature {
+        Some(signature) => println!(
+            "Signed by:   {} with key {}",
+            signature.signer,
+            key_to_hex(&signature.public_key)
+        ),
+        None => println!("Signed by:   unsigned"),
+    }
+    if let Some(debug_info) = &rom.debug_info {
+        let names = debug_info
+            .sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect::<Vec<_>>();
+        println!("Debug info:  {}", names.join(", "));
+    }
+}
+
+fn print_assets(rom: &Rom) {
+    println!("Assets:");
+    for (id, asset) in rom.assets.iter().enumerate() {
+        println!(
+            "  {id:>4} {:<24} {:<8} {}",
+            asset.name,
+            asset.data.kind_name(),
+            describe(&asset.data)
+        );
+    }
+}
+
+fn describe(data: &AssetData) -> String {
+    match data {
+        AssetData::Texture {
+            width,
+            height,
+            has_alpha,
+            ..
+        } => format!(
+            "{width}x{height} {}",
+            if *has_alpha { "rgba" } else { "rgb" }
+        ),
+        AssetData::Mesh {
+            pipeline,
+            vertices,
+            indices,
+        } => {
+            let pipeline = Pipeline::from_id(*pipeline);
+            let vertices = match pipeline {
+                Some(pipeline) => {
+                    format!("{} vertices", vertices.len() / pipeline.floats_per_vertex())
+                }
+                None => format!("{} floats", vertices.len()),
+            };
+            let indices = match indices {
+                Some(indices) => format!(", {} indices", indices.len()),
+                None => String::new(),
+            };
+            match pipeline {
+                Some(pipeline) => format!("{pipeline:?}, {vertices}{indices}"),
+                None => format!("unknown pipeline, {vertices}{indices}"),
+            }
+        }
+        AssetData::Sound {
+            channels,
+            sample_rate,
+            samples,
+            loop_points,
+        } => {
+            let frames = samples.len() / (*channels).max(1) as usize;
+            let encoding = match samples {
+                SoundSamples::Pcm(_) => "pcm",
+                SoundSamples::Adpcm { .. } => "adpcm",
+            };
+            let looping = match loop_points {
+                Some((start, end)) => format!(", loops {start}..{end}"),
+                None => String::new(),
+            };
+            format!(
+                "{channels} channels, {:.2} s, {encoding}{looping}",
+                frames as f64 / *sample_rate as f64
+            )
+        }
+        AssetData::Raw(bytes) => format_size(bytes.len() as u64),
+    }
+}
+
+fn print_code(rom: &Rom) {
+    let info = match validate::parse(&rom.code) {
+        Ok(info) => info,
+        Err(e) => {
+            println!("Failed to parse code: {e}");
+            return;
+        }
+    };
+
+    println!("Imports:");
+    for (module, name, ty) in &info.imports {
+        let ty = match ty {
+            TypeRef::Func(index) => info
+                .types
+                .get(*index as usize)
+                .map_or_else(String::new, validate::func_signature),
+            ty => String::from(validate::type_ref_name(ty)),
+        };
+        println!("  {module}.{name} {ty}");
+    }
+
+    println!("Exports:");
+    for (name, kind, index) in &info.exports {
+        let ty = match kind {
+            ExternalKind::Func => info
+                .function_type(*index)
+                .map_or_else(String::new, validate::func_signature),
+            kind => format!("{kind:?}").to_lowercase(),
+        };
+        println!("  {name} {ty}");
+    }
+}
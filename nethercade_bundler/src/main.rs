@@ -1,56 +1,196 @@
+mod bindings;
+mod cache;
 mod config;
+mod inspect;
+mod mesh;
+mod signing;
+mod sound;
+mod texture;
+mod unpack;
+mod validate;
+mod wasm;
+mod watch;
 
 use std::{
-    fs::File,
-    io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
 
-use clap::Parser;
-use config::Config;
-use nethercade_core::{ROM_FILE_EXTENSION, Rom};
+use cache::AssetCache;
+use clap::{Parser, Subcommand};
+use config::{Config, MetadataConfig};
+use nethercade_core::{
+    Asset, AssetData, DEBUG_INFO_FILE_EXTENSION, DebugInfo, HOT_RELOAD_PORT, ROM_FILE_EXTENSION,
+    Rom, RomImage, RomMetadata, RomVersion, console_spec::MAX_ASSETS_SIZE,
+};
+use wasm::DebugInfoMode;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
     /// Path to the bundler configuration
+    #[arg(short, long, required = true)]
+    bundler_config_path: Option<String>,
+
+    /// Strip custom sections and debug info unless the config keeps them
+    #[arg(short, long)]
+    release: bool,
+
+    /// Config profile applied on top of the rest of the config, defaults to
+    /// release or debug when the config has one
+    #[arg(short, long)]
+    profile: Option<String>,
+
+    /// Rebuild whenever the wasm, the config or an asset changes
     #[arg(short, long)]
-    bundler_config_path: String,
+    watch: bool,
+
+    /// Ask a console listening on this port, or the default one, to reload
+    /// the ROM after each rebuild
+    #[arg(long, requires = "watch", value_name = "PORT")]
+    notify: Option<Option<u16>>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Checks a .wasm or .nzrom against what the console supports, failing if
+    /// it wouldn't run
+    Validate {
+        /// The .wasm or .nzrom to check
+        path: PathBuf,
+    },
+    /// Prints the header, sections, metadata, assets, imports and exports of
+    /// a .nzrom
+    Inspect {
+        /// The .nzrom to inspect
+        path: PathBuf,
+    },
+    /// Extracts the code and assets of a .nzrom into files, along with a
+    /// config bundling them back
+    Unpack {
+        /// The .nzrom to extract
+        path: PathBuf,
+
+        /// Directory to extract into, defaults to the ROM's path without its
+        /// extension
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Creates an ed25519 key pair for signing ROMs, the public key next to
+    /// the secret one with a .pub extension
+    Keygen {
+        /// Where to write the secret key
+        path: PathBuf,
+    },
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    let bundle = match File::open(args.bundler_config_path) {
-        Ok(mut file) => {
-            let mut buf = Vec::new();
-            file.read_to_end(&mut buf).unwrap();
-            match sonic_rs::from_slice::<Config>(&buf) {
-                Ok(bundle) => bundle,
-                Err(e) => {
-                    println!("Failed to parse rom settings file: {e:?}");
-                    return;
+    match args.command {
+        Some(Command::Validate { path }) => validate::run(&path),
+        Some(Command::Inspect { path }) => inspect::run(&path),
+        Some(Command::Unpack { path, output_dir }) => unpack::run(&path, output_dir.as_deref()),
+        Some(Command::Keygen { path }) => signing::keygen(&path),
+        None => {
+            let config_path = PathBuf::from(args.bundler_config_path.unwrap());
+            let options = BuildOptions {
+                profile: args.profile,
+                release: args.release,
+            };
+            if args.watch {
+                let notify_port = args.notify.map(|port| port.unwrap_or(HOT_RELOAD_PORT));
+                watch::run(&config_path, &options, notify_port)
+            } else {
+                match bundle(&config_path, &options, &mut AssetCache::default()) {
+                    Some(_) => ExitCode::SUCCESS,
+                    None => ExitCode::FAILURE,
                 }
             }
         }
+    }
+}
+
+/// How to read the config and build from it, as given on the command line.
+pub struct BuildOptions {
+    pub profile: Option<String>,
+    pub release: bool,
+}
+
+impl BuildOptions {
+    fn read_config(&self, config_path: &Path) -> Result<Config, String> {
+        Config::read(config_path, self.profile.as_deref(), self.release)
+    }
+}
+
+fn load_config(config_path: &Path, options: &BuildOptions) -> Option<Config> {
+    match options.read_config(config_path) {
+        Ok(config) => Some(config),
         Err(e) => {
-            println!("Failed to load rom_settings_path{e:?}");
-            return;
+            println!("{e}");
+            None
         }
-    };
+    }
+}
+
+/// Builds the ROM described by the config, returning where it was written.
+fn bundle(config_path: &Path, options: &BuildOptions, cache: &mut AssetCache) -> Option<PathBuf> {
+    let bundle = load_config(config_path, options)?;
 
-    let code = match File::open(&bundle.wasm_path) {
-        Ok(mut code) => {
-            let mut buf = Vec::new();
-            code.read_to_end(&mut buf).unwrap();
-            buf.into_boxed_slice()
+    let code = match std::fs::read(&bundle.wasm_path) {
+        Ok(code) => code,
+        Err(e) => {
+            println!("Failed to read {:?}: {e}", bundle.wasm_path);
+            return None;
         }
+    };
+
+    let processed = match wasm::process(&code, &bundle.wasm, options.release) {
+        Ok(processed) => processed,
         Err(e) => {
-            println!("Failed to load rom_settings_path{e:?}");
-            return;
+            println!("Failed to process {:?}: {e}", bundle.wasm_path);
+            return None;
+        }
+    };
+    println!("Code sizes in bytes:");
+    wasm::print_size_report(&code, &processed.code);
+
+    let metadata = match build_metadata(&bundle.metadata, &bundle.wasm_path) {
+        Ok(metadata) => metadata,
+        Err(errors) => {
+            println!("Invalid metadata:");
+            for error in errors {
+                println!("  {error}");
+            }
+            return None;
         }
     };
 
+    let assets = match load_assets(&bundle, cache) {
+        Ok(assets) => assets,
+        Err(errors) => {
+            println!("Invalid assets:");
+            for error in errors {
+                println!("  {error}");
+            }
+            return None;
+        }
+    };
+
+    let mut bindings = Vec::new();
+    for config in &bundle.bindings {
+        match bindings::generate(&assets, config.language) {
+            Ok(source) => bindings.push((&config.path, source)),
+            Err(e) => {
+                println!("Failed to generate bindings: {e}");
+                return None;
+            }
+        }
+    }
+
     let output_path = match bundle.output_file {
         Some(output_file) => output_file.with_extension(ROM_FILE_EXTENSION),
         None => PathBuf::new()
@@ -58,27 +198,226 @@ fn main() {
             .with_extension(ROM_FILE_EXTENSION),
     };
 
-    let mut out_file = match File::options()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&output_path)
-    {
-        Ok(file) => file,
-        Err(e) => {
-            println!("Failed to open output file: {e:?}");
-            return;
-        }
-    };
-
-    let rom = Rom {
-        code,
+    let mut rom = Rom {
+        code: processed.code.into_boxed_slice(),
         resolution: bundle.resolution.unwrap_or_default(),
         frame_rate: bundle.frame_rate.unwrap_or_default(),
+        metadata,
+        assets: assets.into(),
+        debug_info: None,
+        signature: None,
     };
 
-    let bytes = bitcode::encode(&rom);
-    let bytes = zstd::bulk::compress(&bytes, 0).unwrap();
-    out_file.write_all(&bytes).unwrap();
+    let assets_size = rom.assets_size();
+    if assets_size > MAX_ASSETS_SIZE {
+        println!(
+            "Assets take {}, {} over the {} budget.",
+            validate::format_size(assets_size as u64),
+            validate::format_size((assets_size - MAX_ASSETS_SIZE) as u64),
+            validate::format_size(MAX_ASSETS_SIZE as u64)
+        );
+        return None;
+    }
+
+    let debug_info = (!processed.debug_sections.is_empty()).then(|| DebugInfo {
+        code_hash: rom.hash(),
+        sections: processed.debug_sections,
+    });
+    let sidecar = match processed.debug_info {
+        DebugInfoMode::Section => {
+            rom.debug_info = debug_info;
+            None
+        }
+        DebugInfoMode::Sidecar => debug_info,
+        DebugInfoMode::Keep | DebugInfoMode::Strip => None,
+    };
+
+    let mut container = rom.to_container();
+    if let Some(signing) = &bundle.signing {
+        match signing::load_key(&signing.key_path) {
+            Ok(key) => container.sign(&key, &signing.signer),
+            Err(e) => {
+                println!("Failed to sign: {e}");
+                return None;
+            }
+        }
+    }
+
+    if let Err(e) = write_atomically(&output_path, &container.encode()) {
+        println!("Failed to write output file: {e:?}");
+        return None;
+    }
     println!("Output file {output_path:?} successfully.");
+
+    if let Some(debug_info) = sidecar {
+        let path = output_path.with_extension(DEBUG_INFO_FILE_EXTENSION);
+        match write_atomically(&path, &debug_info.encode()) {
+            Ok(()) => println!("Wrote debug info {path:?}."),
+            Err(e) => println!("Failed to write debug info {path:?}: {e:?}"),
+        }
+    }
+
+    for (path, source) in bindings {
+        match std::fs::write(path, source) {
+            Ok(()) => println!("Wrote bindings {path:?}."),
+            Err(e) => println!("Failed to write bindings {path:?}: {e:?}"),
+        }
+    }
+
+    Some(output_path)
+}
+
+/// Writes through a temporary file, so anything watching `path` never sees
+/// it half written.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, bytes)?;
+    std::fs::rename(&temp, path)
+}
+
+/// Loads the images named by the config and checks everything against the
+/// console's limits.
+fn build_metadata(config: &MetadataConfig, wasm_path: &Path) -> Result<RomMetadata, Vec<String>> {
+    let mut errors = Vec::new();
+
+    let title = match &config.title {
+        Some(title) => title.clone(),
+        None => {
+            let title = wasm_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            println!("Warning: the config has no metadata title, using {title:?}.");
+            title
+        }
+    };
+
+    let version = match &config.version {
+        Some(version) => version.parse().unwrap_or_else(|e| {
+            errors.push(e);
+            RomVersion::default()
+        }),
+        None => RomVersion {
+            major: 0,
+            minor: 1,
+            patch: 0,
+        },
+    };
+
+    let mut load = |name: &str, path: &Option<PathBuf>| {
+        path.as_deref().and_then(|path| match load_image(path) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                errors.push(format!("Failed to load {name} {path:?}: {e}"));
+                None
+            }
+        })
+    };
+    let icon = load("icon", &config.icon_path);
+    let cover = load("cover", &config.cover_path);
+
+    let metadata = RomMetadata {
+        title,
+        author: config.author.clone().unwrap_or_default(),
+        version,
+        description: config.description.clone().unwrap_or_default(),
+        min_players: config.min_players.unwrap_or(1),
+        max_players: config
+            .max_players
+            .unwrap_or(config.min_players.unwrap_or(1)),
+        netplay: config.netplay,
+        genres: config.genres.clone(),
+        icon,
+        cover,
+    };
+
+    if let Err(mut invalid) = metadata.validate() {
+        errors.append(&mut invalid);
+    }
+
+    if errors.is_empty() {
+        Ok(metadata)
+    } else {
+        Err(errors)
+    }
+}
+
+fn load_assets(config: &Config, cache: &mut AssetCache) -> Result<Vec<Asset>, Vec<String>> {
+    let mut imports = Vec::new();
+    for asset in &config.assets {
+        let result = cache.import(&[&asset.path], asset, || {
+            std::fs::read(&asset.path)
+                .map(|bytes| {
+                    vec![Asset {
+                        name: asset.name.clone(),
+                        data: AssetData::Raw(bytes),
+                    }]
+                })
+                .map_err(|e| format!("Asset {:?}: {e}", asset.path))
+        });
+        imports.push((&asset.path, result));
+    }
+    for texture in &config.textures {
+        let result = cache.import(&[&texture.path], texture, || {
+            texture::import(texture).map(|asset| vec![asset])
+        });
+        imports.push((&texture.path, result));
+    }
+    for mesh in &config.meshes {
+        let result = cache.import(&mesh.source_paths(), mesh, || mesh::import(mesh));
+        imports.push((&mesh.path, result));
+    }
+    for sound in &config.sounds {
+        let result = cache.import(&[&sound.path], sound, || {
+            sound::import(sound).map(|asset| vec![asset])
+        });
+        imports.push((&sound.path, result));
+    }
+
+    let reused = cache.finish_build();
+    if reused > 0 {
+        println!("Reused {reused} unchanged asset imports.");
+    }
+
+    let mut errors = Vec::new();
+    let mut assets = Vec::<Asset>::new();
+    for (path, result) in imports {
+        let imported = match result {
+            Ok(imported) => imported,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        for asset in imported {
+            if asset.name.is_empty() {
+                errors.push(format!("Asset {path:?} has an empty name"));
+            } else if assets.iter().any(|other| other.name == asset.name) {
+                errors.push(format!(
+                    "Asset {path:?}: name {:?} is used more than once",
+                    asset.name
+                ));
+            } else {
+                assets.push(asset);
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(assets)
+    } else {
+        Err(errors)
+    }
+}
+
+fn load_image(path: &Path) -> Result<RomImage, image::ImageError> {
+    let image = image::open(path)?.into_rgba8();
+    Ok(RomImage {
+        width: image.width(),
+        height: image.height(),
+        rgba: image.into_raw(),
+    })
 }
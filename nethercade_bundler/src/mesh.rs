@@ -0,0 +1,509 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt::Write,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use nethercade_core::{Asset, AssetData, Pipeline};
+
+use crate::config::MeshConfig;
+
+/// Most vertices a single `u16` indexed mesh can reference.
+const MAX_CHUNK_VERTICES: usize = u16::MAX as usize + 1;
+
+const DEFAULT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+/// Triangles merged from every mesh in a file. Attributes missing from some
+/// parts are filled with defaults.
+#[derive(Default)]
+struct Geometry {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+    has_colors: bool,
+    has_uvs: bool,
+    /// Whether every part came with normals.
+    complete_normals: bool,
+}
+
+/// A part of a model, as read from the file.
+struct Part {
+    positions: Vec<[f32; 3]>,
+    colors: Option<Vec<[f32; 3]>>,
+    uvs: Option<Vec<[f32; 2]>>,
+    normals: Option<Vec<[f32; 3]>>,
+    indices: Vec<u32>,
+}
+
+impl Geometry {
+    /// Fails if the part's attributes or indices don't match its positions.
+    fn add(&mut self, part: Part) -> Result<(), String> {
+        let offset = self.positions.len() as u32;
+        let count = part.positions.len();
+
+        let lengths = [
+            ("colors", part.colors.as_ref().map(Vec::len)),
+            ("texture coordinates", part.uvs.as_ref().map(Vec::len)),
+            ("normals", part.normals.as_ref().map(Vec::len)),
+        ];
+        for (attribute, length) in lengths {
+            if let Some(length) = length.filter(|&length| length != count) {
+                return Err(format!("has {length} {attribute} for {count} vertices"));
+            }
+        }
+        if let Some(index) = part.indices.iter().find(|&&index| index as usize >= count) {
+            return Err(format!("index {index} is past its {count} vertices"));
+        }
+
+        self.has_colors |= part.colors.is_some();
+        self.has_uvs |= part.uvs.is_some();
+        self.complete_normals &= part.normals.is_some();
+
+        self.positions.extend(part.positions);
+        self.colors
+            .extend(part.colors.unwrap_or_else(|| vec![DEFAULT_COLOR; count]));
+        self.uvs
+            .extend(part.uvs.unwrap_or_else(|| vec![[0.0; 2]; count]));
+        self.normals
+            .extend(part.normals.unwrap_or_else(|| vec![[0.0; 3]; count]));
+        self.indices
+            .extend(part.indices.into_iter().map(|index| index + offset));
+        Ok(())
+    }
+
+    /// Smooth normals, averaged from the faces around each vertex.
+    fn compute_normals(&mut self) {
+        let mut normals = vec![[0.0f32; 3]; self.positions.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.positions[triangle[i] as usize]);
+            let ab = sub(b, a);
+            let ac = sub(c, a);
+            let face = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            for &index in triangle {
+                let normal = &mut normals[index as usize];
+                for axis in 0..3 {
+                    normal[axis] += face[axis];
+                }
+            }
+        }
+
+        for normal in normals.iter_mut() {
+            let length = normal.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if length > 0.0 {
+                normal.iter_mut().for_each(|v| *v /= length);
+            }
+        }
+
+        self.normals = normals;
+    }
+
+    fn write_vertex(&self, index: usize, pipeline: Pipeline, out: &mut Vec<f32>) {
+        out.extend_from_slice(&self.positions[index]);
+        if pipeline.has_color() {
+            out.extend_from_slice(&self.colors[index]);
+        }
+        if pipeline.has_uv() {
+            out.extend_from_slice(&self.uvs[index]);
+        }
+        if pipeline.has_normals() {
+            out.extend_from_slice(&self.normals[index]);
+        }
+    }
+
+    /// Splits into `u16` indexed meshes of at most [`MAX_CHUNK_VERTICES`]
+    /// vertices, never splitting a triangle.
+    fn into_chunks(self, pipeline: Pipeline) -> Vec<(Vec<f32>, Vec<u16>)> {
+        let mut chunks = Vec::new();
+        let mut remap = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for triangle in self.indices.chunks_exact(3) {
+            let new_vertices = triangle
+                .iter()
+                .filter(|index| !remap.contains_key(*index))
+                .count();
+            if remap.len() + new_vertices > MAX_CHUNK_VERTICES {
+                chunks.push((std::mem::take(&mut vertices), std::mem::take(&mut indices)));
+                remap.clear();
+            }
+
+            for &index in triangle {
+                let next = remap.len() as u16;
+                let local = *remap.entry(index).or_insert_with(|| {
+                    self.write_vertex(index as usize, pipeline, &mut vertices);
+                    next
+                });
+                indices.push(local);
+            }
+        }
+
+        if !indices.is_empty() {
+            chunks.push((vertices, indices));
+        }
+        chunks
+    }
+}
+
+/// Imports a glTF or OBJ model as one mesh asset, or several named
+/// `name.0`, `name.1`, ... if it has too many vertices for `u16` indices.
+pub fn import(config: &MeshConfig) -> Result<Vec<Asset>, String> {
+    let path = &config.path;
+    if config.pipeline == Some(Pipeline::Quad2d) {
+        return Err(format!(
+            "Mesh {path:?}: quad2d is only for the console's 2D drawing, pick another pipeline"
+        ));
+    }
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    let parts = match extension.as_deref() {
+        Some("gltf" | "glb") => read_gltf(path),
+        Some("obj") => read_obj(path),
+        _ => Err(String::from(
+            "unsupported format, expected one of gltf, glb or obj",
+        )),
+    }
+    .map_err(|e| format!("Mesh {path:?}: {e}"))?;
+
+    let mut geometry = Geometry {
+        complete_normals: true,
+        ..Default::default()
+    };
+    for part in parts {
+        geometry
+            .add(part)
+            .map_err(|e| format!("Mesh {path:?}: {e}"))?;
+    }
+    if geometry.indices.is_empty() {
+        return Err(format!("Mesh {path:?}: contains no triangles"));
+    }
+
+    let pipeline = config.pipeline.unwrap_or_else(|| {
+        Pipeline::from_attributes(
+            geometry.has_colors,
+            geometry.has_uvs,
+            geometry.complete_normals || config.compute_normals,
+        )
+    });
+    if config.compute_normals || (pipeline.has_normals() && !geometry.complete_normals) {
+        geometry.compute_normals();
+    }
+
+    let chunks = geometry.into_chunks(pipeline);
+    let split = chunks.len() > 1;
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, (vertices, indices))| Asset {
+            name: if split {
+                format!("{}.{index}", config.name)
+            } else {
+                config.name.clone()
+            },
+            data: AssetData::Mesh {
+                pipeline: pipeline.id(),
+                vertices,
+                indices: Some(indices),
+            },
+        })
+        .collect())
+}
+
+/// Writes a mesh asset as OBJ, with vertex colors following positions as
+/// many tools expect. Every vertex gets its own position, texture coordinate
+/// and normal, so importing the file gives back the same vertices.
+pub fn export(
+    path: &Path,
+    pipeline: Pipeline,
+    vertices: &[f32],
+    indices: Option<&[u16]>,
+) -> Result<(), String> {
+    let stride = pipeline.floats_per_vertex();
+    if !vertices.len().is_multiple_of(stride) {
+        return Err(format!(
+            "Mesh {path:?}: {} floats don't fit {pipeline:?} vertices of {stride}",
+            vertices.len()
+        ));
+    }
+
+    let mut obj = String::new();
+    for vertex in vertices.chunks_exact(stride) {
+        let (position, mut rest) = vertex.split_at(3);
+        write!(obj, "v {} {} {}", position[0], position[1], position[2]).unwrap();
+        if pipeline.has_color() {
+            let (color, remaining) = rest.split_at(3);
+            write!(obj, " {} {} {}", color[0], color[1], color[2]).unwrap();
+            rest = remaining;
+        }
+        obj.push('\n');
+        if pipeline.has_uv() {
+            let (uv, remaining) = rest.split_at(2);
+            writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1]).unwrap();
+            rest = remaining;
+        }
+        if pipeline.has_normals() {
+            writeln!(obj, "vn {} {} {}", rest[0], rest[1], rest[2]).unwrap();
+        }
+    }
+
+    let count = vertices.len() / stride;
+    let indices = match indices {
+        Some(indices) => indices.iter().map(|&index| index as usize).collect(),
+        None => (0..count).collect::<Vec<_>>(),
+    };
+    for triangle in indices.chunks_exact(3) {
+        obj.push('f');
+        for &index in triangle {
+            if index >= count {
+                return Err(format!(
+                    "Mesh {path:?}: index {index} is past its {count} vertices"
+                ));
+            }
+            // OBJ indices start at 1
+            let index = index + 1;
+            match (pipeline.has_uv(), pipeline.has_normals()) {
+                (true, true) => write!(obj, " {index}/{index}/{index}"),
+                (true, false) => write!(obj, " {index}/{index}"),
+                (false, true) => write!(obj, " {index}//{index}"),
+                (false, false) => write!(obj, " {index}"),
+            }
+            .unwrap();
+        }
+        obj.push('\n');
+    }
+
+    std::fs::write(path, obj).map_err(|e| format!("Mesh {path:?}: {e}"))
+}
+
+/// Files a model's buffers are read from, which a change to should import it
+/// again. Only glTF refers to other files the import reads.
+pub fn dependencies(path: &Path) -> Vec<PathBuf> {
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    let json = match extension.as_deref() {
+        Some("gltf") => std::fs::read(path),
+        Some("glb") => read_glb_json(path),
+        _ => return Vec::new(),
+    };
+    let Some(gltf) = json
+        .ok()
+        .and_then(|json| gltf::Gltf::from_slice(&json).ok())
+    else {
+        return Vec::new();
+    };
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    gltf.document
+        .buffers()
+        .filter_map(|buffer| match buffer.source() {
+            // Resolved like the glTF importer does, embedded data aside
+            gltf::buffer::Source::Uri(uri) if !uri.contains(':') => {
+                Some(base.join(&*urlencoding::decode(uri).ok()?))
+            }
+            gltf::buffer::Source::Uri(uri) => uri
+                .strip_prefix("file://")
+                .or_else(|| uri.strip_prefix("file:"))
+                .map(PathBuf::from),
+            gltf::buffer::Source::Bin => None,
+        })
+        .collect()
+}
+
+/// The JSON chunk of a `.glb`, without reading its binary chunk.
+fn read_glb_json(path: &Path) -> std::io::Result<Vec<u8>> {
+    // 12 byte header, then the JSON chunk's length and type
+    let mut file = std::fs::File::open(path)?;
+    let mut header = [0; 20];
+    file.read_exact(&mut header)?;
+    let length = u32::from_le_bytes(header[12..16].try_into().unwrap());
+    let mut json = Vec::new();
+    file.take(length as u64).read_to_end(&mut json)?;
+    Ok(json)
+}
+
+fn read_gltf(path: &Path) -> Result<Vec<Part>, String> {
+    let gltf = gltf::Gltf::open(path).map_err(|e| e.to_string())?;
+    let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone())
+        .map_err(|e| e.to_string())?;
+
+    let mut parts = Vec::new();
+    for mesh in gltf.document.meshes() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                return Err(format!(
+                    "mesh {:?} uses {:?}, only triangles are supported",
+                    mesh.name().unwrap_or_default(),
+                    primitive.mode()
+                ));
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let positions = positions.collect::<Vec<_>>();
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+
+            parts.push(Part {
+                positions,
+                colors: reader
+                    .read_colors(0)
+                    .map(|colors| colors.into_rgb_f32().collect()),
+                uvs: reader
+                    .read_tex_coords(0)
+                    .map(|uvs| uvs.into_f32().collect()),
+                normals: reader.read_normals().map(Iterator::collect),
+                indices,
+            });
+        }
+    }
+
+    Ok(parts)
+}
+
+fn read_obj(path: &Path) -> Result<Vec<Part>, String> {
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ..Default::default()
+    };
+    let (models, _) = tobj::load_obj(path, &options).map_err(|e| e.to_string())?;
+
+    Ok(models
+        .into_iter()
+        .map(|model| {
+            let mesh = model.mesh;
+            Part {
+                positions: triples(&mesh.positions),
+                colors: (!mesh.vertex_color.is_empty()).then(|| triples(&mesh.vertex_color)),
+                // OBJ puts the origin of texture coordinates at the bottom
+                uvs: (!mesh.texcoords.is_empty()).then(|| {
+                    mesh.texcoords
+                        .chunks_exact(2)
+                        .map(|uv| [uv[0], 1.0 - uv[1]])
+                        .collect()
+                }),
+                normals: (!mesh.normals.is_empty()).then(|| triples(&mesh.normals)),
+                indices: mesh.indices,
+            }
+        })
+        .collect())
+}
+
+fn triples(values: &[f32]) -> Vec<[f32; 3]> {
+    values.chunks_exact(3).map(|v| [v[0], v[1], v[2]]).collect()
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(indices: Vec<u32>) -> Part {
+        Part {
+            positions: vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            colors: None,
+            uvs: None,
+            normals: None,
+            indices,
+        }
+    }
+
+    #[test]
+    fn offsets_indices_of_later_parts() {
+        let mut geometry = Geometry::default();
+        geometry.add(triangle(vec![0, 1, 2])).unwrap();
+        geometry.add(triangle(vec![2, 1, 0])).unwrap();
+        assert_eq!(geometry.indices, [0, 1, 2, 5, 4, 3]);
+        assert_eq!(geometry.colors.len(), 6);
+    }
+
+    #[test]
+    fn rejects_indices_past_the_vertices() {
+        let error = Geometry::default()
+            .add(triangle(vec![0, 1, 3]))
+            .unwrap_err();
+        assert_eq!(error, "index 3 is past its 3 vertices");
+    }
+
+    #[test]
+    fn rejects_missing_attributes() {
+        let part = Part {
+            normals: Some(vec![[0.0, 0.0, 1.0]]),
+            ..triangle(vec![0, 1, 2])
+        };
+        let error = Geometry::default().add(part).unwrap_err();
+        assert_eq!(error, "has 1 normals for 3 vertices");
+    }
+
+    #[test]
+    fn rejects_quad2d_meshes() {
+        let config = MeshConfig {
+            name: String::from("quad"),
+            path: PathBuf::from("quad.obj"),
+            pipeline: Some(Pipeline::Quad2d),
+            compute_normals: false,
+        };
+        assert!(import(&config).unwrap_err().contains("quad2d"));
+    }
+
+    #[test]
+    fn lists_external_gltf_buffers() {
+        let dir = std::env::temp_dir().join(format!("nethercade_mesh_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let gltf = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [
+                { "uri": "mesh%20data.bin", "byteLength": 4 },
+                { "uri": "data:application/octet-stream;base64,AAAAAA==", "byteLength": 4 }
+            ]
+        }"#;
+        let gltf_path = dir.join("model.gltf");
+        std::fs::write(&gltf_path, gltf).unwrap();
+        assert_eq!(dependencies(&gltf_path), [dir.join("mesh data.bin")]);
+
+        // The binary chunk is the first buffer, which has no uri
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 4 }, { "uri": "extra.bin", "byteLength": 4 }]
+        }"#;
+        // Chunks are padded to 4 bytes, JSON with spaces
+        let json = format!("{json:<0$}", json.len().next_multiple_of(4));
+        let bin = [0u8; 4];
+        let mut glb = Vec::new();
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(json.as_bytes());
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(bin);
+        let glb_path = dir.join("model.glb");
+        std::fs::write(&glb_path, glb).unwrap();
+        assert_eq!(dependencies(&glb_path), [dir.join("extra.bin")]);
+
+        assert!(dependencies(&dir.join("model.obj")).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fs::OpenOptions, io::Write, path::Path, process::ExitCode};
+
+use ed25519_dalek::SigningKey;
+use nethercade_core::{key_from_hex, key_to_hex};
+
+pub const PUBLIC_KEY_EXTENSION: &str = "pub";
+
+/// Writes a new secret key to `path` and its public key next to it, never
+/// overwriting an existing key.
+pub fn keygen(path: &Path) -> ExitCode {
+    let mut seed = [0; 32];
+    if let Err(e) = getrandom::getrandom(&mut seed) {
+        println!("Failed to generate a key: {e}");
+        return ExitCode::FAILURE;
+    }
+    let key = SigningKey::from_bytes(&seed);
+    let public_key = key_to_hex(&key.verifying_key().to_bytes());
+
+    let public_path = path.with_extension(PUBLIC_KEY_EXTENSION);
+    let result = write_new(path, &key_to_hex(&key.to_bytes()), true)
+        .and_then(|()| write_new(&public_path, &public_key, false));
+    if let Err(e) = result {
+        println!("Failed to write key: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    println!("Wrote secret key {path:?}, keep it out of version control.");
+    println!("Wrote public key {public_path:?}: {public_key}");
+    ExitCode::SUCCESS
+}
+
+pub fn load_key(path: &Path) -> Result<SigningKey, String> {
+    let hex = std::fs::read_to_string(path).map_err(|e| format!("Signing key {path:?}: {e}"))?;
+    let seed = key_from_hex(&hex).map_err(|e| format!("Signing key {path:?}: {e}"))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Fails if `path` exists. Secret files are only readable by their owner.
+fn write_new(path: &Path, contents: &str, secret: bool) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if secret {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+
+    let mut file = options.open(path).map_err(|e| format!("{path:?}: {e}"))?;
+    writeln!(file, "{contents}").map_err(|e| format!("{path:?}: {e}"))
+}
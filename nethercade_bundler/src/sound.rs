@@ -0,0 +1,362 @@
+use std::{ffi::OsStr, fs::File, path::Path};
+
+use nethercade_core::{Asset, AssetData, SOUND_SAMPLE_RATE, SoundSamples, adpcm};
+use serde::{Deserialize, Serialize};
+
+use crate::config::SoundConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Compression {
+    /// 4 bits per sample, decoded by the console.
+    Adpcm,
+}
+
+/// Audio as read from a file, before conversion.
+struct Decoded {
+    channels: u16,
+    sample_rate: u32,
+    /// Interleaved, in `[-1, 1]`.
+    samples: Vec<f32>,
+    /// Loop points stored in the file, in frames.
+    loop_points: Option<(u32, u32)>,
+}
+
+impl Decoded {
+    fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+}
+
+/// Decodes an audio file and converts it to a sound asset at
+/// [`SOUND_SAMPLE_RATE`].
+pub fn import(config: &SoundConfig) -> Result<Asset, String> {
+    let path = &config.path;
+    let decoded = decode(path).map_err(|e| format!("Sound {path:?}: {e}"))?;
+    if decoded.channels == 0 || decoded.samples.is_empty() {
+        return Err(format!("Sound {path:?}: contains no audio"));
+    }
+
+    let channels = config.channels.unwrap_or(decoded.channels.min(2));
+    if !(1..=2).contains(&channels) {
+        return Err(format!(
+            "Sound {path:?}: channels must be 1 or 2, got {channels}"
+        ));
+    }
+
+    let source_frames = decoded.frames() as u32;
+    let loop_points = match (config.loop_start, config.loop_end) {
+        (None, None) => decoded.loop_points,
+        (start, end) => Some((start.unwrap_or(0), end.unwrap_or(source_frames))),
+    };
+    if let Some((start, end)) = loop_points
+        && (start >= end || end > source_frames)
+    {
+        return Err(format!(
+            "Sound {path:?}: loop {start}..{end} doesn't fit in its {source_frames} frames"
+        ));
+    }
+
+    let samples = convert_channels(&decoded, channels);
+    let samples = resample(&samples, channels, decoded.sample_rate, SOUND_SAMPLE_RATE);
+
+    let frames = (samples.len() / channels as usize) as u32;
+    let ratio = SOUND_SAMPLE_RATE as f64 / decoded.sample_rate as f64;
+    let loop_points = loop_points.map(|(start, end)| {
+        let scale = |frame: u32| ((frame as f64 * ratio).round() as u32).min(frames);
+        (scale(start), scale(end))
+    });
+
+    let samples = match config.compression {
+        None => SoundSamples::Pcm(samples),
+        Some(Compression::Adpcm) => SoundSamples::Adpcm {
+            sample_count: samples.len() as u32,
+            data: adpcm::encode(&samples, channels),
+        },
+    };
+
+    Ok(Asset {
+        name: config.name.clone(),
+        data: AssetData::Sound {
+            channels,
+            sample_rate: SOUND_SAMPLE_RATE,
+            samples,
+            loop_points,
+        },
+    })
+}
+
+/// Writes interleaved samples as a 32-bit float WAV.
+pub fn export(path: &Path, channels: u16, sample_rate: u32, samples: &[f32]) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let write = || {
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for &sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()
+    };
+    write().map_err(|e: hound::Error| format!("Sound {path:?}: {e}"))
+}
+
+fn decode(path: &Path) -> Result<Decoded, String> {
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("wav") => decode_wav(path),
+        Some("ogg") => decode_ogg(path),
+        Some("flac") => decode_flac(path),
+        _ => Err(String::from(
+            "unsupported format, expected one of wav, ogg or flac",
+        )),
+    }
+}
+
+fn decode_wav(path: &Path) -> Result<Decoded, String> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| e.to_string())?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?
+        }
+    };
+
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    Ok(Decoded {
+        channels: spec.channels,
+        sample_rate: spec.sample_rate,
+        samples,
+        loop_points: wav_loop_points(&bytes),
+    })
+}
+
+/// Reads the first loop of a WAV `smpl` chunk, whose end is inclusive.
+fn wav_loop_points(bytes: &[u8]) -> Option<(u32, u32)> {
+    let read_u32 = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+
+    // Chunks follow the 12 byte RIFF header, padded to even sizes
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = read_u32(offset + 4)? as usize;
+        let data = offset + 8;
+
+        if id == b"smpl" {
+            let loop_count = read_u32(data + 28)?;
+            if loop_count == 0 {
+                return None;
+            }
+            let start = read_u32(data + 36 + 8)?;
+            let end = read_u32(data + 36 + 12)?;
+            return Some((start, end.checked_add(1)?));
+        }
+
+        offset = data + size + size % 2;
+    }
+
+    None
+}
+
+fn decode_ogg(path: &Path) -> Result<Decoded, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut reader = lewton::inside_ogg::OggStreamReader::new(file).map_err(|e| e.to_string())?;
+
+    let mut samples = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl().map_err(|e| e.to_string())? {
+        samples.extend(packet.iter().map(|&sample| sample as f32 / 32768.0));
+    }
+
+    let tags = reader
+        .comment_hdr
+        .comment_list
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()));
+    Ok(Decoded {
+        channels: reader.ident_hdr.audio_channels as u16,
+        sample_rate: reader.ident_hdr.audio_sample_rate,
+        samples,
+        loop_points: tag_loop_points(tags),
+    })
+}
+
+fn decode_flac(path: &Path) -> Result<Decoded, String> {
+    let mut reader = claxon::FlacReader::open(path).map_err(|e| e.to_string())?;
+    let info = reader.streaminfo();
+    let scale = (1i64 << (info.bits_per_sample - 1)) as f32;
+
+    let loop_points = tag_loop_points(reader.tags());
+    let samples = reader
+        .samples()
+        .map(|sample| sample.map(|sample| sample as f32 / scale))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Decoded {
+        channels: info.channels as u16,
+        sample_rate: info.sample_rate,
+        samples,
+        loop_points,
+    })
+}
+
+/// The `LOOPSTART` and `LOOPLENGTH` comments used by many game engines.
+fn tag_loop_points<'a>(tags: impl Iterator<Item = (&'a str, &'a str)>) -> Option<(u32, u32)> {
+    let mut start = None;
+    let mut length = None;
+    for (key, value) in tags {
+        if key.eq_ignore_ascii_case("LOOPSTART") {
+            start = value.trim().parse::<u32>().ok();
+        } else if key.eq_ignore_ascii_case("LOOPLENGTH") {
+            length = value.trim().parse::<u32>().ok();
+        }
+    }
+
+    Some((start?, start?.checked_add(length?)?))
+}
+
+/// Downmixes to mono by averaging. Downmixing to stereo keeps the first two
+/// channels as left and right and mixes the rest equally into both, scaled
+/// so the sum can't clip. Upmixes by repeating the last channel.
+fn convert_channels(decoded: &Decoded, channels: u16) -> Vec<f32> {
+    let source = decoded.channels as usize;
+    if source == channels as usize {
+        return decoded.samples.clone();
+    }
+
+    let mut samples = Vec::with_capacity(decoded.frames() * channels as usize);
+    for frame in decoded.samples.chunks_exact(source) {
+        if channels == 1 {
+            samples.push(frame.iter().sum::<f32>() / source as f32);
+        } else if source > 2 {
+            let (sides, rest) = frame.split_at(2);
+            let rest_sum = rest.iter().sum::<f32>();
+            let scale = 1.0 / (1 + rest.len()) as f32;
+            samples.extend(sides.iter().map(|side| (side + rest_sum) * scale));
+        } else {
+            for channel in 0..channels as usize {
+                samples.push(frame[channel.min(source - 1)]);
+            }
+        }
+    }
+    samples
+}
+
+/// Linear interpolation between neighbouring frames.
+fn resample(samples: &[f32], channels: u16, from: u32, to: u32) -> Vec<f32> {
+    if from == to {
+        return samples.to_vec();
+    }
+
+    let channels = channels as usize;
+    let frames = samples.len() / channels;
+    let new_frames = (frames as u64 * to as u64 / from as u64) as usize;
+    let step = from as f64 / to as f64;
+
+    let mut resampled = Vec::with_capacity(new_frames * channels);
+    for frame in 0..new_frames {
+        let position = frame as f64 * step;
+        let index = position as usize;
+        let next = (index + 1).min(frames - 1);
+        let t = (position - index as f64) as f32;
+        for channel in 0..channels {
+            let a = samples[index * channels + channel];
+            let b = samples[next * channels + channel];
+            resampled.push(a + (b - a) * t);
+        }
+    }
+    resampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(channels: u16, samples: Vec<f32>) -> Decoded {
+        Decoded {
+            channels,
+            sample_rate: SOUND_SAMPLE_RATE,
+            samples,
+            loop_points: None,
+        }
+    }
+
+    #[test]
+    fn mixes_extra_channels_into_stereo() {
+        let surround = decoded(4, vec![0.2, -0.2, 0.4, 0.6]);
+        let stereo = convert_channels(&surround, 2);
+        assert_eq!(stereo.len(), 2);
+        assert!((stereo[0] - 0.4).abs() < 1e-6, "{stereo:?}");
+        assert!((stereo[1] - 0.2666667).abs() < 1e-6, "{stereo:?}");
+
+        assert_eq!(convert_channels(&surround, 1), [0.25]);
+        assert_eq!(convert_channels(&decoded(1, vec![0.5]), 2), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn resamples_to_the_new_length() {
+        let samples = vec![0.0; 44100 * 2];
+        assert_eq!(resample(&samples, 2, 44100, 22050).len(), 22050 * 2);
+        assert_eq!(resample(&samples, 1, 44100, 48000).len(), 96000);
+        assert_eq!(resample(&samples, 2, 44100, 44100).len(), samples.len());
+
+        let ramp = resample(&[0.0, 1.0], 1, 1, 4);
+        assert_eq!(ramp, [0.0, 0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn reads_wav_loop_points() {
+        let mut smpl = vec![0; 60];
+        smpl[28..32].copy_from_slice(&1u32.to_le_bytes());
+        smpl[44..48].copy_from_slice(&100u32.to_le_bytes());
+        smpl[48..52].copy_from_slice(&199u32.to_le_bytes());
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        // An odd sized chunk, followed by a padding byte
+        bytes.extend_from_slice(b"junk\x03\0\0\0abc\0");
+        bytes.extend_from_slice(b"smpl");
+        bytes.extend_from_slice(&(smpl.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&smpl);
+        assert_eq!(wav_loop_points(&bytes), Some((100, 200)));
+
+        smpl[28..32].copy_from_slice(&0u32.to_le_bytes());
+        let without_loops = [&bytes[..bytes.len() - smpl.len()], &smpl].concat();
+        assert_eq!(wav_loop_points(&without_loops), None);
+        assert_eq!(wav_loop_points(&bytes[..30]), None);
+    }
+
+    #[test]
+    fn reads_tag_loop_points() {
+        let tags = [
+            ("title", "Song"),
+            ("LoopStart", " 1000 "),
+            ("LOOPLENGTH", "500"),
+        ];
+        assert_eq!(tag_loop_points(tags.into_iter()), Some((1000, 1500)));
+
+        let tags = [("LOOPSTART", "1000")];
+        assert_eq!(tag_loop_points(tags.into_iter()), None);
+        let tags = [("LOOPSTART", "x"), ("LOOPLENGTH", "500")];
+        assert_eq!(tag_loop_points(tags.into_iter()), None);
+    }
+}
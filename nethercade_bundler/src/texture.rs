@@ -0,0 +1,223 @@
+use std::{ffi::OsStr, path::Path};
+
+use image::{DynamicImage, ExtendedColorType, ImageFormat, RgbaImage, imageops::FilterType};
+use nethercade_core::{Asset, AssetData};
+
+use crate::config::TextureConfig;
+
+/// Formats accepted for textures, by file extension.
+const SUPPORTED_FORMATS: &[(&str, ImageFormat)] = &[
+    ("png", ImageFormat::Png),
+    ("qoi", ImageFormat::Qoi),
+    ("bmp", ImageFormat::Bmp),
+    ("tga", ImageFormat::Tga),
+];
+
+/// Decodes and converts an image into a texture asset laid out the way
+/// `load_texture` expects.
+pub fn import(config: &TextureConfig) -> Result<Asset, String> {
+    convert(config, decode(&config.path)?)
+}
+
+/// The conversion part of [`import`], on an already decoded image.
+fn convert(config: &TextureConfig, image: DynamicImage) -> Result<Asset, String> {
+    let has_alpha = config.alpha.unwrap_or_else(|| image.color().has_alpha());
+    let mut image = image.into_rgba8();
+
+    if config.power_of_two {
+        let (width, height) = image.dimensions();
+        let (new_width, new_height) = (width.next_power_of_two(), height.next_power_of_two());
+        if (new_width, new_height) != (width, height) {
+            image = image::imageops::resize(&image, new_width, new_height, FilterType::Nearest);
+        }
+    }
+
+    if let Some(bits) = config.quantize_bits {
+        if !(1..=8).contains(&bits) {
+            return Err(format!(
+                "Texture {:?}: quantizeBits must be between 1 and 8, got {bits}",
+                config.path
+            ));
+        }
+        quantize(&mut image, bits);
+    }
+
+    let (width, height) = image.dimensions();
+    let pixels = if has_alpha {
+        image.into_raw()
+    } else {
+        DynamicImage::ImageRgba8(image).into_rgb8().into_raw()
+    };
+
+    Ok(Asset {
+        name: config.name.clone(),
+        data: AssetData::Texture {
+            width,
+            height,
+            has_alpha,
+            pixels,
+        },
+    })
+}
+
+/// Writes a texture asset's pixels as a PNG.
+pub fn export(
+    path: &Path,
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+    pixels: &[u8],
+) -> Result<(), String> {
+    let color = if has_alpha {
+        ExtendedColorType::Rgba8
+    } else {
+        ExtendedColorType::Rgb8
+    };
+    image::save_buffer_with_format(path, pixels, width, height, color, ImageFormat::Png)
+        .map_err(|e| format!("Texture {path:?}: {e}"))
+}
+
+fn decode(path: &Path) -> Result<DynamicImage, String> {
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    let Some((_, format)) = SUPPORTED_FORMATS
+        .iter()
+        .find(|(ext, _)| extension.as_deref() == Some(*ext))
+    else {
+        return Err(format!(
+            "Texture {path:?}: unsupported format, expected one of png, qoi, bmp or tga"
+        ));
+    };
+
+    let bytes = std::fs::read(path).map_err(|e| format!("Texture {path:?}: {e}"))?;
+    image::load_from_memory_with_format(&bytes, *format)
+        .map_err(|e| format!("Texture {path:?}: {e}"))
+}
+
+/// Reduces every channel to `bits` bits, spreading the remaining levels
+/// evenly over the full range.
+fn quantize(image: &mut RgbaImage, bits: u8) {
+    let max = (1u32 << bits) - 1;
+    for value in image.iter_mut() {
+        let level = (*value as u32 * max + 127) / 255;
+        *value = (level * 255 / max) as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use image::{GrayImage, Luma, Rgb, RgbImage, Rgba};
+
+    use super::*;
+
+    fn config() -> TextureConfig {
+        TextureConfig {
+            name: String::from("texture"),
+            path: PathBuf::from("texture.png"),
+            alpha: None,
+            power_of_two: false,
+            quantize_bits: None,
+        }
+    }
+
+    fn texture(asset: Asset) -> (u32, u32, bool, Vec<u8>) {
+        match asset.data {
+            AssetData::Texture {
+                width,
+                height,
+                has_alpha,
+                pixels,
+            } => (width, height, has_alpha, pixels),
+            _ => panic!("not a texture"),
+        }
+    }
+
+    #[test]
+    fn quantizes_to_evenly_spread_levels() {
+        let mut image = RgbaImage::from_fn(4, 1, |x, _| Rgba([[0, 127, 128, 255][x as usize]; 4]));
+        quantize(&mut image, 1);
+        assert_eq!(image.as_raw()[..8], [0; 8]);
+        assert_eq!(image.as_raw()[8..], [255; 8]);
+
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([0, 100, 200, 255]));
+        quantize(&mut image, 2);
+        assert_eq!(image.as_raw(), &[0, 85, 170, 255]);
+
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([1, 127, 128, 254]));
+        quantize(&mut image, 8);
+        assert_eq!(image.as_raw(), &[1, 127, 128, 254]);
+    }
+
+    #[test]
+    fn rejects_out_of_range_quantize_bits() {
+        for bits in [0, 9] {
+            let config = TextureConfig {
+                quantize_bits: Some(bits),
+                ..config()
+            };
+            let image = DynamicImage::ImageRgb8(RgbImage::new(1, 1));
+            assert!(
+                convert(&config, image)
+                    .unwrap_err()
+                    .contains("quantizeBits")
+            );
+        }
+    }
+
+    #[test]
+    fn stores_rgb_unless_the_image_has_alpha() {
+        let rgb = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 1, Rgb([1, 2, 3])));
+        let (_, _, has_alpha, pixels) = texture(convert(&config(), rgb.clone()).unwrap());
+        assert!(!has_alpha);
+        assert_eq!(pixels, [1, 2, 3, 1, 2, 3]);
+
+        let rgba = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([1, 2, 3, 4])));
+        let (_, _, has_alpha, pixels) = texture(convert(&config(), rgba.clone()).unwrap());
+        assert!(has_alpha);
+        assert_eq!(pixels, [1, 2, 3, 4]);
+
+        let gray = DynamicImage::ImageLuma8(GrayImage::from_pixel(1, 1, Luma([9])));
+        let (_, _, has_alpha, pixels) = texture(convert(&config(), gray).unwrap());
+        assert!(!has_alpha);
+        assert_eq!(pixels, [9, 9, 9]);
+
+        // The config overrides what the image says, both ways
+        let forced = TextureConfig {
+            alpha: Some(true),
+            ..config()
+        };
+        let (_, _, has_alpha, pixels) = texture(convert(&forced, rgb).unwrap());
+        assert!(has_alpha);
+        assert_eq!(pixels, [1, 2, 3, 255, 1, 2, 3, 255]);
+
+        let dropped = TextureConfig {
+            alpha: Some(false),
+            ..config()
+        };
+        let (_, _, has_alpha, pixels) = texture(convert(&dropped, rgba).unwrap());
+        assert!(!has_alpha);
+        assert_eq!(pixels, [1, 2, 3]);
+    }
+
+    #[test]
+    fn scales_up_to_powers_of_two() {
+        let config = TextureConfig {
+            power_of_two: true,
+            ..config()
+        };
+        let image = DynamicImage::ImageRgb8(RgbImage::new(3, 4));
+        let (width, height, _, pixels) = texture(convert(&config, image).unwrap());
+        assert_eq!((width, height), (4, 4));
+        assert_eq!(pixels.len(), 4 * 4 * 3);
+    }
+
+    #[test]
+    fn rejects_unsupported_formats() {
+        let error = decode(Path::new("texture.jpg")).unwrap_err();
+        assert!(error.contains("unsupported format"), "{error}");
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use image::{ExtendedColorType, ImageFormat};
+use nethercade_core::{Asset, AssetData, Pipeline, Rom, RomImage, SoundSamples};
+
+use crate::{
+    config::{
+        AssetConfig, Config, MeshConfig, MetadataConfig, SoundConfig, TextureConfig, WasmConfig,
+    },
+    mesh,
+    sound::{self, Compression},
+    texture,
+    wasm::DebugInfoMode,
+};
+
+const CONFIG_FILE_NAME: &str = "config.json";
+const ASSETS_DIRECTORY: &str = "assets";
+
+/// Extracts a `.nzrom` into a directory holding its code, images and assets
+/// as files, and a config bundling them back into the same ROM.
+pub fn run(path: &Path, output_dir: Option<&Path>) -> ExitCode {
+    let rom = match std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Rom::decode(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(rom) => rom,
+        Err(e) => {
+            println!("Failed to load ROM {path:?}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let output_dir = output_dir.map_or_else(|| path.with_extension(""), Path::to_path_buf);
+    if std::fs::read_dir(&output_dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        println!("{output_dir:?} isn't empty, pick another directory with --output-dir");
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = std::fs::create_dir_all(output_dir.join(ASSETS_DIRECTORY)) {
+        println!("Failed to create {output_dir:?}: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    match unpack(&rom, &stem, &output_dir) {
+        Ok(()) => {
+            println!(
+                "Unpacked {path:?} to {output_dir:?}, rebuild it from {:?}.",
+                output_dir.join(CONFIG_FILE_NAME)
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("Failed to unpack {path:?}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn unpack(rom: &Rom, stem: &str, output_dir: &Path) -> Result<(), String> {
+    let write = |path: &Path, bytes: &[u8]| {
+        std::fs::write(output_dir.join(path), bytes).map_err(|e| format!("{path:?}: {e}"))
+    };
+
+    // Debug info goes back into the code, and the config moves it out again
+    let wasm_path = PathBuf::from(format!("{stem}.wasm"));
+    write(&wasm_path, &rom.symbolicated_code())?;
+
+    let metadata = &rom.metadata;
+    let write_image = |name: &str, image: &Option<RomImage>| -> Result<_, String> {
+        let Some(image) = image else {
+            return Ok(None);
+        };
+        let path = PathBuf::from(format!("{name}.png"));
+        image::save_buffer_with_format(
+            output_dir.join(&path),
+            &image.rgba,
+            image.width,
+            image.height,
+            ExtendedColorType::Rgba8,
+            ImageFormat::Png,
+        )
+        .map_err(|e| format!("{path:?}: {e}"))?;
+        Ok(Some(path))
+    };
+    let icon_path = write_image("icon", &metadata.icon)?;
+    let cover_path = write_image("cover", &metadata.cover)?;
+
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    let mut config = Config {
+        resolution: Some(rom.resolution),
+        frame_rate: Some(rom.frame_rate),
+        wasm_path,
+        output_file: Some(PathBuf::from(stem)),
+        wasm: WasmConfig {
+            debug_info: rom.debug_info.is_some().then_some(DebugInfoMode::Section),
+            ..Default::default()
+        },
+        metadata: MetadataConfig {
+            title: non_empty(&metadata.title),
+            author: non_empty(&metadata.author),
+            version: Some(metadata.version.to_string()),
+            description: non_empty(&metadata.description),
+            min_players: Some(metadata.min_players),
+            max_players: Some(metadata.max_players),
+            netplay: metadata.netplay,
+            genres: metadata.genres.clone(),
+            icon_path,
+            cover_path,
+        },
+        assets: Vec::new(),
+        textures: Vec::new(),
+        meshes: Vec::new(),
+        sounds: Vec::new(),
+        signing: None,
+        bindings: Vec::new(),
+    };
+
+    let mut file_names = HashSet::new();
+    for asset in rom.assets.iter() {
+        let path = Path::new(ASSETS_DIRECTORY).join(file_name(asset, &mut file_names));
+        unpack_asset(asset, path, output_dir, &mut config)?;
+    }
+
+    // The config lists assets by kind, which only keeps their ids when the
+    // ROM did the same
+    let rebuilt_order = config
+        .assets
+        .iter()
+        .map(|asset| &asset.name)
+        .chain(config.textures.iter().map(|texture| &texture.name))
+        .chain(config.meshes.iter().map(|mesh| &mesh.name))
+        .chain(config.sounds.iter().map(|sound| &sound.name));
+    if !rebuilt_order.eq(rom.assets.iter().map(|asset| &asset.name)) {
+        println!("Asset ids will change when rebuilt, the config groups assets by kind.");
+    }
+
+    let json = sonic_rs::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    write(Path::new(CONFIG_FILE_NAME), json.as_bytes())
+}
+
+/// Writes an asset to `path`, relative to the output directory, and adds
+/// the config entry importing it back.
+fn unpack_asset(
+    asset: &Asset,
+    path: PathBuf,
+    output_dir: &Path,
+    config: &mut Config,
+) -> Result<(), String> {
+    let name = asset.name.clone();
+    let output_path = output_dir.join(&path);
+    match &asset.data {
+        AssetData::Texture {
+            width,
+            height,
+            has_alpha,
+            pixels,
+        } => {
+            texture::export(&output_path, *width, *height, *has_alpha, pixels)?;
+            config.textures.push(TextureConfig {
+                name,
+                path,
+                alpha: Some(*has_alpha),
+                power_of_two: false,
+                quantize_bits: None,
+            });
+        }
+        AssetData::Mesh {
+            pipeline,
+            vertices,
+            indices,
+        } => {
+            let pipeline = Pipeline::from_id(*pipeline)
+                .ok_or_else(|| format!("Mesh {name:?}: unknown pipeline {pipeline}"))?;
+            mesh::export(&output_path, pipeline, vertices, indices.as_deref())?;
+            config.meshes.push(MeshConfig {
+                name,
+                path,
+                pipeline: Some(pipeline),
+                compute_normals: false,
+            });
+        }
+        AssetData::Sound {
+            channels,
+            sample_rate,
+            samples,
+            loop_points,
+        } => {
+            sound::export(
+                &output_path,
+                *channels,
+                *sample_rate,
+                &samples.decode(*channels),
+            )?;
+            config.sounds.push(SoundConfig {
+                name,
+                path,
+                channels: Some(*channels),
+                compression: match samples {
+                    SoundSamples::Pcm(_) => None,
+                    SoundSamples::Adpcm { .. } => Some(Compression::Adpcm),
+                },
+                loop_start: loop_points.map(|(start, _)| start),
+                loop_end: loop_points.map(|(_, end)| end),
+            });
+        }
+        AssetData::Raw(bytes) => {
+            std::fs::write(&output_path, bytes).map_err(|e| format!("Asset {name:?}: {e}"))?;
+            config.assets.push(AssetConfig { name, path });
+        }
+    }
+    Ok(())
+}
+
+/// A file name for the asset that's safe on any platform and not yet used.
+/// Raw assets keep their name, which often already has an extension.
+fn file_name(asset: &Asset, used: &mut HashSet<String>) -> String {
+    let stem = asset
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let stem = stem.trim_start_matches('.');
+    let extension = match &asset.data {
+        AssetData::Texture { .. } => ".png",
+        AssetData::Mesh { .. } => ".obj",
+        AssetData::Sound { .. } => ".wav",
+        AssetData::Raw(_) => "",
+    };
+
+    let mut name = format!("{stem}{extension}");
+    let mut suffix = 1;
+    while name == extension || !used.insert(name.clone()) {
+        name = format!("{stem}_{suffix}{extension}");
+        suffix += 1;
+    }
+    name
+}
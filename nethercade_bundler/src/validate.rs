@@ -0,0 +1,451 @@
+use std::{path::Path, process::ExitCode};
+
+use nethercade_core::{
+    Rom, RomContainer, SectionKind,
+    console_spec::{
+        self, IMPORT_MODULE, ImportSpec, MAX_ASSETS_SIZE, MAX_MEMORY_SIZE, REQUIRED_EXPORTS,
+        WasmType,
+    },
+};
+use wasmparser::{
+    ExternalKind, FuncType, MemoryType, Parser, Payload, TypeRef, ValType, Validator, WasmFeatures,
+};
+
+const WASM_MAGIC: [u8; 4] = *b"\0asm";
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Proposals wasm toolchains may emit which the console doesn't run.
+const UNSUPPORTED_FEATURES: [(WasmFeatures, &str); 2] = [
+    (WasmFeatures::THREADS, "threads"),
+    (WasmFeatures::MEMORY64, "memory64"),
+];
+
+/// The outcome of one group of checks.
+struct Check {
+    name: &'static str,
+    summary: String,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+    skipped: bool,
+}
+
+impl Check {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            summary: String::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            skipped: false,
+        }
+    }
+
+    fn skipped(name: &'static str, reason: &str) -> Self {
+        Self {
+            summary: reason.to_string(),
+            skipped: true,
+            ..Self::new(name)
+        }
+    }
+}
+
+/// What the checks need from a module, with imported items first in each
+/// index space.
+#[derive(Default)]
+pub struct ModuleInfo {
+    pub types: Vec<FuncType>,
+    pub imports: Vec<(String, String, TypeRef)>,
+    pub functions: Vec<u32>,
+    pub memories: Vec<MemoryType>,
+    pub exports: Vec<(String, ExternalKind, u32)>,
+}
+
+impl ModuleInfo {
+    pub fn function_type(&self, index: u32) -> Option<&FuncType> {
+        let type_index = *self.functions.get(index as usize)?;
+        self.types.get(type_index as usize)
+    }
+}
+
+/// Checks a `.wasm` or `.nzrom` against what the console supports, prints a
+/// summary and fails if anything would stop the game from running.
+pub fn run(path: &Path) -> ExitCode {
+    println!("Validating {path:?}");
+
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            println!("Failed to read {path:?}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Legacy ROMs, and containers from before metadata, get the default
+    let has_metadata = RomContainer::read_table(&bytes).is_ok_and(|table| {
+        table
+            .entries
+            .iter()
+            .any(|entry| entry.kind == SectionKind::Metadata)
+    });
+
+    let (code, rom) = if RomContainer::is_container(&bytes) || !bytes.starts_with(&WASM_MAGIC) {
+        match Rom::decode(&bytes) {
+            Ok(rom) => (rom.code.clone(), Some(rom)),
+            Err(e) => {
+                println!("Failed to load ROM: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        (bytes.into_boxed_slice(), None)
+    };
+
+    let mut checks = check_module(&code);
+    match &rom {
+        Some(rom) => {
+            checks.push(check_assets(rom));
+            if has_metadata {
+                checks.push(check_metadata(rom));
+            } else {
+                checks.push(Check::skipped(
+                    "metadata",
+                    "the ROM was bundled before metadata existed",
+                ));
+            }
+        }
+        None => {
+            for name in ["assets", "metadata"] {
+                checks.push(Check::skipped(name, "only ROMs carry these"));
+            }
+        }
+    }
+
+    let mut error_count = 0;
+    let mut warning_count = 0;
+    for check in &checks {
+        let status = if !check.errors.is_empty() {
+            "error"
+        } else if !check.warnings.is_empty() {
+            "warning"
+        } else if check.skipped {
+            "skipped"
+        } else {
+            "ok"
+        };
+        println!("  {:<9} {status:<8} {}", check.name, check.summary);
+        for error in &check.errors {
+            println!("      error: {error}");
+        }
+        for warning in &check.warnings {
+            println!("      warning: {warning}");
+        }
+        error_count += check.errors.len();
+        warning_count += check.warnings.len();
+    }
+
+    if error_count == 0 {
+        println!("Passed with {warning_count} warnings.");
+        ExitCode::SUCCESS
+    } else {
+        println!("Failed with {error_count} errors and {warning_count} warnings.");
+        ExitCode::FAILURE
+    }
+}
+
+fn check_module(code: &[u8]) -> Vec<Check> {
+    let base = WasmFeatures::default();
+    if let Err(e) = Validator::new_with_features(base).validate_all(code) {
+        let mut module = Check::new("module");
+        module.summary = String::from("not a valid wasm module");
+        module.errors.push(e.to_string());
+        return vec![module];
+    }
+
+    let mut features = Check::new("features");
+    // Validating again without a proposal fails only if the module uses it
+    for (feature, name) in UNSUPPORTED_FEATURES {
+        if let Err(e) = Validator::new_with_features(base - feature).validate_all(code) {
+            features.errors.push(format!(
+                "uses {name}, which the console doesn't enable: {e}"
+            ));
+        }
+    }
+    features.summary = if features.errors.is_empty() {
+        String::from("only uses proposals the console supports")
+    } else {
+        String::from("uses unsupported proposals")
+    };
+
+    let info = match parse(code) {
+        Ok(info) => info,
+        Err(e) => {
+            features.errors.push(e.to_string());
+            return vec![features];
+        }
+    };
+
+    vec![
+        check_imports(&info),
+        check_exports(&info),
+        check_memory(&info),
+        features,
+    ]
+}
+
+pub fn parse(code: &[u8]) -> wasmparser::Result<ModuleInfo> {
+    let mut info = ModuleInfo::default();
+    for payload in Parser::new(0).parse_all(code) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for ty in reader.into_iter_err_on_gc_types() {
+                    info.types.push(ty?);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    match import.ty {
+                        TypeRef::Func(type_index) => info.functions.push(type_index),
+                        TypeRef::Memory(memory) => info.memories.push(memory),
+                        _ => (),
+                    }
+                    info.imports.push((
+                        import.module.to_string(),
+                        import.name.to_string(),
+                        import.ty,
+                    ));
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for type_index in reader {
+                    info.functions.push(type_index?);
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for memory in reader {
+                    info.memories.push(memory?);
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    info.exports
+                        .push((export.name.to_string(), export.kind, export.index));
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(info)
+}
+
+fn check_imports(info: &ModuleInfo) -> Check {
+    let mut check = Check::new("imports");
+    for (module, name, ty) in &info.imports {
+        if module != IMPORT_MODULE {
+            check.errors.push(format!(
+                "{module}.{name} comes from module {module:?}, the console only provides {IMPORT_MODULE:?}"
+            ));
+            continue;
+        }
+
+        let TypeRef::Func(type_index) = ty else {
+            check.errors.push(format!(
+                "{name} is imported as a {}, the console only provides functions",
+                type_ref_name(ty)
+            ));
+            continue;
+        };
+
+        let Some(spec) = ImportSpec::find(name) else {
+            check
+                .errors
+                .push(format!("{name} isn't provided by the console"));
+            continue;
+        };
+
+        if let Some(ty) = info.types.get(*type_index as usize)
+            && !matches_spec(ty, spec)
+        {
+            check.errors.push(format!(
+                "{name} is imported as {} but the console provides {}",
+                func_signature(ty),
+                spec.signature()
+            ));
+        }
+    }
+
+    check.summary = format!(
+        "{} imports, {} unresolved",
+        info.imports.len(),
+        check.errors.len()
+    );
+    check
+}
+
+fn check_exports(info: &ModuleInfo) -> Check {
+    let mut check = Check::new("exports");
+    for name in REQUIRED_EXPORTS {
+        let Some((_, kind, index)) = info.exports.iter().find(|(export, ..)| export == name) else {
+            check.errors.push(format!("{name} isn't exported"));
+            continue;
+        };
+
+        if *kind != ExternalKind::Func {
+            check
+                .errors
+                .push(format!("{name} is exported as a {kind:?}, not a function"));
+            continue;
+        }
+
+        if let Some(ty) = info.function_type(*index)
+            && !(ty.params().is_empty() && ty.results().is_empty())
+        {
+            check.errors.push(format!(
+                "{name} has type {}, the console calls it with no parameters or results",
+                func_signature(ty)
+            ));
+        }
+    }
+
+    // Imports reading guest memory look for it under this name
+    match info.exports.iter().find(|(export, ..)| export == "memory") {
+        Some((_, ExternalKind::Memory, _)) => (),
+        Some((_, kind, _)) => check
+            .errors
+            .push(format!("memory is exported as a {kind:?}, not a memory")),
+        None => check.errors.push(String::from("memory isn't exported")),
+    }
+
+    check.summary = if check.errors.is_empty() {
+        format!("{} and memory exported", REQUIRED_EXPORTS.join(", "))
+    } else {
+        String::from("missing or mistyped exports")
+    };
+    check
+}
+
+fn check_memory(info: &ModuleInfo) -> Check {
+    let mut check = Check::new("memory");
+    let Some(memory) = info.memories.first() else {
+        check.summary = String::from("no memory declared");
+        return check;
+    };
+
+    if info.memories.len() > 1 {
+        check.warnings.push(format!(
+            "declares {} memories, the console only gives imports access to the first",
+            info.memories.len()
+        ));
+    }
+
+    let limit = MAX_MEMORY_SIZE as u64;
+    let initial = memory.initial.saturating_mul(WASM_PAGE_SIZE);
+    let maximum = memory
+        .maximum
+        .map(|pages| pages.saturating_mul(WASM_PAGE_SIZE));
+
+    if initial > limit {
+        check.errors.push(format!(
+            "starts at {}, over the console's limit of {}",
+            format_size(initial),
+            format_size(limit)
+        ));
+    }
+    if let Some(maximum) = maximum
+        && maximum > limit
+    {
+        check.warnings.push(format!(
+            "may grow to {}, but the console stops it at {}",
+            format_size(maximum),
+            format_size(limit)
+        ));
+    }
+
+    check.summary = format!(
+        "starts at {}, grows up to {}",
+        format_size(initial),
+        format_size(maximum.unwrap_or(limit).min(limit))
+    );
+    check
+}
+
+fn check_assets(rom: &Rom) -> Check {
+    let mut check = Check::new("assets");
+    let size = rom.assets_size() as u64;
+    let budget = MAX_ASSETS_SIZE as u64;
+
+    if size > budget {
+        check
+            .errors
+            .push(format!("{} over the budget", format_size(size - budget)));
+    }
+
+    check.summary = format!(
+        "{} assets using {} of {} ({:.1}%)",
+        rom.assets.len(),
+        format_size(size),
+        format_size(budget),
+        size as f64 / budget as f64 * 100.0
+    );
+    check
+}
+
+fn check_metadata(rom: &Rom) -> Check {
+    let mut check = Check::new("metadata");
+    if let Err(errors) = rom.metadata.validate() {
+        check.errors = errors;
+    }
+    check.summary = format!(
+        "{:?} {} by {:?}",
+        rom.metadata.title, rom.metadata.version, rom.metadata.author
+    );
+    check
+}
+
+fn matches_spec(ty: &FuncType, spec: &ImportSpec) -> bool {
+    let same = |types: &[ValType], expected: &[WasmType]| {
+        types.len() == expected.len()
+            && types
+                .iter()
+                .zip(expected)
+                .all(|(ty, expected)| to_wasm_type(*ty) == Some(*expected))
+    };
+    same(ty.params(), spec.params) && same(ty.results(), spec.results)
+}
+
+fn to_wasm_type(ty: ValType) -> Option<WasmType> {
+    match ty {
+        ValType::I32 => Some(WasmType::I32),
+        ValType::I64 => Some(WasmType::I64),
+        ValType::F32 => Some(WasmType::F32),
+        ValType::F64 => Some(WasmType::F64),
+        _ => None,
+    }
+}
+
+pub fn func_signature(ty: &FuncType) -> String {
+    console_spec::signature(ty.params(), ty.results())
+}
+
+pub fn type_ref_name(ty: &TypeRef) -> &'static str {
+    match ty {
+        TypeRef::Func(_) => "function",
+        TypeRef::Table(_) => "table",
+        TypeRef::Memory(_) => "memory",
+        TypeRef::Global(_) => "global",
+        TypeRef::Tag(_) => "tag",
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{bytes} B")
+    }
+}
@@ -0,0 +1,387 @@
+use std::convert::Infallible;
+
+use nethercade_core::CustomSection;
+use serde::{Deserialize, Serialize};
+use wasm_encoder::{
+    CodeSection, Function, Instruction, Module,
+    reencode::{self, Reencode},
+};
+use wasmparser::{ElementItems, ExternalKind, FunctionBody, Operator, Parser, Payload, TypeRef};
+
+use crate::config::WasmConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DebugInfoMode {
+    /// Left in the code.
+    Keep,
+    Strip,
+    /// Moved to the ROM's debug info section.
+    Section,
+    /// Moved to a `.nzdbg` file next to the ROM.
+    Sidecar,
+}
+
+/// A module after post-processing.
+pub struct Processed {
+    pub code: Vec<u8>,
+    /// The mode used, after defaults.
+    pub debug_info: DebugInfoMode,
+    /// Debug sections moved out of the code, for [`DebugInfoMode::Section`]
+    /// and [`DebugInfoMode::Sidecar`].
+    pub debug_sections: Vec<CustomSection>,
+}
+
+/// One section of a module, as stored.
+struct RawSection<'a> {
+    id: u8,
+    /// Set for custom sections.
+    name: Option<&'a str>,
+    /// The whole section, including its id and size.
+    bytes: &'a [u8],
+    /// Contents of a custom section after its name.
+    custom_data: &'a [u8],
+}
+
+impl RawSection<'_> {
+    fn label(&self) -> String {
+        match self.name {
+            Some(name) => format!("custom {name:?}"),
+            None => String::from(section_name(self.id)),
+        }
+    }
+}
+
+/// Strips, optimizes and splits the debug info from a module as configured.
+/// Release builds strip by default.
+pub fn process(code: &[u8], config: &WasmConfig, release: bool) -> Result<Processed, String> {
+    let strip = config.strip.unwrap_or(release);
+    let debug_info = config.debug_info.unwrap_or(if release {
+        DebugInfoMode::Strip
+    } else {
+        DebugInfoMode::Keep
+    });
+
+    let optimized;
+    let code = if config.optimize {
+        optimized = stub_unreachable_functions(code)?;
+        &optimized[..]
+    } else {
+        code
+    };
+
+    let sections = sections(code)?;
+    let mut dropped_dwarf = false;
+    let mut processed = Processed {
+        code: code[..8].to_vec(),
+        debug_info,
+        debug_sections: Vec::new(),
+    };
+    for section in sections {
+        let Some(name) = section.name else {
+            processed.code.extend_from_slice(section.bytes);
+            continue;
+        };
+
+        if !is_debug_section(name) {
+            if !strip {
+                processed.code.extend_from_slice(section.bytes);
+            }
+            continue;
+        }
+
+        // DWARF describes code offsets, which optimizing moves around
+        if config.optimize && name.starts_with(".debug_") {
+            if !dropped_dwarf && debug_info != DebugInfoMode::Strip {
+                println!("Dropping DWARF, which doesn't survive optimization.");
+                dropped_dwarf = true;
+            }
+            continue;
+        }
+
+        match debug_info {
+            DebugInfoMode::Keep => processed.code.extend_from_slice(section.bytes),
+            DebugInfoMode::Strip => (),
+            DebugInfoMode::Section | DebugInfoMode::Sidecar => {
+                processed.debug_sections.push(CustomSection {
+                    name: name.to_string(),
+                    data: section.custom_data.to_vec(),
+                });
+            }
+        }
+    }
+
+    Ok(processed)
+}
+
+/// Sections used for symbolication rather than by the toolchain.
+fn is_debug_section(name: &str) -> bool {
+    name == "name"
+        || name == "sourceMappingURL"
+        || name == "external_debug_info"
+        || name.starts_with(".debug_")
+}
+
+/// Prints the size of each section before and after processing.
+pub fn print_size_report(before: &[u8], after: &[u8]) {
+    let (Ok(before), Ok(after)) = (sections(before), sections(after)) else {
+        return;
+    };
+
+    let mut labels = before.iter().map(RawSection::label).collect::<Vec<_>>();
+    for section in &after {
+        let label = section.label();
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+
+    let size = |sections: &[RawSection], label: &str| {
+        sections
+            .iter()
+            .filter(|section| section.label() == label)
+            .map(|section| section.bytes.len())
+            .sum::<usize>()
+    };
+
+    println!("  {:<28} {:>10} {:>10}", "section", "before", "after");
+    for label in &labels {
+        println!(
+            "  {label:<28} {:>10} {:>10}",
+            size(&before, label),
+            size(&after, label)
+        );
+    }
+    let total = |sections: &[RawSection]| 8 + sections.iter().map(|s| s.bytes.len()).sum::<usize>();
+    println!(
+        "  {:<28} {:>10} {:>10}",
+        "total",
+        total(&before),
+        total(&after)
+    );
+}
+
+fn sections(code: &[u8]) -> Result<Vec<RawSection<'_>>, String> {
+    if code.len() < 8 || &code[..4] != b"\0asm" {
+        return Err(String::from("not a wasm module"));
+    }
+
+    let mut sections = Vec::new();
+    let mut offset = 8;
+    while offset < code.len() {
+        let start = offset;
+        let id = code[offset];
+        offset += 1;
+        let size = read_leb128(code, &mut offset)? as usize;
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= code.len())
+            .ok_or("section runs past the end of the module")?;
+
+        let (name, custom_data) = if id == 0 {
+            let mut name_offset = offset;
+            let name_len = read_leb128(code, &mut name_offset)? as usize;
+            let name_end = name_offset + name_len;
+            let name = code
+                .get(name_offset..name_end)
+                .filter(|_| name_end <= end)
+                .and_then(|name| std::str::from_utf8(name).ok())
+                .ok_or("custom section has an invalid name")?;
+            (Some(name), &code[name_end..end])
+        } else {
+            (None, &code[end..end])
+        };
+
+        sections.push(RawSection {
+            id,
+            name,
+            bytes: &code[start..end],
+            custom_data,
+        });
+        offset = end;
+    }
+
+    Ok(sections)
+}
+
+fn read_leb128(bytes: &[u8], offset: &mut usize) -> Result<u32, String> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*offset).ok_or("truncated section header")?;
+        *offset += 1;
+        value |= ((byte & 0x7F) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(String::from("invalid section size"))
+}
+
+fn section_name(id: u8) -> &'static str {
+    match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data count",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+/// Re-encodes a module with the bodies of functions that can't be reached
+/// from its exports, start function or tables replaced by `unreachable`.
+/// Indices stay the same, so the name section remains valid.
+fn stub_unreachable_functions(code: &[u8]) -> Result<Vec<u8>, String> {
+    let (imported, live) = live_functions(code).map_err(|e| e.to_string())?;
+    let stubbed = live[imported..].iter().filter(|live| !**live).count();
+    println!("Stubbing {stubbed} unreachable functions.");
+
+    let mut stubber = Stubber {
+        live,
+        imported,
+        next_function: 0,
+    };
+    let mut module = Module::new();
+    stubber
+        .parse_core_module(&mut module, Parser::new(0), code)
+        .map_err(|e| e.to_string())?;
+    Ok(module.finish())
+}
+
+/// The number of imported functions, and whether each function, imports
+/// included, may be called.
+fn live_functions(code: &[u8]) -> wasmparser::Result<(usize, Vec<bool>)> {
+    let mut imported = 0;
+    let mut roots = Vec::new();
+    let mut bodies = Vec::new();
+
+    for payload in Parser::new(0).parse_all(code) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    if let TypeRef::Func(_) = import?.ty {
+                        imported += 1;
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        roots.push(export.index);
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => roots.push(func),
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    match element?.items {
+                        ElementItems::Functions(functions) => {
+                            for function in functions {
+                                roots.push(function?);
+                            }
+                        }
+                        ElementItems::Expressions(_, expressions) => {
+                            for expression in expressions {
+                                referenced_functions(
+                                    expression?.get_operators_reader(),
+                                    &mut roots,
+                                )?;
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    referenced_functions(global?.init_expr.get_operators_reader(), &mut roots)?;
+                }
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body),
+            _ => (),
+        }
+    }
+
+    let mut live = vec![false; imported + bodies.len()];
+    while let Some(function) = roots.pop() {
+        let Some(is_live) = live.get_mut(function as usize) else {
+            continue;
+        };
+        if *is_live {
+            continue;
+        }
+        *is_live = true;
+
+        if let Some(body) = (function as usize)
+            .checked_sub(imported)
+            .and_then(|index| bodies.get(index))
+        {
+            referenced_functions(body.get_operators_reader()?, &mut roots)?;
+        }
+    }
+
+    Ok((imported, live))
+}
+
+fn referenced_functions(
+    mut reader: wasmparser::OperatorsReader,
+    functions: &mut Vec<u32>,
+) -> wasmparser::Result<()> {
+    while !reader.eof() {
+        match reader.read()? {
+            Operator::Call { function_index }
+            | Operator::ReturnCall { function_index }
+            | Operator::RefFunc { function_index } => functions.push(function_index),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+struct Stubber {
+    live: Vec<bool>,
+    imported: usize,
+    /// Index of the next body among local functions.
+    next_function: usize,
+}
+
+impl Reencode for Stubber {
+    type Error = Infallible;
+
+    fn parse_function_body(
+        &mut self,
+        code: &mut CodeSection,
+        func: FunctionBody<'_>,
+    ) -> Result<(), reencode::Error> {
+        let index = self.imported + self.next_function;
+        self.next_function += 1;
+
+        if self.live[index] {
+            return reencode::utils::parse_function_body(self, code, func);
+        }
+
+        let mut stub = Function::new([]);
+        stub.instruction(&Instruction::Unreachable);
+        stub.instruction(&Instruction::End);
+        code.function(&stub);
+        Ok(())
+    }
+
+    // Copied as is, the name section stays valid since indices don't change
+    fn parse_custom_section(
+        &mut self,
+        module: &mut Module,
+        section: wasmparser::CustomSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        module.section(&self.custom_section(section));
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::{Ipv4Addr, SocketAddr, TcpStream},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{BuildOptions, bundle, cache::AssetCache};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Time given to whatever changed a file to finish writing it.
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+const NOTIFY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Rebuilds the ROM whenever the config or a file it names changes, reusing
+/// imports of unchanged assets. Runs until killed.
+pub fn run(config_path: &Path, options: &BuildOptions, notify_port: Option<u16>) -> ExitCode {
+    let mut cache = AssetCache::default();
+    let mut stamps = HashMap::new();
+
+    loop {
+        let current = watched_paths(config_path, options)
+            .into_iter()
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect::<HashMap<_, _>>();
+
+        if current != stamps {
+            if !stamps.is_empty() {
+                println!("Change detected, rebuilding.");
+                thread::sleep(SETTLE_TIME);
+            }
+            stamps = current;
+
+            if let Some(output_path) = bundle(config_path, options, &mut cache)
+                && let Some(port) = notify_port
+            {
+                notify(port, &output_path);
+            }
+            println!("Watching {config_path:?} for changes.");
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The config, every file it names and the files those refer to. Only the
+/// config while it doesn't parse, the build reports why.
+fn watched_paths(config_path: &Path, options: &BuildOptions) -> Vec<PathBuf> {
+    let mut paths = vec![config_path.to_path_buf()];
+    if let Ok(config) = options.read_config(config_path) {
+        paths.extend(config.source_paths());
+    }
+    paths
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Sends the path of a rebuilt ROM to a console listening for hot reloads.
+fn notify(port: u16, rom_path: &Path) {
+    let path = std::fs::canonicalize(rom_path).unwrap_or_else(|_| rom_path.to_path_buf());
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let result = TcpStream::connect_timeout(&address, NOTIFY_TIMEOUT)
+        .and_then(|mut stream| writeln!(stream, "{}", path.display()));
+
+    match result {
+        Ok(()) => println!("Asked the console on port {port} to reload."),
+        Err(e) => println!("No console to reload on port {port}: {e}"),
+    }
+}
@@ -0,0 +1,89 @@
+//! Frames simulated per second per core by headless instances sharing one
+//! compiled module. Run with
+//! `cargo bench -p nethercade_console --bench batch_throughput`, optionally
+//! setting `NETHERCADE_BENCH_ROM` to a `.nzrom` or `.wasm` to measure a
+//! real game instead of the built in guest. Criterion keeps the results
+//! under `target/criterion` and reports changes against the previous run.
+
+use std::path::PathBuf;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use nethercade_console::{BatchSimulator, load_rom_file};
+use nethercade_core::Rom;
+
+const INSTANCES: usize = 64;
+const FRAMES: u32 = 120;
+
+/// Moves 256 entities with random velocities each update, standing in for
+/// game logic which calls back into the host.
+const GUEST: &str = r#"
+(module
+  (import "env" "random_int_range" (func $random (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+
+  (func (export "update")
+    (local $i i32)
+    (local $address i32)
+    (loop $entities
+      (local.set $address (i32.shl (local.get $i) (i32.const 3)))
+      (i32.store
+        (local.get $address)
+        (i32.add
+          (i32.load (local.get $address))
+          (call $random (i32.const -4) (i32.const 4))))
+      (i32.store offset=4
+        (local.get $address)
+        (i32.add
+          (i32.load offset=4 (local.get $address))
+          (i32.load (local.get $address))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $entities (i32.lt_u (local.get $i) (i32.const 256)))))
+
+  (func (export "render")))
+"#;
+
+fn batch_throughput(c: &mut Criterion) {
+    let rom = match std::env::var_os("NETHERCADE_BENCH_ROM") {
+        Some(path) => load_rom_file(&PathBuf::from(path)).expect("Failed to load bench rom"),
+        None => Rom::from_code(&wat::parse_str(GUEST).unwrap()),
+    };
+
+    let batch = BatchSimulator::new(rom, INSTANCES as u32)
+        .unwrap_or_else(|report| panic!("Failed to load bench rom: {}", report.message));
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+
+    let mut thread_counts = vec![1];
+    if cores > 1 {
+        thread_counts.push(cores);
+    }
+
+    let mut group = c.benchmark_group("batch_throughput");
+    group.sample_size(10);
+    for threads in thread_counts {
+        // Counting the frames each core simulates makes the reported rate
+        // frames per second per core
+        let frames = INSTANCES as u64 * FRAMES as u64;
+        group.throughput(Throughput::Elements(frames / threads as u64));
+        group.bench_with_input(
+            BenchmarkId::new("frames_per_core", format!("{threads}_threads")),
+            &threads,
+            |bencher, &threads| bencher.iter(|| run(&batch, threads)),
+        );
+    }
+    group.finish();
+}
+
+/// Runs every instance for [`FRAMES`], returning the frames simulated.
+fn run(batch: &BatchSimulator, threads: usize) -> u64 {
+    let frames = batch.run_parallel((0..INSTANCES as u64).collect(), threads, |batch, seed| {
+        let mut emulator = batch.instantiate(1, seed).unwrap();
+        for _ in 0..FRAMES {
+            emulator.step().unwrap();
+        }
+        emulator.frame() as u64
+    });
+    frames.iter().sum()
+}
+
+criterion_group!(benches, batch_throughput);
+criterion_main!(benches);
@@ -9,6 +9,12 @@ pub struct AudioUnit {
     _stream: OutputStream,
 }
 
+impl Default for AudioUnit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AudioUnit {
     pub fn append_data(&self, index: usize, channels: u16, data: &[f32], sample_rate: u32) {
         if let Some(sink) = self.sinks.get(index) {
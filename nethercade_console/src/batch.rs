@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use nethercade_core::{Rom, console_spec::MAX_MEMORY_SIZE};
+use rayon::prelude::*;
+use wasmtime::{Engine, InstanceAllocationStrategy, InstancePre, PoolingAllocationConfig};
+
+use crate::{
+    Emulator,
+    console::{Console, CrashReport, GameInstance, WasmContexts},
+};
+
+/// Compiles a ROM once so many headless instances of it can share the
+/// compiled code. Instances come out of a pool sized up front, which keeps
+/// creating and dropping them cheap.
+pub struct BatchSimulator {
+    rom: Arc<Rom>,
+    instance_pre: InstancePre<WasmContexts>,
+}
+
+impl BatchSimulator {
+    /// `max_instances` is how many instances may be alive at the same time.
+    /// Fails if the ROM's code can't be compiled or linked.
+    pub fn new(rom: Rom, max_instances: u32) -> Result<Self, CrashReport> {
+        let mut pooling = PoolingAllocationConfig::default();
+        pooling
+            .total_core_instances(max_instances)
+            .total_memories(max_instances)
+            .total_tables(max_instances)
+            .max_memory_size(MAX_MEMORY_SIZE);
+
+        let mut config = Console::engine_config(false);
+        config.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+        let engine = Engine::new(&config).map_err(|e| CrashReport::new(&e, 0, &[]))?;
+        let instance_pre = Console::link(&engine, &rom)?;
+
+        Ok(Self {
+            rom: Arc::new(rom),
+            instance_pre,
+        })
+    }
+
+    pub fn rom(&self) -> &Rom {
+        &self.rom
+    }
+
+    /// A fresh headless instance, which has already run `init`. Fails if
+    /// the pool is exhausted. Batch instances don't load saved cheats.
+    pub fn instantiate(
+        &self,
+        num_players: usize,
+        random_seed: u64,
+    ) -> Result<Emulator, CrashReport> {
+        GameInstance::new(
+            self.rom.clone(),
+            self.instance_pre.clone(),
+            None,
+            num_players,
+            random_seed,
+        )
+        .map(Emulator::from_game)
+    }
+
+    /// Runs every job on a pool of `threads` threads, returning the results
+    /// in the order of `jobs`. Instances aren't `Send`, so each job creates
+    /// its own through [`Self::instantiate`].
+    pub fn run_parallel<J, R, F>(&self, jobs: Vec<J>, threads: usize, run: F) -> Vec<R>
+    where
+        J: Send,
+        R: Send,
+        F: Fn(&Self, J) -> R + Sync,
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| jobs.into_par_iter().map(|job| run(self, job)).collect())
+    }
+}
@@ -0,0 +1,298 @@
+use std::path::PathBuf;
+
+use nethercade_core::Rom;
+use serde::{Deserialize, Serialize};
+
+use super::value_type::ValueType;
+
+const CHEATS_DIRECTORY: &str = "cheats";
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CheatKind {
+    /// Written once, after the first update following activation.
+    Poke,
+    /// Written after every update while enabled.
+    Freeze,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cheat {
+    pub name: String,
+    pub enabled: bool,
+    pub kind: CheatKind,
+    pub address: usize,
+    pub value_type: ValueType,
+    pub value: String,
+
+    #[serde(skip)]
+    applied: bool,
+}
+
+impl Cheat {
+    pub fn new(
+        name: String,
+        kind: CheatKind,
+        address: usize,
+        value_type: ValueType,
+        value: String,
+    ) -> Self {
+        Self {
+            name,
+            enabled: true,
+            kind,
+            address,
+            value_type,
+            value,
+            applied: false,
+        }
+    }
+
+    /// Re-arms a poke so it is written again on the next frame.
+    pub fn reset(&mut self) {
+        self.applied = false;
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheatFile {
+    cheats: Vec<Cheat>,
+}
+
+/// Per-ROM list of memory pokes and freezes, stored in `cheats/<rom hash>.json`.
+/// Starts out empty, the saved list is only read by [`CheatEngine::load`].
+#[derive(Debug, Default)]
+pub struct CheatEngine {
+    pub cheats: Vec<Cheat>,
+    /// Cheats are only applied when this is set. Networked sessions must leave
+    /// it off since other players would desync.
+    pub enabled: bool,
+    /// Where the cheats were loaded from and are saved to.
+    path: Option<PathBuf>,
+}
+
+impl CheatEngine {
+    pub fn load(rom: &Rom) -> Self {
+        let path = PathBuf::from(CHEATS_DIRECTORY).join(format!("{}.json", rom.hash()));
+
+        let cheats = if path.exists() {
+            match std::fs::read(&path) {
+                Ok(file) => match sonic_rs::from_slice::<CheatFile>(&file) {
+                    Ok(cheat_file) => {
+                        println!("Loaded cheats from: {}", path.display());
+                        cheat_file.cheats
+                    }
+                    Err(e) => {
+                        println!("{} found, but unable to parse: {e}", path.display());
+                        Vec::new()
+                    }
+                },
+                Err(e) => {
+                    println!("{} found, but unable to read: {e}", path.display());
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            cheats,
+            enabled: false,
+            path: Some(path),
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            println!("Cheats weren't loaded from a file, so they can't be saved");
+            return;
+        };
+
+        let cheat_file = CheatFile {
+            cheats: self.cheats.clone(),
+        };
+        let json = sonic_rs::to_string_pretty(&cheat_file).unwrap();
+
+        if let Err(e) = std::fs::create_dir_all(CHEATS_DIRECTORY) {
+            println!("Error creating {CHEATS_DIRECTORY}: {e}");
+            return;
+        }
+
+        match std::fs::write(path, json) {
+            Ok(()) => println!("Saved cheats to: {}", path.display()),
+            Err(e) => println!("Error writing {}: {e}", path.display()),
+        }
+    }
+
+    /// Writes every active cheat into guest memory. Called after `update`.
+    pub fn apply(&mut self, memory: &mut [u8]) {
+        if !self.enabled {
+            return;
+        }
+
+        for cheat in self.cheats.iter_mut() {
+            if !cheat.enabled || (cheat.kind == CheatKind::Poke && cheat.applied) {
+                continue;
+            }
+
+            let Some(bytes) = cheat.value_type.encode(&cheat.value) else {
+                continue;
+            };
+
+            if let Some(target) = memory
+                .get_mut(cheat.address..)
+                .and_then(|arr| arr.get_mut(..bytes.len()))
+            {
+                target.copy_from_slice(&bytes);
+                cheat.applied = true;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SearchFilter {
+    Equal,
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+/// Narrows down which addresses hold a value by comparing memory between
+/// frames, the classic "find value" workflow.
+#[derive(Debug, Default)]
+pub struct CheatSearch {
+    pub value_type: ValueType,
+    pub candidates: Vec<usize>,
+    snapshot: Vec<u8>,
+    started: bool,
+}
+
+impl CheatSearch {
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Starts over with every aligned address as a candidate.
+    pub fn start(&mut self, value_type: ValueType, memory: &[u8]) {
+        let size = value_type.size();
+        let alignment = size.min(4);
+
+        self.value_type = value_type;
+        self.candidates = (0..memory.len().saturating_sub(size - 1))
+            .step_by(alignment)
+            .collect();
+        self.snapshot = memory.to_vec();
+        self.started = true;
+    }
+
+    /// Keeps the candidates matching `filter`. `value` is only used by
+    /// [`SearchFilter::Equal`]. The snapshot is then updated so the next
+    /// comparison is against the current memory.
+    pub fn filter(&mut self, filter: SearchFilter, value: &str, memory: &[u8]) {
+        let value_type = self.value_type;
+        let expected = value_type.encode(value);
+        let snapshot = &self.snapshot;
+
+        self.candidates.retain(|&address| {
+            let size = value_type.size();
+            let Some(current) = memory.get(address..address + size) else {
+                return false;
+            };
+            let Some(previous) = snapshot.get(address..address + size) else {
+                return false;
+            };
+
+            match filter {
+                SearchFilter::Equal => expected.as_deref() == Some(current),
+                SearchFilter::Changed => current != previous,
+                SearchFilter::Unchanged => current == previous,
+                SearchFilter::Increased | SearchFilter::Decreased => {
+                    let (Some(now), Some(before)) = (
+                        value_type.read_scalar(memory, address),
+                        value_type.read_scalar(snapshot, address),
+                    ) else {
+                        return false;
+                    };
+
+                    if filter == SearchFilter::Increased {
+                        now > before
+                    } else {
+                        now < before
+                    }
+                }
+            }
+        });
+
+        self.snapshot = memory.to_vec();
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(value_type: ValueType, memory: &[u8]) -> CheatSearch {
+        let mut search = CheatSearch::default();
+        search.start(value_type, memory);
+        search
+    }
+
+    #[test]
+    fn starts_with_aligned_candidates() {
+        assert_eq!(search(ValueType::U8, &[0; 4]).candidates, [0, 1, 2, 3]);
+        assert_eq!(search(ValueType::I32, &[0; 14]).candidates, [0, 4, 8]);
+        assert_eq!(search(ValueType::F64, &[0; 16]).candidates, [0, 4, 8]);
+    }
+
+    #[test]
+    fn equal_keeps_matching_values() {
+        let mut memory = vec![0; 16];
+        memory[4..8].copy_from_slice(&(-2i32).to_le_bytes());
+        memory[12..16].copy_from_slice(&(-2i32).to_le_bytes());
+
+        let mut search = search(ValueType::I32, &memory);
+        search.filter(SearchFilter::Equal, "-2", &memory);
+        assert_eq!(search.candidates, [4, 12]);
+
+        // Unparsable values match nothing
+        search.filter(SearchFilter::Equal, "x", &memory);
+        assert!(search.candidates.is_empty());
+    }
+
+    #[test]
+    fn changed_and_unchanged_compare_with_the_last_filter() {
+        let mut memory = vec![0; 8];
+        let mut search = search(ValueType::U8, &memory);
+        memory[2] = 1;
+        memory[5] = 1;
+        search.filter(SearchFilter::Changed, "", &memory);
+        assert_eq!(search.candidates, [2, 5]);
+
+        memory[5] = 2;
+        search.filter(SearchFilter::Unchanged, "", &memory);
+        assert_eq!(search.candidates, [2]);
+    }
+
+    #[test]
+    fn increased_and_decreased_compare_numerically() {
+        let mut memory = vec![5, 5, 5, 0x7F];
+        let mut increased = search(ValueType::I8, &memory);
+        let mut decreased = search(ValueType::I8, &memory);
+        memory[0] = 6;
+        memory[1] = 4;
+        // 127 wrapping to -128 is a decrease for a signed type
+        memory[3] = 0x80;
+
+        increased.filter(SearchFilter::Increased, "", &memory);
+        assert_eq!(increased.candidates, [0]);
+        decreased.filter(SearchFilter::Decreased, "", &memory);
+        assert_eq!(decreased.candidates, [1, 3]);
+    }
+}
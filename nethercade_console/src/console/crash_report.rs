@@ -0,0 +1,38 @@
+use wasmtime::{Trap, WasmBacktrace};
+
+use super::{input::PlayerInputEntry, wasm_contexts::GuestAbort};
+
+/// Everything shown to the player when the guest traps or aborts.
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: Option<String>,
+    pub frame: u32,
+    pub inputs: Box<[PlayerInputEntry]>,
+}
+
+impl CrashReport {
+    pub fn new(error: &wasmtime::Error, frame: u32, inputs: &[PlayerInputEntry]) -> Self {
+        let (message, location) = if let Some(abort) = error.downcast_ref::<GuestAbort>() {
+            (
+                abort.message.clone(),
+                abort.location.as_ref().map(ToString::to_string),
+            )
+        } else if let Some(trap) = error.downcast_ref::<Trap>() {
+            (format!("Guest trapped: {trap}"), None)
+        } else {
+            (error.root_cause().to_string(), None)
+        };
+
+        Self {
+            message,
+            location,
+            backtrace: error
+                .downcast_ref::<WasmBacktrace>()
+                .map(ToString::to_string),
+            frame,
+            inputs: inputs.into(),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use egui::{ComboBox, Grid, RichText, ScrollArea, Ui};
+use strum::IntoEnumIterator;
+
+use crate::console::{
+    GameInstance,
+    cheats::{Cheat, CheatKind, CheatSearch, SearchFilter},
+    value_type::{ValueType, parse_address},
+};
+
+const MAX_LISTED_CANDIDATES: usize = 100;
+
+#[derive(Default)]
+pub struct CheatsWindow {
+    new_name: String,
+    new_address: String,
+    new_value: String,
+    new_type: ValueType,
+    new_freeze: bool,
+
+    search: CheatSearch,
+    search_type: ValueType,
+    search_value: String,
+}
+
+impl CheatsWindow {
+    pub(crate) fn draw(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        if !game.cheats.enabled {
+            ui.label(
+                RichText::new("Cheats are disabled in networked sessions.")
+                    .color(egui::Color32::LIGHT_RED),
+            );
+        }
+
+        self.draw_cheat_list(ui, game);
+        ui.separator();
+        self.draw_new_cheat(ui, game);
+        ui.separator();
+        self.draw_search(ui, game);
+    }
+
+    fn draw_cheat_list(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        ui.horizontal(|ui| {
+            ui.label("Cheats:");
+            if ui.button("Save").clicked() {
+                game.cheats.save();
+            }
+        });
+
+        let mut removed = None;
+        Grid::new("cheat list").striped(true).show(ui, |ui| {
+            for (index, cheat) in game.cheats.cheats.iter_mut().enumerate() {
+                if ui.checkbox(&mut cheat.enabled, &cheat.name).changed() {
+                    cheat.reset();
+                }
+                ui.monospace(format!("0x{:08X}", cheat.address));
+                ui.label(cheat.value_type.name());
+                if ui.text_edit_singleline(&mut cheat.value).changed() {
+                    cheat.reset();
+                }
+                let kind = match cheat.kind {
+                    CheatKind::Poke => "Poke",
+                    CheatKind::Freeze => "Freeze",
+                };
+                ui.label(kind);
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(index) = removed {
+            game.cheats.cheats.remove(index);
+        }
+    }
+
+    fn draw_new_cheat(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        ui.label("New Cheat:");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("name"));
+            ui.add(egui::TextEdit::singleline(&mut self.new_address).hint_text("address"));
+        });
+        ui.horizontal(|ui| {
+            value_type_combo(ui, "new cheat type", &mut self.new_type, false);
+            ui.add(egui::TextEdit::singleline(&mut self.new_value).hint_text("value"));
+            ui.checkbox(&mut self.new_freeze, "Freeze");
+
+            let address = parse_address(&self.new_address);
+            let valid = address.is_some() && self.new_type.encode(&self.new_value).is_some();
+            if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                let address = address.unwrap();
+                let name = if self.new_name.is_empty() {
+                    format!("0x{address:X}")
+                } else {
+                    std::mem::take(&mut self.new_name)
+                };
+                let kind = if self.new_freeze {
+                    CheatKind::Freeze
+                } else {
+                    CheatKind::Poke
+                };
+                game.cheats.cheats.push(Cheat::new(
+                    name,
+                    kind,
+                    address,
+                    self.new_type,
+                    self.new_value.clone(),
+                ));
+            }
+        });
+    }
+
+    fn draw_search(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        ui.label("Find Value:");
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!self.search.is_started(), |ui| {
+                value_type_combo(ui, "search type", &mut self.search_type, true);
+            });
+            ui.add(egui::TextEdit::singleline(&mut self.search_value).hint_text("value"));
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("New Search").clicked() {
+                self.search.start(self.search_type, game.memory());
+            }
+
+            ui.add_enabled_ui(self.search.is_started(), |ui| {
+                for (label, filter) in [
+                    ("Equal", SearchFilter::Equal),
+                    ("Changed", SearchFilter::Changed),
+                    ("Unchanged", SearchFilter::Unchanged),
+                    ("Increased", SearchFilter::Increased),
+                    ("Decreased", SearchFilter::Decreased),
+                ] {
+                    if ui.button(label).clicked() {
+                        self.search
+                            .filter(filter, &self.search_value, game.memory());
+                    }
+                }
+
+                if ui.button("Clear").clicked() {
+                    self.search.clear();
+                }
+            });
+        });
+
+        if !self.search.is_started() {
+            return;
+        }
+
+        let count = self.search.candidates.len();
+        ui.label(format!("{count} candidates"));
+        if count > MAX_LISTED_CANDIDATES {
+            return;
+        }
+
+        let value_type = self.search.value_type;
+        let mut new_cheat = None;
+        ScrollArea::vertical()
+            .id_salt("cheat search results")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                Grid::new("cheat search results grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let memory = game.memory();
+                        for address in &self.search.candidates {
+                            ui.monospace(format!("0x{address:08X}"));
+                            let value = value_type.read(memory, *address).unwrap_or_default();
+                            ui.monospace(&value);
+                            if ui.small_button("Add Cheat").clicked() {
+                                new_cheat = Some((*address, value));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some((address, value)) = new_cheat {
+            game.cheats.cheats.push(Cheat::new(
+                format!("0x{address:X}"),
+                CheatKind::Freeze,
+                address,
+                value_type,
+                value,
+            ));
+        }
+    }
+}
+
+fn value_type_combo(ui: &mut Ui, id: &str, value: &mut ValueType, scalar_only: bool) {
+    ComboBox::from_id_salt(id)
+        .selected_text(value.name())
+        .show_ui(ui, |ui| {
+            for value_type in ValueType::iter().filter(|ty| !scalar_only || ty.is_scalar()) {
+                ui.selectable_value(value, value_type, value_type.name());
+            }
+        });
+}
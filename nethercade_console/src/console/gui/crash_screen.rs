@@ -0,0 +1,71 @@
+use egui::{Color32, RichText, ScrollArea, Ui};
+use strum::IntoEnumIterator;
+
+use crate::console::{
+    CrashReport,
+    input::{ButtonCode, PlayerInputEntry},
+};
+
+pub struct CrashScreen {
+    pub(crate) report: CrashReport,
+}
+
+impl CrashScreen {
+    pub fn new(report: CrashReport) -> Self {
+        Self { report }
+    }
+
+    /// Draws the error screen. Returns true once the player dismisses it.
+    pub fn draw(&self, ui: &mut Ui) -> bool {
+        let report = &self.report;
+
+        ui.heading(RichText::new("The game has stopped").color(Color32::LIGHT_RED));
+        ui.separator();
+
+        ui.label(RichText::new(&report.message).strong());
+        if let Some(location) = &report.location {
+            ui.label(format!("at {location}"));
+        }
+        ui.label(format!("Frame: {}", report.frame));
+
+        ui.group(|ui| {
+            ui.label("Player Inputs:");
+            for (index, entry) in report.inputs.iter().enumerate() {
+                ui.label(format!("Player {}: {}", index + 1, describe_input(entry)));
+            }
+        });
+
+        if let Some(backtrace) = &report.backtrace {
+            ui.collapsing("Backtrace", |ui| {
+                ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.monospace(backtrace);
+                });
+            });
+        }
+
+        ui.separator();
+        ui.button("Back").clicked()
+    }
+}
+
+fn describe_input(entry: &PlayerInputEntry) -> String {
+    let input = &entry.current;
+    let held = ButtonCode::iter()
+        .filter(|code| input.buttons.get_button_state(*code))
+        .map(|code| format!("{code:?}"))
+        .collect::<Vec<_>>();
+    let mouse = entry.current_mouse;
+
+    format!(
+        "buttons [{}], left stick ({:.2}, {:.2}), right stick ({:.2}, {:.2}), triggers ({:.2}, {:.2}), mouse ({}, {})",
+        held.join(", "),
+        input.left_stick.get_x_axis(),
+        input.left_stick.get_y_axis(),
+        input.right_stick.get_x_axis(),
+        input.right_stick.get_y_axis(),
+        input.left_trigger.get_value(),
+        input.right_trigger.get_value(),
+        mouse.get_x_pos(),
+        mouse.get_y_pos(),
+    )
+}
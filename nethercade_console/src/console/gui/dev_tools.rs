@@ -0,0 +1,99 @@
+use egui::{Context, Window};
+
+use crate::console::{CrashReport, GameInstance};
+
+use super::{
+    cheats_window::CheatsWindow, memory_inspector::MemoryInspector, movie_window::MovieWindow,
+    profiler_window::ProfilerWindow, timeline_window::TimelineWindow,
+};
+
+/// Debugging windows for the running game, toggled with F12.
+#[derive(Default)]
+pub struct DevTools {
+    pub open: bool,
+    pub paused: bool,
+
+    show_memory_inspector: bool,
+    memory_inspector: MemoryInspector,
+
+    show_cheats: bool,
+    cheats: CheatsWindow,
+
+    show_profiler: bool,
+    profiler: ProfilerWindow,
+
+    show_timeline: bool,
+    timeline: TimelineWindow,
+
+    show_movie: bool,
+    movie: MovieWindow,
+}
+
+impl DevTools {
+    /// `can_pause` should be false when remote players are connected, since
+    /// halting the simulation would stall them. Returns a report if the game
+    /// crashed while a tool was driving it.
+    pub fn draw(
+        &mut self,
+        ctx: &Context,
+        game: &mut GameInstance,
+        can_pause: bool,
+    ) -> Option<CrashReport> {
+        if !can_pause {
+            self.paused = false;
+        }
+
+        Window::new("Dev Tools")
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Frame: {}", game.frame));
+                ui.add_enabled(can_pause, egui::Checkbox::new(&mut self.paused, "Paused"));
+                ui.separator();
+                ui.checkbox(&mut self.show_memory_inspector, "Memory Inspector");
+                ui.checkbox(&mut self.show_cheats, "Cheats");
+                ui.checkbox(&mut self.show_profiler, "Profiler");
+                ui.checkbox(&mut self.show_timeline, "Timeline");
+                ui.checkbox(&mut self.show_movie, "Movie");
+            });
+
+        game.keep_previous_memory(self.open && self.show_memory_inspector);
+        if !self.open {
+            return None;
+        }
+
+        Window::new("Memory Inspector")
+            .open(&mut self.show_memory_inspector)
+            .default_size([900.0, 500.0])
+            .show(ctx, |ui| self.memory_inspector.draw(ui, game));
+
+        Window::new("Cheats")
+            .open(&mut self.show_cheats)
+            .default_width(500.0)
+            .show(ctx, |ui| self.cheats.draw(ui, game));
+
+        Window::new("Profiler")
+            .open(&mut self.show_profiler)
+            .default_width(500.0)
+            .show(ctx, |ui| self.profiler.draw(ui, game));
+
+        let mut result = Ok(());
+        Window::new("Timeline")
+            .open(&mut self.show_timeline)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                result = self.timeline.draw(ui, game, &mut self.paused);
+            });
+
+        if result.is_ok() {
+            Window::new("Movie")
+                .open(&mut self.show_movie)
+                .default_width(500.0)
+                .show(ctx, |ui| {
+                    result = self.movie.draw(ui, game, &mut self.paused);
+                });
+        }
+
+        result.err().map(|e| game.crash_report(&e))
+    }
+}
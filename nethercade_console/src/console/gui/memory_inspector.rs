@@ -0,0 +1,356 @@
+use egui::{Color32, ComboBox, Grid, RichText, ScrollArea, TextStyle, Ui};
+use strum::IntoEnumIterator;
+use wasmtime::Val;
+
+use crate::console::{
+    GameInstance,
+    value_type::{ValueType, parse_address},
+};
+
+const BYTES_PER_ROW: usize = 16;
+const MAX_SEARCH_RESULTS: usize = 256;
+
+const CHANGED_COLOR: Color32 = Color32::from_rgb(255, 140, 60);
+const MATCH_COLOR: Color32 = Color32::from_rgb(90, 200, 255);
+
+struct Watch {
+    label: String,
+    address: usize,
+    value_type: ValueType,
+}
+
+#[derive(Default)]
+pub struct MemoryInspector {
+    // Memory as it is now and before the last simulated frame, used to
+    // highlight what that frame changed
+    current: Vec<u8>,
+    previous: Vec<u8>,
+    seen_frame: Option<u32>,
+
+    goto_text: String,
+    scroll_to_row: Option<usize>,
+
+    search_text: String,
+    // Whether the search text is hex bytes rather than text to find as is
+    search_hex: bool,
+    search_invalid: bool,
+    search_len: usize,
+    search_results: Vec<usize>,
+
+    watches: Vec<Watch>,
+    watch_label: String,
+    watch_address: String,
+    watch_type: ValueType,
+}
+
+impl MemoryInspector {
+    pub(crate) fn draw(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        let globals = game.exported_globals();
+        self.track_frame(game);
+
+        ui.columns(2, |columns| {
+            self.draw_hex_view(&mut columns[0]);
+
+            ScrollArea::vertical()
+                .id_salt("memory inspector side panel")
+                .show(&mut columns[1], |ui| {
+                    self.draw_search(ui);
+                    ui.separator();
+                    self.draw_watches(ui);
+                    ui.separator();
+                    draw_globals(ui, &globals);
+                });
+        });
+    }
+
+    /// Copies memory once per simulated frame, taking what it was before
+    /// that frame from the game so changes stay right however many frames
+    /// ran since the last draw. Changes stay highlighted while paused.
+    fn track_frame(&mut self, game: &mut GameInstance) {
+        game.keep_previous_memory(true);
+        let frame = game.frame;
+        let stepped = self.seen_frame != Some(frame);
+        let memory = game.memory();
+        if !stepped && self.current.len() == memory.len() {
+            return;
+        }
+
+        self.current.clear();
+        self.current.extend_from_slice(memory);
+        if stepped && let Some(previous) = &mut game.previous_memory {
+            std::mem::swap(&mut self.previous, previous);
+        }
+        if self.previous.len() != self.current.len() {
+            self.previous = self.current.clone();
+        }
+        self.seen_frame = Some(frame);
+    }
+
+    fn draw_hex_view(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} bytes", self.current.len()));
+            ui.separator();
+            ui.label("Go to:");
+            let response = ui.text_edit_singleline(&mut self.goto_text);
+            let go = response.lost_focus() || ui.button("Go").clicked();
+            if let Some(address) = parse_address(&self.goto_text).filter(|_| go) {
+                self.scroll_to_row = Some(address / BYTES_PER_ROW);
+            }
+        });
+
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        let total_rows = self.current.len().div_ceil(BYTES_PER_ROW);
+
+        let mut scroll = ScrollArea::vertical()
+            .id_salt("memory inspector hex view")
+            .auto_shrink(false);
+        if let Some(row) = self.scroll_to_row.take() {
+            let spacing = ui.spacing().item_spacing.y;
+            scroll = scroll.vertical_scroll_offset(row as f32 * (row_height + spacing));
+        }
+
+        scroll.show_rows(ui, row_height, total_rows, |ui, rows| {
+            for row in rows {
+                self.draw_hex_row(ui, row * BYTES_PER_ROW);
+            }
+        });
+    }
+
+    fn draw_hex_row(&self, ui: &mut Ui, start: usize) {
+        let end = (start + BYTES_PER_ROW).min(self.current.len());
+
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 4.0;
+            ui.label(RichText::new(format!("{start:08X}")).monospace().weak());
+
+            for address in start..end {
+                let byte = self.current[address];
+                let mut text = RichText::new(format!("{byte:02X}")).monospace();
+                if self.is_search_match(address) {
+                    text = text.color(Color32::BLACK).background_color(MATCH_COLOR);
+                } else if self.previous.get(address) != Some(&byte) {
+                    text = text.color(CHANGED_COLOR);
+                }
+                ui.label(text);
+            }
+
+            let ascii = self.current[start..end]
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii_graphic() {
+                        *byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            ui.label(RichText::new(ascii).monospace().weak());
+        });
+    }
+
+    fn is_search_match(&self, address: usize) -> bool {
+        if self.search_len == 0 {
+            return false;
+        }
+
+        // Results are sorted, so find the last match starting at or before address
+        let index = self
+            .search_results
+            .partition_point(|start| *start <= address);
+        index > 0 && address < self.search_results[index - 1] + self.search_len
+    }
+
+    fn draw_search(&mut self, ui: &mut Ui) {
+        ui.label("Search:");
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(&mut self.search_text);
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let toggled = ui
+                .checkbox(&mut self.search_hex, "Hex")
+                .on_hover_text("Search for bytes like \"DE AD\" instead of text")
+                .changed();
+            if submitted || toggled || ui.button("Find").clicked() {
+                self.run_search();
+            }
+        });
+
+        if self.search_invalid {
+            ui.label(RichText::new("Expected pairs of hex digits").color(CHANGED_COLOR));
+        }
+
+        if self.search_len > 0 {
+            let count = self.search_results.len();
+            if count == MAX_SEARCH_RESULTS {
+                ui.label(format!("First {count} matches:"));
+            } else {
+                ui.label(format!("{count} matches:"));
+            }
+        }
+
+        let mut jump = None;
+        ScrollArea::vertical()
+            .id_salt("memory inspector search results")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                for address in &self.search_results {
+                    if ui.link(format!("0x{address:08X}")).clicked() {
+                        jump = Some(*address);
+                    }
+                }
+            });
+
+        if let Some(address) = jump {
+            self.scroll_to_row = Some(address / BYTES_PER_ROW);
+        }
+    }
+
+    fn run_search(&mut self) {
+        let pattern = if self.search_hex {
+            parse_hex_pattern(&self.search_text)
+        } else {
+            Some(self.search_text.as_bytes().to_vec())
+        };
+        self.search_invalid = pattern.is_none();
+        let pattern = pattern.unwrap_or_default();
+
+        self.search_len = pattern.len();
+        self.search_results = if pattern.is_empty() {
+            Vec::new()
+        } else {
+            self.current
+                .windows(pattern.len())
+                .enumerate()
+                .filter(|(_, window)| *window == pattern.as_slice())
+                .map(|(address, _)| address)
+                .take(MAX_SEARCH_RESULTS)
+                .collect()
+        };
+    }
+
+    fn draw_watches(&mut self, ui: &mut Ui) {
+        ui.label("Watches:");
+
+        let mut removed = None;
+        Grid::new("memory inspector watches")
+            .striped(true)
+            .show(ui, |ui| {
+                for (index, watch) in self.watches.iter().enumerate() {
+                    ui.label(&watch.label);
+                    ui.monospace(format!("0x{:08X}", watch.address));
+                    ui.label(watch.value_type.name());
+                    let value = watch
+                        .value_type
+                        .read(&self.current, watch.address)
+                        .unwrap_or_else(|| String::from("out of bounds"));
+                    let changed = watch.value_type.read(&self.previous, watch.address);
+                    if changed.as_ref() != Some(&value) {
+                        ui.monospace(RichText::new(value).color(CHANGED_COLOR));
+                    } else {
+                        ui.monospace(value);
+                    }
+                    if ui.small_button("x").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = removed {
+            self.watches.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.watch_label).hint_text("label"));
+            ui.add(egui::TextEdit::singleline(&mut self.watch_address).hint_text("address"));
+        });
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("memory inspector watch type")
+                .selected_text(self.watch_type.name())
+                .show_ui(ui, |ui| {
+                    for value_type in ValueType::iter() {
+                        ui.selectable_value(&mut self.watch_type, value_type, value_type.name());
+                    }
+                });
+
+            let address = parse_address(&self.watch_address);
+            if ui
+                .add_enabled(address.is_some(), egui::Button::new("Add Watch"))
+                .clicked()
+            {
+                let address = address.unwrap();
+                let label = if self.watch_label.is_empty() {
+                    format!("0x{address:X}")
+                } else {
+                    std::mem::take(&mut self.watch_label)
+                };
+                self.watches.push(Watch {
+                    label,
+                    address,
+                    value_type: self.watch_type,
+                });
+            }
+        });
+    }
+}
+
+fn draw_globals(ui: &mut Ui, globals: &[(String, Val)]) {
+    ui.label("Exported Globals:");
+    if globals.is_empty() {
+        ui.label(RichText::new("none").weak());
+        return;
+    }
+
+    Grid::new("memory inspector globals")
+        .striped(true)
+        .show(ui, |ui| {
+            for (name, value) in globals {
+                ui.label(name);
+                ui.monospace(format_val(value));
+                ui.end_row();
+            }
+        });
+}
+
+fn format_val(value: &Val) -> String {
+    match value {
+        Val::I32(v) => format!("i32 {v} (0x{v:X})"),
+        Val::I64(v) => format!("i64 {v} (0x{v:X})"),
+        Val::F32(bits) => format!("f32 {}", f32::from_bits(*bits)),
+        Val::F64(bits) => format!("f64 {}", f64::from_bits(*bits)),
+        Val::V128(v) => format!("v128 0x{:032X}", v.as_u128()),
+        _ => String::from("reference"),
+    }
+}
+
+/// Parses whitespace separated hex bytes. Returns `None` if the text is not hex.
+fn parse_hex_pattern(text: &str) -> Option<Vec<u8>> {
+    let digits = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    if digits.is_empty() || digits.len() % 2 == 1 || !digits.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    digits
+        .chunks_exact(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_patterns() {
+        assert_eq!(
+            parse_hex_pattern("DE ad\tbe EF"),
+            Some(vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+        assert_eq!(parse_hex_pattern("cafe"), Some(vec![0xCA, 0xFE]));
+        for invalid in ["", "abc", "xy", "aé1", "ff€", "€€"] {
+            assert_eq!(parse_hex_pattern(invalid), None, "{invalid:?}");
+        }
+    }
+}
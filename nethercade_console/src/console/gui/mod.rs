@@ -1,2 +1,24 @@
 mod play_mode_gui;
 pub use play_mode_gui::PlayModeGui;
+
+mod rom_details;
+pub use rom_details::{RomDetails, display_title};
+
+mod crash_screen;
+pub use crash_screen::CrashScreen;
+
+mod dev_tools;
+pub use dev_tools::DevTools;
+
+mod memory_inspector;
+
+mod performance_hud;
+pub use performance_hud::PerformanceHud;
+
+mod cheats_window;
+
+mod profiler_window;
+
+mod timeline_window;
+
+mod movie_window;
@@ -0,0 +1,375 @@
+use egui::{Color32, DragValue, RichText, ScrollArea, SelectableLabel, Ui};
+use strum::IntoEnumIterator;
+
+use crate::console::{
+    GameInstance,
+    input::{ButtonCode, InputState},
+    movie::{MOVIE_FILE_EXTENSION, Movie, MovieMode, MovieSession},
+};
+
+const FRAME_WIDTH: f32 = 56.0;
+const CELL_WIDTH: f32 = 22.0;
+
+#[derive(Default)]
+pub struct MovieWindow {
+    player: usize,
+    selected: u32,
+    error: Option<String>,
+}
+
+impl MovieWindow {
+    /// Returns an error if restarting or rewinding the game failed.
+    pub(crate) fn draw(
+        &mut self,
+        ui: &mut Ui,
+        game: &mut GameInstance,
+        paused: &mut bool,
+    ) -> Result<(), wasmtime::Error> {
+        if !game.timeline.enabled {
+            ui.label(
+                RichText::new("Movies are disabled in networked sessions.")
+                    .color(Color32::LIGHT_RED),
+            );
+            return Ok(());
+        }
+
+        self.draw_file_controls(ui, game)?;
+        if let Some(error) = &self.error {
+            ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+        }
+
+        if game.movie.is_none() {
+            return Ok(());
+        }
+
+        ui.separator();
+        self.draw_mode_controls(ui, game, paused)?;
+        ui.separator();
+
+        let Some(edited) = self.draw_piano_roll(ui, game) else {
+            return Ok(());
+        };
+
+        // Rewind so the edit is simulated, playing the movie from there on
+        // since recording would overwrite the edit with live inputs.
+        let session = game.movie.as_mut().unwrap();
+        if session.mode != MovieMode::Stopped && edited < game.frame {
+            session.mode = MovieMode::Playing;
+            *paused = true;
+            game.rewind_for_movie_edit(edited)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_file_controls(
+        &mut self,
+        ui: &mut Ui,
+        game: &mut GameInstance,
+    ) -> Result<(), wasmtime::Error> {
+        let mut result = Ok(());
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("Record New")
+                .on_hover_text("Restart the game and record every input")
+                .clicked()
+            {
+                self.error = None;
+                result = game.reset(game.random_seed);
+                game.movie = Some(MovieSession {
+                    movie: Movie::new(&game.rom, game.num_players(), game.random_seed),
+                    mode: MovieMode::Recording,
+                });
+            }
+
+            let load = ui.button("Load").clicked();
+            if let Some(path) = load.then(|| movie_dialog().pick_file()).flatten() {
+                result = self.load(&path, game);
+            }
+
+            let Some(session) = &game.movie else {
+                return;
+            };
+
+            let save = ui.button("Save").clicked();
+            let dialog = || {
+                movie_dialog()
+                    .set_file_name(format!("movie.{MOVIE_FILE_EXTENSION}"))
+                    .save_file()
+            };
+            if let Some(path) = save.then(dialog).flatten() {
+                self.error = session.movie.save(&path).err();
+            }
+
+            if ui.button("Close").clicked() {
+                game.movie = None;
+            }
+        });
+
+        result
+    }
+
+    fn load(
+        &mut self,
+        path: &std::path::Path,
+        game: &mut GameInstance,
+    ) -> Result<(), wasmtime::Error> {
+        let movie = match Movie::load(path).and_then(|movie| {
+            movie.check(&game.rom, game.num_players())?;
+            Ok(movie)
+        }) {
+            Ok(movie) => movie,
+            Err(e) => {
+                self.error = Some(format!("Failed to load movie: {e}"));
+                return Ok(());
+            }
+        };
+
+        self.error = None;
+        self.selected = 0;
+        game.reset(movie.random_seed)?;
+        game.movie = Some(MovieSession {
+            movie,
+            mode: MovieMode::Playing,
+        });
+        Ok(())
+    }
+
+    fn draw_mode_controls(
+        &mut self,
+        ui: &mut Ui,
+        game: &mut GameInstance,
+        paused: &mut bool,
+    ) -> Result<(), wasmtime::Error> {
+        let session = game.movie.as_ref().unwrap();
+        ui.label(format!(
+            "{:?}, frame {} of {}",
+            session.mode,
+            game.frame,
+            session.movie.len()
+        ));
+
+        let mut result = Ok(());
+        ui.horizontal(|ui| {
+            if ui.button("Play From Start").clicked() {
+                let seed = game.movie.as_ref().unwrap().movie.random_seed;
+                result = game.reset(seed);
+                game.movie.as_mut().unwrap().mode = MovieMode::Playing;
+                *paused = false;
+            }
+
+            let session = game.movie.as_mut().unwrap();
+            if ui
+                .button("Re-record Here")
+                .on_hover_text("Record live inputs, replacing the movie after this frame")
+                .clicked()
+            {
+                session.mode = MovieMode::Recording;
+            }
+
+            if ui.button("Stop").clicked() {
+                session.mode = MovieMode::Stopped;
+            }
+        });
+
+        result
+    }
+
+    /// Draws the editable inputs of the selected player. Returns the first
+    /// frame which was changed.
+    fn draw_piano_roll(&mut self, ui: &mut Ui, game: &mut GameInstance) -> Option<u32> {
+        let current_frame = game.frame;
+        let num_players = game.num_players();
+        let movie = &mut game.movie.as_mut().unwrap().movie;
+        let mut edited = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Player");
+            ui.add(DragValue::new(&mut self.player).range(0..=num_players - 1));
+        });
+        self.player = self.player.min(num_players - 1);
+
+        if movie.is_empty() {
+            ui.label(RichText::new("No frames recorded").weak());
+            return None;
+        }
+        self.selected = self.selected.min(movie.len() - 1);
+
+        ui.horizontal(|ui| {
+            ui.add_sized([FRAME_WIDTH, 0.0], egui::Label::new("Frame"));
+            for code in ButtonCode::iter() {
+                ui.add_sized([CELL_WIDTH, 0.0], egui::Label::new(button_label(code)))
+                    .on_hover_text(format!("{code:?}"));
+            }
+        });
+
+        let row_height = ui.spacing().interact_size.y;
+        ScrollArea::vertical()
+            .id_salt("movie piano roll")
+            .max_height(300.0)
+            .show_rows(ui, row_height, movie.inputs.len(), |ui, rows| {
+                for row in rows {
+                    let frame = row as u32;
+                    ui.horizontal(|ui| {
+                        let mut text = RichText::new(frame.to_string()).monospace();
+                        if frame == current_frame {
+                            text = text.color(Color32::YELLOW);
+                        }
+                        if ui
+                            .add_sized(
+                                [FRAME_WIDTH, row_height],
+                                SelectableLabel::new(frame == self.selected, text),
+                            )
+                            .clicked()
+                        {
+                            self.selected = frame;
+                        }
+
+                        let buttons = &mut movie.inputs[row][self.player].input_state.buttons;
+                        for code in ButtonCode::iter() {
+                            let pressed = buttons.get_button_state(code);
+                            if ui
+                                .add_sized(
+                                    [CELL_WIDTH, row_height],
+                                    SelectableLabel::new(pressed, ""),
+                                )
+                                .clicked()
+                            {
+                                buttons.set_button_state(code, !pressed);
+                                edited = Some(frame);
+                            }
+                        }
+                    });
+                }
+            });
+
+        ui.separator();
+        let selected = self.selected as usize;
+        ui.label(format!("Frame {}", self.selected));
+        if draw_analog_inputs(ui, &mut movie.inputs[selected][self.player].input_state) {
+            edited = Some(self.selected);
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("Insert Frame")
+                .on_hover_text("Duplicate the selected frame")
+                .clicked()
+            {
+                let inputs = movie.inputs[selected].clone();
+                movie.inputs.insert(selected + 1, inputs);
+                edited = Some(self.selected + 1);
+            }
+
+            if ui.button("Delete Frame").clicked() {
+                movie.inputs.remove(selected);
+                self.selected = self.selected.saturating_sub(1);
+                edited = Some(selected as u32);
+            }
+        });
+
+        edited
+    }
+}
+
+/// Returns true if any value was changed.
+fn draw_analog_inputs(ui: &mut Ui, input: &mut InputState) -> bool {
+    let mut changed = false;
+
+    let mut stick = |ui: &mut Ui, label: &str, x: f32, y: f32| -> Option<(f32, f32)> {
+        let (mut x, mut y) = (x, y);
+        ui.horizontal(|ui| {
+            ui.label(label);
+            let x_changed = ui
+                .add(
+                    DragValue::new(&mut x)
+                        .range(-1.0..=1.0)
+                        .speed(0.01)
+                        .prefix("x: "),
+                )
+                .changed();
+            let y_changed = ui
+                .add(
+                    DragValue::new(&mut y)
+                        .range(-1.0..=1.0)
+                        .speed(0.01)
+                        .prefix("y: "),
+                )
+                .changed();
+            changed |= x_changed || y_changed;
+            (x_changed || y_changed).then_some((x, y))
+        })
+        .inner
+    };
+
+    let left = input.left_stick;
+    if let Some((x, y)) = stick(ui, "Left Stick", left.get_x_axis(), left.get_y_axis()) {
+        input.left_stick.set_x_axis(x);
+        input.left_stick.set_y_axis(y);
+    }
+
+    let right = input.right_stick;
+    if let Some((x, y)) = stick(ui, "Right Stick", right.get_x_axis(), right.get_y_axis()) {
+        input.right_stick.set_x_axis(x);
+        input.right_stick.set_y_axis(y);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Triggers");
+        let mut left = input.left_trigger.get_value();
+        if ui
+            .add(
+                DragValue::new(&mut left)
+                    .range(0.0..=1.0)
+                    .speed(0.01)
+                    .prefix("l: "),
+            )
+            .changed()
+        {
+            input.left_trigger.set_value(left);
+            changed = true;
+        }
+
+        let mut right = input.right_trigger.get_value();
+        if ui
+            .add(
+                DragValue::new(&mut right)
+                    .range(0.0..=1.0)
+                    .speed(0.01)
+                    .prefix("r: "),
+            )
+            .changed()
+        {
+            input.right_trigger.set_value(right);
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+fn movie_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("nzmovie (.nzmovie)", &[MOVIE_FILE_EXTENSION])
+}
+
+fn button_label(code: ButtonCode) -> &'static str {
+    match code {
+        ButtonCode::Up => "Up",
+        ButtonCode::Down => "Dn",
+        ButtonCode::Left => "Lt",
+        ButtonCode::Right => "Rt",
+        ButtonCode::ButtonA => "A",
+        ButtonCode::ButtonB => "B",
+        ButtonCode::ButtonC => "C",
+        ButtonCode::ButtonD => "D",
+        ButtonCode::Start => "St",
+        ButtonCode::Select => "Se",
+        ButtonCode::LeftShoulder => "LB",
+        ButtonCode::RightShoulder => "RB",
+        ButtonCode::LeftStick => "LS",
+        ButtonCode::RightStick => "RS",
+        ButtonCode::LeftTrigger => "LT",
+        ButtonCode::RightTrigger => "RT",
+    }
+}
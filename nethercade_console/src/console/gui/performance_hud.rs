@@ -0,0 +1,190 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use egui::{Align2, Area, Color32, Context, Frame, Id, ProgressBar, Sense, Stroke, Ui, pos2, vec2};
+
+use crate::{
+    console::{GameInstance, wasm_contexts::FrameTimings},
+    graphics::{IMMEDIATE_BUFFER_SIZE, virtual_render_pass::RenderPassStats},
+};
+
+const GRAPH_HISTORY: Duration = Duration::from_secs(5);
+const GRAPH_SIZE: [f32; 2] = [240.0, 48.0];
+
+const BUDGET_COLOR: Color32 = Color32::from_rgb(90, 200, 90);
+const FRAME_COLOR: Color32 = Color32::from_rgb(255, 200, 60);
+const WASM_COLOR: Color32 = Color32::from_rgb(90, 170, 255);
+
+struct Sample {
+    time: Instant,
+    timings: FrameTimings,
+    stats: RenderPassStats,
+}
+
+/// Performance overlay drawn over the game, toggled with F3.
+#[derive(Default)]
+pub struct PerformanceHud {
+    pub open: bool,
+    samples: VecDeque<Sample>,
+    snapshot_bytes: usize,
+}
+
+impl PerformanceHud {
+    /// Collects the stats of the game frame which was just rendered.
+    pub fn record_frame(&mut self, game: &mut GameInstance) {
+        if !self.open {
+            return;
+        }
+
+        let time = Instant::now();
+        let timings = game.profiler().history.back().copied().unwrap_or_default();
+        if timings.snapshot_bytes > 0 {
+            self.snapshot_bytes = timings.snapshot_bytes;
+        }
+
+        self.samples.push_back(Sample {
+            time,
+            timings,
+            stats: game.store.data().draw_3d.last_stats,
+        });
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| time - sample.time > GRAPH_HISTORY)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn draw(&mut self, ctx: &Context, game: &GameInstance) {
+        if !self.open {
+            self.samples.clear();
+            return;
+        }
+
+        Area::new(Id::new("performance hud"))
+            .anchor(Align2::LEFT_TOP, vec2(8.0, 8.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::popup(ui.style())
+                    .fill(Color32::from_black_alpha(200))
+                    .show(ui, |ui| self.draw_contents(ui, game));
+            });
+    }
+
+    fn draw_contents(&self, ui: &mut Ui, game: &GameInstance) {
+        let target_fps = game.rom.frame_rate.frames_per_second();
+        let budget_ms = game.rom.frame_rate.frame_time() * 1000.0;
+
+        // Everything is averaged over the last second
+        let now = Instant::now();
+        let last_second = self
+            .samples
+            .iter()
+            .filter(|sample| now - sample.time <= Duration::from_secs(1))
+            .collect::<Vec<_>>();
+        let count = last_second.len().max(1) as f32;
+        let average_ms = |time: fn(&FrameTimings) -> Duration| {
+            last_second
+                .iter()
+                .map(|sample| time(&sample.timings).as_secs_f32())
+                .sum::<f32>()
+                * 1000.0
+                / count
+        };
+        let rollback_frames = last_second
+            .iter()
+            .map(|sample| sample.timings.rollback_frames)
+            .sum::<u32>();
+
+        let fps = last_second.len();
+        let fps_color = if fps + 1 < target_fps {
+            Color32::LIGHT_RED
+        } else {
+            Color32::WHITE
+        };
+        ui.colored_label(fps_color, format!("FPS: {fps} / {target_fps}"));
+        ui.label(format!(
+            "Update: {:.2} ms  Render: {:.2} ms",
+            average_ms(|t| t.update),
+            average_ms(|t| t.render),
+        ));
+        ui.label(format!(
+            "Snapshot: {:.1} KB in {:.2} ms",
+            self.snapshot_bytes as f32 / 1024.0,
+            average_ms(|t| t.snapshot),
+        ));
+        ui.label(format!("Rollback frames/s: {rollback_frames}"));
+
+        let stats = self
+            .samples
+            .back()
+            .map(|sample| sample.stats)
+            .unwrap_or_default();
+        ui.label(format!(
+            "Draws: {}  Vertices: {}",
+            stats.draw_commands, stats.vertices
+        ));
+
+        let usage = stats.immediate_bytes as f32 / IMMEDIATE_BUFFER_SIZE as f32;
+        ui.add(
+            ProgressBar::new(usage)
+                .desired_width(GRAPH_SIZE[0])
+                .text(format!(
+                    "Immediate: {:.2} / {} MB",
+                    stats.immediate_bytes as f32 / (1024.0 * 1024.0),
+                    IMMEDIATE_BUFFER_SIZE / (1024 * 1024)
+                )),
+        );
+
+        ui.add_space(4.0);
+        ui.colored_label(FRAME_COLOR, "Frame interval (ms)");
+        let intervals = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(previous, sample)| (sample.time - previous.time).as_secs_f32() * 1000.0)
+            .collect::<Vec<_>>();
+        draw_graph(ui, &intervals, budget_ms, FRAME_COLOR);
+
+        ui.colored_label(WASM_COLOR, "Update + render (ms)");
+        let wasm = self
+            .samples
+            .iter()
+            .map(|sample| (sample.timings.update + sample.timings.render).as_secs_f32() * 1000.0)
+            .collect::<Vec<_>>();
+        draw_graph(ui, &wasm, budget_ms, WASM_COLOR);
+    }
+}
+
+/// Line graph scaled so the frame budget sits in the middle, which keeps
+/// hitches visible as spikes above the budget line.
+fn draw_graph(ui: &mut Ui, values: &[f32], budget: f32, color: Color32) {
+    let (rect, _) = ui.allocate_exact_size(vec2(GRAPH_SIZE[0], GRAPH_SIZE[1]), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, Color32::from_black_alpha(120));
+
+    let max = values.iter().copied().fold(budget * 2.0, f32::max);
+    let to_y = |value: f32| rect.bottom() - (value / max).min(1.0) * rect.height();
+
+    let budget_y = to_y(budget);
+    painter.line_segment(
+        [pos2(rect.left(), budget_y), pos2(rect.right(), budget_y)],
+        Stroke::new(1.0, BUDGET_COLOR),
+    );
+
+    if values.len() < 2 {
+        return;
+    }
+
+    let step = rect.width() / (values.len() - 1) as f32;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| pos2(rect.left() + index as f32 * step, to_y(*value)))
+        .collect::<Vec<_>>();
+    painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
+}
@@ -43,7 +43,7 @@ pub struct PlayModeGui {
 }
 
 impl PlayModeGui {
-    pub(crate) fn draw(&mut self, ui: &mut Ui) {
+    pub fn draw(&mut self, ui: &mut Ui) {
         ui.group(|ui| {
             ui.label("Play Mode:");
             ui.horizontal(|ui| {
@@ -87,7 +87,19 @@ impl PlayModeGui {
         });
     }
 
-    pub(crate) fn generate_session_descriptor(
+    pub fn is_networked(&self) -> bool {
+        matches!(self.play_mode, PlayMode::Networked(..))
+    }
+
+    /// Players a session started now would have, local and remote.
+    pub fn num_players(&self, local_player_count: usize) -> usize {
+        match &self.play_mode {
+            PlayMode::Local => local_player_count,
+            PlayMode::Networked(networked) => local_player_count + networked.remote_player_count,
+        }
+    }
+
+    pub fn generate_session_descriptor(
         &self,
         local_player_count: usize,
     ) -> Option<SessionDescriptor> {
@@ -0,0 +1,190 @@
+use std::{cmp::Reverse, time::Duration};
+
+use egui::{DragValue, Grid, RichText, ScrollArea, Ui};
+
+use crate::console::{
+    GameInstance,
+    wasm_contexts::{FrameTimings, ImportStats},
+};
+
+type TimingRow = (&'static str, fn(&FrameTimings) -> Duration);
+
+pub struct ProfilerWindow {
+    record_frames: u32,
+}
+
+impl Default for ProfilerWindow {
+    fn default() -> Self {
+        Self { record_frames: 300 }
+    }
+}
+
+impl ProfilerWindow {
+    pub(crate) fn draw(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        self.draw_recording(ui, game);
+        ui.separator();
+
+        let profiler = game.profiler();
+        let last = profiler.history.back().copied().unwrap_or_default();
+        let average = average_timings(profiler.history.iter());
+        draw_frame_timings(ui, &last, &average, profiler.history.len());
+        ui.separator();
+
+        let mut enabled = profiler.is_enabled();
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut enabled, "Collect import statistics")
+                .changed()
+            {
+                game.set_profiling(enabled);
+            }
+            if ui.button("Reset").clicked() {
+                game.profiler().reset_imports();
+            }
+        });
+
+        let profiler = game.profiler();
+
+        let mut imports = profiler
+            .imports
+            .iter()
+            .map(|(name, stats)| (*name, *stats))
+            .collect::<Vec<_>>();
+        imports.sort_by_key(|(_, stats)| Reverse(stats.time));
+        draw_imports(ui, &imports, profiler.import_frames);
+    }
+
+    fn draw_recording(&mut self, ui: &mut Ui, game: &mut GameInstance) {
+        let recording = game.profiler().is_recording();
+
+        ui.horizontal(|ui| {
+            ui.label("Guest profile:");
+            ui.add_enabled(
+                !recording,
+                DragValue::new(&mut self.record_frames)
+                    .range(1..=100_000)
+                    .suffix(" frames"),
+            );
+            if ui
+                .add_enabled(!recording, egui::Button::new("Record"))
+                .clicked()
+            {
+                game.start_profile_recording(self.record_frames);
+            }
+        });
+
+        if recording {
+            ui.label(RichText::new("Recording...").color(egui::Color32::LIGHT_RED));
+        } else {
+            ui.label(RichText::new("Saved to profiles/, open with profiler.firefox.com").weak());
+        }
+    }
+}
+
+fn average_timings<'a>(history: impl ExactSizeIterator<Item = &'a FrameTimings>) -> FrameTimings {
+    let count = history.len().max(1) as u32;
+    let total = history.fold(FrameTimings::default(), |mut total, frame| {
+        total.updates += frame.updates;
+        total.update += frame.update;
+        total.render += frame.render;
+        total.submit += frame.submit;
+        total.snapshot += frame.snapshot;
+        total.restore += frame.restore;
+        total.host += frame.host;
+        total.snapshot_bytes += frame.snapshot_bytes;
+        total.rollback_frames += frame.rollback_frames;
+        total
+    });
+
+    FrameTimings {
+        updates: total.updates / count,
+        update: total.update / count,
+        render: total.render / count,
+        submit: total.submit / count,
+        snapshot: total.snapshot / count,
+        restore: total.restore / count,
+        host: total.host / count,
+        snapshot_bytes: total.snapshot_bytes / count as usize,
+        rollback_frames: total.rollback_frames / count,
+    }
+}
+
+fn draw_frame_timings(ui: &mut Ui, last: &FrameTimings, average: &FrameTimings, frames: usize) {
+    ui.label(format!("Frame timings (average over {frames} frames):"));
+
+    let rows: [TimingRow; 8] = [
+        ("Update", |t| t.update),
+        ("Render", |t| t.render),
+        ("  Guest code", |t| {
+            (t.update + t.render).saturating_sub(t.host)
+        }),
+        ("  Host imports", |t| t.host),
+        ("GPU submit", |t| t.submit),
+        ("Snapshot", |t| t.snapshot),
+        ("Restore", |t| t.restore),
+        ("Total", |t| {
+            t.update + t.render + t.submit + t.snapshot + t.restore
+        }),
+    ];
+
+    Grid::new("profiler frame timings")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label("Last");
+            ui.label("Average");
+            ui.end_row();
+
+            for (label, time) in rows {
+                ui.label(label);
+                ui.monospace(format_ms(time(last)));
+                ui.monospace(format_ms(time(average)));
+                ui.end_row();
+            }
+
+            ui.label("Updates");
+            ui.monospace(last.updates.to_string());
+            ui.monospace(average.updates.to_string());
+            ui.end_row();
+        });
+}
+
+fn draw_imports(ui: &mut Ui, imports: &[(&str, ImportStats)], frames: u32) {
+    if imports.is_empty() {
+        ui.label(RichText::new("No import calls recorded").weak());
+        return;
+    }
+
+    let frames = frames.max(1) as f64;
+    ScrollArea::vertical()
+        .id_salt("profiler imports")
+        .max_height(300.0)
+        .show(ui, |ui| {
+            Grid::new("profiler imports grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Import");
+                    ui.label("Calls");
+                    ui.label("Calls/Frame");
+                    ui.label("Total");
+                    ui.label("Per Frame");
+                    ui.label("Per Call");
+                    ui.end_row();
+
+                    for (name, stats) in imports {
+                        ui.monospace(*name);
+                        ui.monospace(stats.calls.to_string());
+                        ui.monospace(format!("{:.1}", stats.calls as f64 / frames));
+                        ui.monospace(format_ms(stats.time));
+                        ui.monospace(format_ms(stats.time.div_f64(frames)));
+                        let per_call = stats.time.as_secs_f64() / stats.calls.max(1) as f64;
+                        ui.monospace(format!("{:.2} us", per_call * 1_000_000.0));
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn format_ms(time: Duration) -> String {
+    format!("{:.3} ms", time.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,126 @@
+use egui::{Color32, ColorImage, Context, RichText, TextureHandle, TextureOptions, Ui, vec2};
+use nethercade_core::{Rom, RomImage};
+
+use crate::trust::Signer;
+
+const ICON_DISPLAY_SIZE: f32 = 64.0;
+const COVER_DISPLAY_WIDTH: f32 = 320.0;
+
+/// The metadata of a loaded ROM, shown before starting it.
+pub struct RomDetails {
+    pub rom: Rom,
+    signer: Signer,
+    icon: Option<TextureHandle>,
+    cover: Option<TextureHandle>,
+}
+
+impl RomDetails {
+    pub fn new(ctx: &Context, rom: Rom, signer: Signer) -> Self {
+        let icon = rom
+            .metadata
+            .icon
+            .as_ref()
+            .and_then(|image| load_texture(ctx, "rom_icon", image));
+        let cover = rom
+            .metadata
+            .cover
+            .as_ref()
+            .and_then(|image| load_texture(ctx, "rom_cover", image));
+
+        Self {
+            rom,
+            signer,
+            icon,
+            cover,
+        }
+    }
+
+    /// Draws the details, with warnings if the game can't be played the way
+    /// the player set up. Returns true once the player starts the game.
+    pub fn draw(&self, ui: &mut Ui, num_players: usize, networked: bool) -> bool {
+        let metadata = &self.rom.metadata;
+
+        if let Some(cover) = &self.cover {
+            let size = cover.size_vec2();
+            let scale = (COVER_DISPLAY_WIDTH / size.x).min(1.0);
+            ui.image((cover.id(), size * scale));
+        }
+
+        ui.horizontal(|ui| {
+            if let Some(icon) = &self.icon {
+                ui.image((icon.id(), vec2(ICON_DISPLAY_SIZE, ICON_DISPLAY_SIZE)));
+            }
+
+            ui.vertical(|ui| {
+                ui.heading(display_title(&self.rom));
+                if !metadata.author.is_empty() {
+                    ui.label(format!("by {}", metadata.author));
+                }
+                ui.label(format!("Version {}", metadata.version));
+            });
+        });
+
+        if !metadata.description.is_empty() {
+            ui.label(&metadata.description);
+        }
+
+        ui.label(format!(
+            "{}, {}",
+            metadata.players_label(),
+            if metadata.netplay {
+                "netplay supported"
+            } else {
+                "local only"
+            }
+        ));
+        if !metadata.genres.is_empty() {
+            ui.label(format!("Genres: {}", metadata.genres.join(", ")));
+        }
+
+        let signer = RichText::new(self.signer.label());
+        ui.label(match self.signer {
+            Signer::Trusted(_) => signer.color(Color32::GREEN),
+            Signer::Untrusted { .. } => signer.color(Color32::YELLOW),
+            Signer::Unsigned => signer,
+        });
+
+        if !metadata.supports_players(num_players) {
+            ui.label(
+                RichText::new(format!(
+                    "This game is made for {}, {num_players} set up",
+                    metadata.players_label()
+                ))
+                .color(Color32::YELLOW),
+            );
+        }
+        if networked && !metadata.netplay {
+            ui.label(
+                RichText::new("This game doesn't support netplay and may desync")
+                    .color(Color32::YELLOW),
+            );
+        }
+
+        ui.button("Start").clicked()
+    }
+}
+
+/// The ROM's title, or a placeholder for ROMs bundled without one.
+pub fn display_title(rom: &Rom) -> &str {
+    if rom.metadata.title.is_empty() {
+        "Untitled Game"
+    } else {
+        &rom.metadata.title
+    }
+}
+
+/// Skips images whose size doesn't match their pixel data instead of
+/// panicking on a damaged ROM.
+fn load_texture(ctx: &Context, name: &str, image: &RomImage) -> Option<TextureHandle> {
+    let size = [image.width as usize, image.height as usize];
+    if image.rgba.len() != size[0] * size[1] * 4 {
+        return None;
+    }
+
+    let image = ColorImage::from_rgba_unmultiplied(size, &image.rgba);
+    Some(ctx.load_texture(name, image, TextureOptions::default()))
+}
@@ -0,0 +1,95 @@
+use egui::{RichText, Slider, Ui};
+
+use crate::console::GameInstance;
+
+#[derive(Default)]
+pub struct TimelineWindow {
+    scrub_frame: u32,
+}
+
+impl TimelineWindow {
+    /// Returns an error if re-simulating to the selected frame failed.
+    pub(crate) fn draw(
+        &mut self,
+        ui: &mut Ui,
+        game: &mut GameInstance,
+        paused: &mut bool,
+    ) -> Result<(), wasmtime::Error> {
+        if !game.timeline.enabled {
+            ui.label(
+                RichText::new("The timeline is disabled in networked sessions.")
+                    .color(egui::Color32::LIGHT_RED),
+            );
+            return Ok(());
+        }
+
+        let timeline = &game.timeline;
+        ui.label(format!(
+            "Recorded frames {}..{} with {} snapshots ({:.1} MB)",
+            timeline.first_frame(),
+            timeline.last_frame(),
+            timeline.snapshot_count(),
+            timeline.size() as f32 / (1024.0 * 1024.0),
+        ));
+
+        if timeline.is_empty() {
+            return Ok(());
+        }
+
+        let first = timeline.first_frame();
+        let last = timeline.last_frame();
+        if !*paused {
+            self.scrub_frame = game.frame;
+        }
+        self.scrub_frame = self.scrub_frame.clamp(first, last);
+
+        let mut seek_to = None;
+        let response = ui.add(Slider::new(&mut self.scrub_frame, first..=last).text("Frame"));
+        if response.changed() {
+            seek_to = Some(self.scrub_frame);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("<").clicked() {
+                seek_to = Some(self.scrub_frame.saturating_sub(1).max(first));
+            }
+            if ui.button(">").clicked() {
+                seek_to = Some((self.scrub_frame + 1).min(last));
+            }
+
+            if ui
+                .button("Play")
+                .on_hover_text("Resume, following the recorded inputs")
+                .clicked()
+            {
+                game.timeline.play();
+                *paused = false;
+            }
+
+            if ui
+                .button("Branch")
+                .on_hover_text("Resume with live inputs, discarding the recorded future")
+                .clicked()
+            {
+                game.timeline.branch();
+                *paused = false;
+            }
+
+            if ui.button("Clear").clicked() {
+                game.timeline.clear();
+            }
+        });
+
+        if game.timeline.is_playing() {
+            ui.label(RichText::new("Playing back recorded inputs").weak());
+        }
+
+        if let Some(frame) = seek_to {
+            *paused = true;
+            self.scrub_frame = frame;
+            game.seek(frame)?;
+        }
+
+        Ok(())
+    }
+}
@@ -107,6 +107,14 @@ impl Buttons {
         self.state |= code.to_bit_mask();
     }
 
+    pub fn set_button_state(&mut self, code: ButtonCode, pressed: bool) {
+        if pressed {
+            self.state |= code.to_bit_mask();
+        } else {
+            self.state &= !code.to_bit_mask();
+        }
+    }
+
     pub fn get_button_state(&self, code: ButtonCode) -> bool {
         self.state & code.to_bit_mask() != 0
     }
@@ -33,6 +33,12 @@ pub struct LocalInputManager {
     pub(crate) player_bindings: Vec<InputMode>,
 }
 
+impl Default for LocalInputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LocalInputManager {
     pub fn new() -> Self {
         Self {
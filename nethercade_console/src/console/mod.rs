@@ -1,53 +1,190 @@
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use std::{cell::RefCell, rc::Rc, sync::Arc, time::Instant};
 
 use eframe::wgpu;
-use ggrs::{GgrsRequest, InputStatus};
+use ggrs::GgrsRequest;
 use nethercade_core::{Resolution, Rom};
-use network::{NetworkInputState, WasmConsoleState};
 use network_session::GgrsInstance;
-use wasmtime::{Config, Engine, Instance, Linker, Module, Store};
+use wasmtime::{
+    Config, Engine, Instance, InstancePre, Linker, Module, Store, Val, WasmBacktraceDetails,
+    WasmResults,
+};
 
 mod wasm_contexts;
-use wasm_contexts::{DrawContextState, PushedAudio, WasmContexts};
+use wasm_contexts::{DrawContextState, Phase, ProfilerContext};
+pub use wasm_contexts::{PushedAudio, WasmContexts};
 
 mod input;
-pub use input::{LocalInputManager, LocalPlayerId, MouseEventCollector};
+pub use input::{
+    ButtonCode, Buttons, InputState, LocalInputManager, LocalPlayerId, MouseEventCollector,
+};
 
 mod network;
+pub use network::{NetworkInputState, SessionDescriptor, WasmConsoleState};
 pub mod network_session;
 
+mod crash_report;
+pub use crash_report::CrashReport;
+
+mod value_type;
+
+mod cheats;
+use cheats::CheatEngine;
+
+mod timeline;
+use timeline::Timeline;
+
+pub mod movie;
+use movie::MovieSession;
+
 pub mod gui;
 
 use crate::{audio::AudioUnit, graphics::VirtualGpu};
 
+pub const DEFAULT_RANDOM_SEED: u64 = 0xA12CADE;
+
 pub struct GameInstance {
     pub store: Store<WasmContexts>,
     instance: Instance,
-    pub rom: Rom,
+    instance_pre: InstancePre<WasmContexts>,
+    pub rom: Arc<Rom>,
     pub this_frame_audio: Vec<PushedAudio>,
+    pub frame: u32,
+    pub random_seed: u64,
+    pub cheats: CheatEngine,
+    pub timeline: Timeline,
+    pub movie: Option<MovieSession>,
+    /// Memory as it was before the last simulated frame, kept only while a
+    /// tool asks for it with [`Self::keep_previous_memory`].
+    pub previous_memory: Option<Vec<u8>>,
 }
 
 impl GameInstance {
-    fn call_wasm_func(&mut self, fn_name: &str) {
-        if let Ok(func) = self
+    fn call_wasm_func(&mut self, fn_name: &str) -> Result<(), wasmtime::Error> {
+        match self
             .instance
             .get_typed_func::<(), ()>(&mut self.store, fn_name)
         {
-            func.call(&mut self.store, ()).unwrap();
+            Ok(func) => func.call(&mut self.store, ()),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Calls an export taking no parameters, or returns `None` if the guest
+    /// doesn't export a function with that name and signature.
+    pub fn call_optional<R: WasmResults>(
+        &mut self,
+        fn_name: &str,
+    ) -> Option<Result<R, wasmtime::Error>> {
+        let func = self
+            .instance
+            .get_typed_func::<(), R>(&mut self.store, fn_name)
+            .ok()?;
+        Some(func.call(&mut self.store, ()))
+    }
+
+    /// Instantiates an already linked `rom` and runs its `init`. Starts
+    /// without cheats, see [`Self::load_cheats`].
+    pub fn new(
+        rom: Arc<Rom>,
+        instance_pre: InstancePre<WasmContexts>,
+        vgpu: Option<Rc<RefCell<VirtualGpu>>>,
+        num_players: usize,
+        random_seed: u64,
+    ) -> Result<Self, CrashReport> {
+        let engine = instance_pre.module().engine().clone();
+        let mut store = Self::create_store(&engine, &rom, vgpu.clone(), num_players, random_seed);
+        let instance = instance_pre
+            .instantiate(&mut store)
+            .map_err(|e| CrashReport::new(&e, 0, &[]))?;
+
+        if let Some(vgpu) = vgpu {
+            vgpu.borrow_mut().resize(rom.resolution);
+        }
+
+        let mut game_instance = Self {
+            store,
+            instance,
+            instance_pre,
+            cheats: CheatEngine::default(),
+            timeline: Timeline::default(),
+            movie: None,
+            previous_memory: None,
+            rom,
+            this_frame_audio: Vec::new(),
+            frame: 0,
+            random_seed,
+        };
+
+        match game_instance.init() {
+            Ok(()) => Ok(game_instance),
+            Err(e) => Err(game_instance.crash_report(&e)),
         }
     }
 
-    pub fn init(&mut self) {
+    fn create_store(
+        engine: &Engine,
+        rom: &Rom,
+        vgpu: Option<Rc<RefCell<VirtualGpu>>>,
+        num_players: usize,
+        random_seed: u64,
+    ) -> Store<WasmContexts> {
+        let mut store = Store::new(
+            engine,
+            WasmContexts::new(rom, vgpu, num_players, random_seed),
+        );
+        store.limiter(|contexts| &mut contexts.limits);
+        store.epoch_deadline_callback(ProfilerContext::epoch_callback);
+        store.set_epoch_deadline(1);
+        store
+    }
+
+    /// Powers the game back on with a fresh instance and `random_seed`. The
+    /// timeline is cleared, cheats and the movie are kept.
+    pub fn reset(&mut self, random_seed: u64) -> Result<(), wasmtime::Error> {
+        let engine = self.store.engine().clone();
+        let vgpu = self.store.data().draw_3d.vgpu.clone();
+        let num_players = self.num_players();
+
+        self.store = Self::create_store(&engine, &self.rom, vgpu, num_players, random_seed);
+        self.instance = self.instance_pre.instantiate(&mut self.store)?;
+        self.random_seed = random_seed;
+        self.frame = 0;
+        self.this_frame_audio.clear();
+        self.timeline.clear();
+        for cheat in self.cheats.cheats.iter_mut() {
+            cheat.reset();
+        }
+
+        self.init()
+    }
+
+    /// Replaces the cheats with the ones saved for this ROM.
+    pub fn load_cheats(&mut self) {
+        self.cheats = CheatEngine::load(&self.rom);
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.store.data().input.input_entries.len()
+    }
+
+    pub fn init(&mut self) -> Result<(), wasmtime::Error> {
         self.store.data_mut().draw_3d.state = DrawContextState::Init;
-        self.call_wasm_func("init");
+        let result = self.call_wasm_func("init");
         self.store.data_mut().draw_3d.state = DrawContextState::Invalid;
+        result
     }
 
-    pub fn update(&mut self) {
-        self.call_wasm_func("update");
+    pub fn update(&mut self) -> Result<(), wasmtime::Error> {
+        let start = Instant::now();
+        let result = self.call_wasm_func("update");
+        self.store
+            .data_mut()
+            .profiler
+            .record(Phase::Update, start.elapsed());
+        result
     }
 
-    pub fn render(&mut self) {
+    pub fn render(&mut self) -> Result<(), wasmtime::Error> {
         {
             let ctx = &mut self.store.data_mut().draw_3d;
             ctx.vrp.reset();
@@ -58,32 +195,149 @@ impl GameInstance {
             ctx.state = DrawContextState::Draw;
         }
 
-        self.call_wasm_func("render");
+        let start = Instant::now();
+        let result = self.call_wasm_func("render");
+        let render_time = start.elapsed();
 
-        {
-            let ctx = &mut self.store.data_mut().draw_3d;
-            ctx.state = DrawContextState::Invalid;
-            ctx.render();
+        let frame = self.frame;
+        let data = self.store.data_mut();
+        data.profiler.record(Phase::Render, render_time);
+
+        data.draw_3d.state = DrawContextState::Invalid;
+        if result.is_ok() {
+            let start = Instant::now();
+            data.draw_3d.render();
+            data.profiler.record(Phase::Submit, start.elapsed());
         }
+        data.profiler.end_frame(frame);
+
+        result
     }
 
-    pub fn handle_requests(&mut self, requests: Vec<GgrsRequest<GgrsInstance>>) {
+    /// The guest's linear memory.
+    pub fn memory(&mut self) -> &[u8] {
+        let mem = self.instance.get_memory(&mut self.store, "memory").unwrap();
+        mem.data(&self.store)
+    }
+
+    /// Starts or stops copying memory before each simulated frame into
+    /// `previous_memory`.
+    pub fn keep_previous_memory(&mut self, keep: bool) {
+        match (keep, &self.previous_memory) {
+            (true, None) => self.previous_memory = Some(Vec::new()),
+            (false, Some(_)) => self.previous_memory = None,
+            _ => (),
+        }
+    }
+
+    /// Name and current value of every global the guest exports.
+    pub fn exported_globals(&mut self) -> Vec<(String, Val)> {
+        let globals = self
+            .instance
+            .exports(&mut self.store)
+            .filter_map(|export| {
+                let name = export.name().to_string();
+                export.into_global().map(|global| (name, global))
+            })
+            .collect::<Vec<_>>();
+
+        globals
+            .into_iter()
+            .map(|(name, global)| (name, global.get(&mut self.store)))
+            .collect()
+    }
+
+    pub fn profiler(&mut self) -> &mut ProfilerContext {
+        &mut self.store.data_mut().profiler
+    }
+
+    /// Turns timing of host imports on or off.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.store.data_mut().profiler.set_enabled(enabled);
+        if enabled {
+            self.install_call_hook();
+        }
+    }
+
+    /// Host calls are only hooked once profiling is wanted, since timing
+    /// every import slows down the games that aren't profiled.
+    fn install_call_hook(&mut self) {
+        let profiler = &mut self.store.data_mut().profiler;
+        if !profiler.call_hook_installed {
+            profiler.call_hook_installed = true;
+            self.store.call_hook(ProfilerContext::call_hook);
+        }
+    }
+
+    /// Samples the guest stack for the next `frames` rendered frames. Only
+    /// works for games loaded with a GPU, whose engine can interrupt them.
+    pub fn start_profile_recording(&mut self, frames: u32) {
+        self.install_call_hook();
+        let engine = self.store.engine().clone();
+        let frame = self.frame;
+        self.store.data_mut().profiler.start_recording(
+            &engine,
+            self.instance_pre.module(),
+            frame,
+            frames,
+        );
+    }
+
+    /// Builds the report shown on the error screen after `error` stopped the game.
+    pub fn crash_report(&self, error: &wasmtime::Error) -> CrashReport {
+        CrashReport::new(error, self.frame, &self.store.data().input.input_entries)
+    }
+
+    pub fn handle_requests(
+        &mut self,
+        requests: Vec<GgrsRequest<GgrsInstance>>,
+    ) -> Result<(), wasmtime::Error> {
+        // Frames advanced after a load are resimulated, except the newest one
+        let mut loaded = false;
+        let mut advanced_after_load = 0;
+
         for request in requests {
             match request {
                 GgrsRequest::LoadGameState { cell, .. } => {
+                    let start = Instant::now();
                     let state = cell.data().expect("Failed to load game state");
                     self.load_save_state(&state);
+                    loaded = true;
+                    self.store
+                        .data_mut()
+                        .profiler
+                        .record(Phase::Restore, start.elapsed());
                 }
                 GgrsRequest::SaveGameState { cell, frame } => {
+                    let start = Instant::now();
                     let state = self.generate_save_state();
+                    let size = state.size();
                     cell.save(frame, Some(state), None);
+                    let profiler = &mut self.store.data_mut().profiler;
+                    profiler.record(Phase::Snapshot, start.elapsed());
+                    profiler.record_snapshot_size(size);
+                }
+                GgrsRequest::AdvanceFrame { inputs } => {
+                    if loaded {
+                        advanced_after_load += 1;
+                    }
+                    let inputs = inputs.iter().map(|(input, _)| *input).collect::<Vec<_>>();
+                    self.advance_frame(&inputs)?
                 }
-                GgrsRequest::AdvanceFrame { inputs } => self.advance_frame(inputs),
             }
         }
+
+        if advanced_after_load > 1 {
+            self.store
+                .data_mut()
+                .profiler
+                .record_rollback(advanced_after_load - 1);
+        }
+
+        Ok(())
     }
 
-    fn generate_save_state(&mut self) -> WasmConsoleState {
+    pub fn generate_save_state(&mut self) -> WasmConsoleState {
         let previous_buttons = self
             .store
             .data()
@@ -101,6 +355,8 @@ impl GameInstance {
         WasmConsoleState {
             previous_buttons,
             memory,
+            frame: self.frame,
+            rng_state: self.store.data().random.state(),
         }
     }
 
@@ -108,8 +364,13 @@ impl GameInstance {
         let WasmConsoleState {
             previous_buttons,
             memory,
+            frame,
+            rng_state,
         } = state;
 
+        self.frame = *frame;
+        self.store.data_mut().random.restore_state(*rng_state);
+
         previous_buttons
             .iter()
             .enumerate()
@@ -117,14 +378,83 @@ impl GameInstance {
                 self.store.data_mut().input.input_entries[index].previous = *prev;
             });
 
-        self.instance
-            .get_memory(&mut self.store, "memory")
-            .unwrap()
-            .data_mut(&mut self.store)
-            .copy_from_slice(memory);
+        // The guest may have grown its memory since the state was saved
+        let mem = self.instance.get_memory(&mut self.store, "memory").unwrap();
+        let missing = memory.len().saturating_sub(mem.data_size(&self.store));
+        if missing > 0 {
+            let page_size = mem.page_size(&self.store) as usize;
+            mem.grow(&mut self.store, missing.div_ceil(page_size) as u64)
+                .unwrap();
+        }
+
+        let data = mem.data_mut(&mut self.store);
+        data[..memory.len()].copy_from_slice(memory);
+        data[memory.len()..].fill(0);
+    }
+
+    /// Restores the state at the start of `frame` from the timeline, then
+    /// renders it. Recorded inputs are played back from there on.
+    pub fn seek(&mut self, frame: u32) -> Result<(), wasmtime::Error> {
+        let Some((snapshot_frame, state)) = self.timeline.nearest_snapshot(frame) else {
+            return Ok(());
+        };
+
+        self.load_save_state(&state);
+        for frame in snapshot_frame..frame {
+            let Some(inputs) = self.timeline.inputs_for(frame) else {
+                break;
+            };
+            let inputs = inputs.to_vec();
+            self.simulate_frame(&inputs)?;
+        }
+
+        self.timeline.play();
+        self.render()
+    }
+
+    /// Rewinds to `frame` so edits to the movie from there on take effect.
+    /// Only possible while the timeline still covers `frame`.
+    pub fn rewind_for_movie_edit(&mut self, frame: u32) -> Result<(), wasmtime::Error> {
+        if frame >= self.frame || frame < self.timeline.first_frame() || self.timeline.is_empty() {
+            return Ok(());
+        }
+
+        self.seek(frame)?;
+        // Let the timeline record the edited inputs instead of replaying its own
+        self.timeline.branch();
+        Ok(())
     }
 
-    fn advance_frame(&mut self, inputs: Vec<(NetworkInputState, InputStatus)>) {
+    /// Simulates one frame. Recorded movie or timeline inputs take priority
+    /// over the `live` ones.
+    pub fn advance_frame(&mut self, live: &[NetworkInputState]) -> Result<(), wasmtime::Error> {
+        let frame = self.frame;
+        let movie_inputs = self
+            .movie
+            .as_ref()
+            .and_then(|movie| movie.playback_inputs(frame));
+        let timeline_inputs = self.timeline.playback_inputs(frame);
+
+        let replaying_timeline = timeline_inputs.is_some();
+        let recorded = movie_inputs.or(timeline_inputs);
+        let inputs = recorded.as_deref().unwrap_or(live);
+
+        if self.timeline.enabled && !replaying_timeline {
+            let snapshot = self
+                .timeline
+                .needs_snapshot(frame)
+                .then(|| self.generate_save_state());
+            self.timeline.record(frame, inputs, snapshot);
+        }
+
+        if let Some(movie) = &mut self.movie {
+            movie.record(frame, inputs);
+        }
+
+        self.simulate_frame(inputs)
+    }
+
+    fn simulate_frame(&mut self, inputs: &[NetworkInputState]) -> Result<(), wasmtime::Error> {
         // Pre Update Input
         self.store
             .data_mut()
@@ -133,12 +463,21 @@ impl GameInstance {
             .iter_mut()
             .zip(inputs.iter())
             .for_each(|(current, new)| {
-                current.current = new.0.input_state;
-                current.current_mouse = new.0.mouse_state;
+                current.current = new.input_state;
+                current.current_mouse = new.mouse_state;
             });
 
+        if let Some(previous) = &mut self.previous_memory {
+            let mem = self.instance.get_memory(&mut self.store, "memory").unwrap();
+            previous.clear();
+            previous.extend_from_slice(mem.data(&self.store));
+        }
+
         // Call WASM Update
-        self.update();
+        self.update()?;
+
+        let mem = self.instance.get_memory(&mut self.store, "memory").unwrap();
+        self.cheats.apply(mem.data_mut(&mut self.store));
 
         // Take only the "Most Recent" audio
         self.this_frame_audio.clear();
@@ -155,11 +494,13 @@ impl GameInstance {
                 inputs.previous = inputs.current.buttons;
                 inputs.previous_mouse = inputs.current_mouse;
             });
+
+        self.frame += 1;
+        Ok(())
     }
 }
 
 pub struct Console {
-    pub game: Option<GameInstance>,
     pub vgpu: Rc<RefCell<VirtualGpu>>,
     pub audio: AudioUnit,
 }
@@ -177,35 +518,44 @@ impl Console {
                 queue,
                 format,
             ))),
-            game: None,
             audio: AudioUnit::new(),
         }
     }
 
-    pub fn load_rom(rom: Rom, vgpu: Rc<RefCell<VirtualGpu>>, num_players: usize) -> GameInstance {
-        let engine = Engine::new(&Config::default()).unwrap();
-        let module = Module::from_binary(&engine, &rom.code).unwrap();
-
-        let mut linker = Linker::new(&engine);
-        WasmContexts::link(&mut linker);
-
-        let mut store = Store::new(
-            &engine,
-            WasmContexts::new(&rom, vgpu.clone(), num_players, 0xA12CADE),
-        );
-        let instance = linker.instantiate(&mut store, &module).unwrap();
-
-        vgpu.borrow_mut().resize(rom.resolution);
-
-        let mut game_instance = GameInstance {
-            store,
-            instance,
-            rom,
-            this_frame_audio: Vec::new(),
-        };
+    /// Instantiates `rom` and runs its `init`. Without a `vgpu` the game runs
+    /// headless, with rendering disabled and no guest profiling.
+    pub fn load_rom(
+        rom: Rom,
+        vgpu: Option<Rc<RefCell<VirtualGpu>>>,
+        num_players: usize,
+        random_seed: u64,
+    ) -> Result<GameInstance, CrashReport> {
+        let engine = Engine::new(&Self::engine_config(vgpu.is_some()))
+            .map_err(|e| CrashReport::new(&e, 0, &[]))?;
+        let instance_pre = Self::link(&engine, &rom)?;
+        GameInstance::new(Arc::new(rom), instance_pre, vgpu, num_players, random_seed)
+    }
 
-        game_instance.init();
+    /// `profiling` compiles in the epoch checks that let the profiler sample
+    /// the guest stack, which costs some speed even while not recording.
+    pub fn engine_config(profiling: bool) -> Config {
+        let mut config = Config::default();
+        config.epoch_interruption(profiling);
+        // Adds source lines to backtraces of games bundled with DWARF
+        config.wasm_backtrace_details(WasmBacktraceDetails::Enable);
+        config
+    }
 
-        game_instance
+    /// Compiles `rom` and resolves its imports, ready to be instantiated any
+    /// number of times. Fails on invalid code or imports the console doesn't
+    /// provide.
+    pub fn link(engine: &Engine, rom: &Rom) -> Result<InstancePre<WasmContexts>, CrashReport> {
+        let module = Module::from_binary(engine, &rom.symbolicated_code())
+            .map_err(|e| CrashReport::new(&e, 0, &[]))?;
+        let mut linker = Linker::new(engine);
+        WasmContexts::link(&mut linker);
+        linker
+            .instantiate_pre(&module)
+            .map_err(|e| CrashReport::new(&e, 0, &[]))
     }
 }
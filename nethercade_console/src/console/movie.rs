@@ -0,0 +1,129 @@
+use std::{io::Read, path::Path};
+
+use nethercade_core::{FrameRate, Rom};
+use serde::{Deserialize, Serialize};
+
+use super::network::NetworkInputState;
+
+pub const MOVIE_FILE_EXTENSION: &str = "nzmovie";
+const MOVIE_VERSION: u32 = 1;
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// Every input of a session from power on, enough to replay it exactly.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Movie {
+    pub version: u32,
+    pub rom_hash: String,
+    pub num_players: usize,
+    pub frame_rate: FrameRate,
+    pub random_seed: u64,
+    /// `inputs[frame][player]`
+    pub inputs: Vec<Box<[NetworkInputState]>>,
+}
+
+impl Movie {
+    pub fn new(rom: &Rom, num_players: usize, random_seed: u64) -> Self {
+        Self {
+            version: MOVIE_VERSION,
+            rom_hash: rom.hash(),
+            num_players,
+            frame_rate: rom.frame_rate,
+            random_seed,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> u32 {
+        self.inputs.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn inputs_for(&self, frame: u32) -> Option<&[NetworkInputState]> {
+        self.inputs.get(frame as usize).map(|inputs| &**inputs)
+    }
+
+    /// Stores the inputs for `frame`, dropping everything recorded after it.
+    pub fn record(&mut self, frame: u32, inputs: &[NetworkInputState]) {
+        let frame = frame as usize;
+        if frame > self.inputs.len() {
+            return;
+        }
+
+        self.inputs.truncate(frame);
+        self.inputs.push(Box::from(inputs));
+    }
+
+    /// Checks the movie was made for `rom` with `num_players` players.
+    pub fn check(&self, rom: &Rom, num_players: usize) -> Result<(), String> {
+        if self.version != MOVIE_VERSION {
+            return Err(format!("Unsupported movie version {}", self.version));
+        }
+
+        if self.rom_hash != rom.hash() {
+            return Err(String::from("Movie was recorded with a different ROM"));
+        }
+
+        if self.num_players != num_players {
+            return Err(format!(
+                "Movie needs {} players, session has {num_players}",
+                self.num_players
+            ));
+        }
+
+        if self.inputs.iter().any(|inputs| inputs.len() != num_players) {
+            return Err(String::from("Movie has frames with missing inputs"));
+        }
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        let mut bytes = Vec::new();
+        zstd::Decoder::new(file)
+            .and_then(|mut decoder| decoder.read_to_end(&mut bytes))
+            .map_err(|e| e.to_string())?;
+        bitcode::deserialize(&bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes = bitcode::serialize(self).map_err(|e| e.to_string())?;
+        let compressed =
+            zstd::encode_all(bytes.as_slice(), COMPRESSION_LEVEL).map_err(|e| e.to_string())?;
+        std::fs::write(path, compressed).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MovieMode {
+    /// Inputs used each frame are written into the movie.
+    Recording,
+    /// The movie's inputs replace live ones.
+    Playing,
+    /// The movie is only kept around for editing and saving.
+    Stopped,
+}
+
+pub struct MovieSession {
+    pub movie: Movie,
+    pub mode: MovieMode,
+}
+
+impl MovieSession {
+    pub(super) fn playback_inputs(&self, frame: u32) -> Option<Box<[NetworkInputState]>> {
+        if self.mode != MovieMode::Playing {
+            return None;
+        }
+
+        self.movie.inputs_for(frame).map(Box::from)
+    }
+
+    pub(super) fn record(&mut self, frame: u32, inputs: &[NetworkInputState]) {
+        if self.mode == MovieMode::Recording {
+            self.movie.record(frame, inputs);
+        }
+    }
+}
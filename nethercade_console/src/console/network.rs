@@ -17,6 +17,18 @@ pub struct NetworkInputState {
 pub struct WasmConsoleState {
     pub previous_buttons: Box<[Buttons]>,
     pub memory: Vec<u8>,
+    pub frame: u32,
+    pub rng_state: u64,
+}
+
+impl WasmConsoleState {
+    /// Approximate size of the state in bytes.
+    pub fn size(&self) -> usize {
+        self.memory.len()
+            + size_of_val(&*self.previous_buttons)
+            + size_of::<u32>()
+            + size_of::<u64>()
+    }
 }
 
 #[derive(Clone)]
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use super::network::{NetworkInputState, WasmConsoleState};
+
+const SNAPSHOT_INTERVAL: u32 = 60;
+/// Past this, older snapshots are thinned out. Inputs are always kept, so
+/// every frame stays reachable, only re-simulating further to get there.
+const MAX_SNAPSHOTS: usize = 600;
+const COMPRESSION_LEVEL: i32 = 1;
+
+/// A save state with its memory zstd compressed, since most of guest memory
+/// is usually zeroes.
+struct Snapshot {
+    state: WasmConsoleState,
+    memory_size: usize,
+}
+
+impl Snapshot {
+    fn new(mut state: WasmConsoleState) -> Self {
+        let memory_size = state.memory.len();
+        state.memory = zstd::bulk::compress(&state.memory, COMPRESSION_LEVEL).unwrap();
+        Self { state, memory_size }
+    }
+
+    fn decompress(&self) -> WasmConsoleState {
+        let mut state = self.state.clone();
+        state.memory = zstd::bulk::decompress(&state.memory, self.memory_size).unwrap();
+        state
+    }
+}
+
+/// Records the inputs of a local session along with periodic snapshots, so
+/// any past frame can be restored by loading the nearest snapshot and
+/// re-simulating the recorded inputs.
+#[derive(Default)]
+pub struct Timeline {
+    /// Recording is only allowed in local sessions, since remote players
+    /// can't follow us back in time.
+    pub enabled: bool,
+    first_frame: u32,
+    /// `inputs[i]` were used to simulate frame `first_frame + i`.
+    inputs: Vec<Box<[NetworkInputState]>>,
+    snapshots: BTreeMap<u32, Snapshot>,
+    /// While set, recorded inputs replace live ones until this frame.
+    playback_end: Option<u32>,
+}
+
+impl Timeline {
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn first_frame(&self) -> u32 {
+        self.first_frame
+    }
+
+    /// The newest frame which can be restored.
+    pub fn last_frame(&self) -> u32 {
+        self.first_frame + self.inputs.len() as u32
+    }
+
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Approximate memory used by the recording, in bytes.
+    pub fn size(&self) -> usize {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|inputs| size_of_val(&**inputs))
+            .sum::<usize>();
+        let snapshots = self
+            .snapshots
+            .values()
+            .map(|snapshot| snapshot.state.size())
+            .sum::<usize>();
+        inputs + snapshots
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback_end.is_some()
+    }
+
+    /// Follows the recorded inputs until the end of the recording.
+    pub fn play(&mut self) {
+        self.playback_end = Some(self.last_frame());
+    }
+
+    /// Switches back to live inputs. Anything recorded after the current
+    /// frame is discarded once the next frame is simulated.
+    pub fn branch(&mut self) {
+        self.playback_end = None;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self {
+            enabled: self.enabled,
+            ..Self::default()
+        };
+    }
+
+    /// Recorded inputs for `frame` while playing back, otherwise `None`.
+    pub(super) fn playback_inputs(&mut self, frame: u32) -> Option<Box<[NetworkInputState]>> {
+        let end = self.playback_end?;
+        if frame >= end {
+            self.playback_end = None;
+            return None;
+        }
+
+        self.inputs_for(frame).map(Box::from)
+    }
+
+    pub(super) fn inputs_for(&self, frame: u32) -> Option<&[NetworkInputState]> {
+        let index = frame.checked_sub(self.first_frame)?;
+        self.inputs.get(index as usize).map(|inputs| &**inputs)
+    }
+
+    pub(super) fn needs_snapshot(&self, frame: u32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        // A frame we can't reach starts a new recording
+        if self.is_empty() || !(self.first_frame..=self.last_frame()).contains(&frame) {
+            return true;
+        }
+
+        self.snapshots
+            .range(..=frame)
+            .next_back()
+            .is_none_or(|(last, _)| frame - last >= SNAPSHOT_INTERVAL)
+    }
+
+    /// Records the inputs used for `frame`. `snapshot` is the state before
+    /// the frame was simulated, when [`Self::needs_snapshot`] asked for one.
+    pub(super) fn record(
+        &mut self,
+        frame: u32,
+        inputs: &[NetworkInputState],
+        snapshot: Option<WasmConsoleState>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.is_empty() || !(self.first_frame..=self.last_frame()).contains(&frame) {
+            self.inputs.clear();
+            self.snapshots.clear();
+            self.first_frame = frame;
+        }
+
+        // Simulating a frame we already have means we branched off
+        let index = (frame - self.first_frame) as usize;
+        self.inputs.truncate(index);
+        self.snapshots.split_off(&(frame + 1));
+
+        if let Some(snapshot) = snapshot {
+            self.snapshots.insert(frame, Snapshot::new(snapshot));
+        }
+        self.inputs.push(Box::from(inputs));
+
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.thin_snapshots();
+        }
+    }
+
+    /// Drops every other snapshot in the older half, so snapshots get sparser
+    /// the further back they go. The first one is kept, the recording starts
+    /// there.
+    fn thin_snapshots(&mut self) {
+        let older = self.snapshots.len() / 2;
+        let dropped = self
+            .snapshots
+            .keys()
+            .take(older)
+            .skip(1)
+            .step_by(2)
+            .copied()
+            .collect::<Vec<_>>();
+        for frame in dropped {
+            self.snapshots.remove(&frame);
+        }
+    }
+
+    /// The closest snapshot taken at or before `frame`.
+    pub(super) fn nearest_snapshot(&self, frame: u32) -> Option<(u32, WasmConsoleState)> {
+        self.snapshots
+            .range(..=frame)
+            .next_back()
+            .map(|(frame, snapshot)| (*frame, snapshot.decompress()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(frame: u32) -> Box<[NetworkInputState]> {
+        let mut input = NetworkInputState::default();
+        bytemuck::bytes_of_mut(&mut input)[..4].copy_from_slice(&frame.to_le_bytes());
+        Box::new([input])
+    }
+
+    fn state(frame: u32) -> WasmConsoleState {
+        WasmConsoleState {
+            previous_buttons: Box::default(),
+            memory: vec![frame as u8; 256],
+            frame,
+            rng_state: frame.into(),
+        }
+    }
+
+    fn record_frames(timeline: &mut Timeline, frames: std::ops::Range<u32>) {
+        for frame in frames {
+            let snapshot = timeline.needs_snapshot(frame).then(|| state(frame));
+            timeline.record(frame, &inputs(frame), snapshot);
+        }
+    }
+
+    fn enabled() -> Timeline {
+        Timeline {
+            enabled: true,
+            ..Timeline::default()
+        }
+    }
+
+    #[test]
+    fn records_inputs_with_periodic_snapshots() {
+        let mut timeline = enabled();
+        record_frames(&mut timeline, 10..140);
+
+        assert_eq!(timeline.first_frame(), 10);
+        assert_eq!(timeline.last_frame(), 140);
+        assert!(timeline.inputs_for(75) == Some(&*inputs(75)));
+        assert!(timeline.inputs_for(140).is_none());
+        assert_eq!(timeline.snapshot_count(), 3);
+
+        let mut disabled = Timeline::default();
+        record_frames(&mut disabled, 0..10);
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn rewinding_and_recording_truncates_the_branch() {
+        let mut timeline = enabled();
+        record_frames(&mut timeline, 0..130);
+
+        timeline.record(70, &inputs(1000), None);
+        assert_eq!(timeline.last_frame(), 71);
+        assert!(timeline.inputs_for(69) == Some(&*inputs(69)));
+        assert!(timeline.inputs_for(70) == Some(&*inputs(1000)));
+        assert!(timeline.inputs_for(71).is_none());
+        // The snapshot at 120 belonged to the discarded branch
+        assert_eq!(timeline.snapshot_count(), 2);
+        assert_eq!(timeline.nearest_snapshot(125).unwrap().0, 60);
+    }
+
+    #[test]
+    fn finds_the_nearest_earlier_snapshot() {
+        let mut timeline = enabled();
+        record_frames(&mut timeline, 10..140);
+
+        assert!(timeline.nearest_snapshot(9).is_none());
+        for (frame, nearest) in [(10, 10), (69, 10), (70, 70), (139, 130)] {
+            let (snapshot_frame, snapshot) = timeline.nearest_snapshot(frame).unwrap();
+            assert_eq!(snapshot_frame, nearest);
+            assert_eq!(snapshot.frame, nearest);
+            assert_eq!(snapshot.memory, state(nearest).memory);
+        }
+    }
+
+    #[test]
+    fn thinning_keeps_the_newest_snapshot_and_every_input() {
+        let mut timeline = enabled();
+        let frames = MAX_SNAPSHOTS as u32 + 1;
+        for frame in 0..frames {
+            timeline.record(frame, &inputs(frame), Some(state(frame)));
+        }
+
+        assert!(timeline.snapshot_count() <= MAX_SNAPSHOTS);
+        assert_eq!(timeline.nearest_snapshot(0).unwrap().0, 0);
+        assert_eq!(timeline.nearest_snapshot(frames - 1).unwrap().0, frames - 1);
+        for frame in 0..frames {
+            assert!(timeline.inputs_for(frame) == Some(&*inputs(frame)));
+        }
+    }
+}
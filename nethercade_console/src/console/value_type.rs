@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+/// How a range of guest memory should be interpreted by the debugging tools.
+#[derive(Debug, Copy, Clone, EnumIter, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+pub enum ValueType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    #[default]
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    Vec2,
+    Vec3,
+    Vec4,
+}
+
+impl ValueType {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ValueType::U8 => "u8",
+            ValueType::I8 => "i8",
+            ValueType::U16 => "u16",
+            ValueType::I16 => "i16",
+            ValueType::U32 => "u32",
+            ValueType::I32 => "i32",
+            ValueType::U64 => "u64",
+            ValueType::I64 => "i64",
+            ValueType::F32 => "f32",
+            ValueType::F64 => "f64",
+            ValueType::Vec2 => "Vec2",
+            ValueType::Vec3 => "Vec3",
+            ValueType::Vec4 => "Vec4",
+        }
+    }
+
+    /// Size in bytes.
+    pub const fn size(&self) -> usize {
+        match self {
+            ValueType::U8 | ValueType::I8 => 1,
+            ValueType::U16 | ValueType::I16 => 2,
+            ValueType::U32 | ValueType::I32 | ValueType::F32 => 4,
+            ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Vec2 => 8,
+            ValueType::Vec3 => 12,
+            ValueType::Vec4 => 16,
+        }
+    }
+
+    /// Formats the value stored at `address`, or `None` if it is out of bounds.
+    pub fn read(&self, memory: &[u8], address: usize) -> Option<String> {
+        let bytes = memory.get(address..)?.get(..self.size())?;
+
+        Some(match self {
+            ValueType::U8 => bytes[0].to_string(),
+            ValueType::I8 => (bytes[0] as i8).to_string(),
+            ValueType::U16 => u16::from_le_bytes(array(bytes)).to_string(),
+            ValueType::I16 => i16::from_le_bytes(array(bytes)).to_string(),
+            ValueType::U32 => u32::from_le_bytes(array(bytes)).to_string(),
+            ValueType::I32 => i32::from_le_bytes(array(bytes)).to_string(),
+            ValueType::U64 => u64::from_le_bytes(array(bytes)).to_string(),
+            ValueType::I64 => i64::from_le_bytes(array(bytes)).to_string(),
+            ValueType::F32 => f32::from_le_bytes(array(bytes)).to_string(),
+            ValueType::F64 => f64::from_le_bytes(array(bytes)).to_string(),
+            ValueType::Vec2 | ValueType::Vec3 | ValueType::Vec4 => {
+                let components = bytes
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes(array(chunk)).to_string())
+                    .collect::<Vec<_>>();
+                format!("({})", components.join(", "))
+            }
+        })
+    }
+
+    /// Whether the type is a single number, which the value search can compare.
+    pub const fn is_scalar(&self) -> bool {
+        !matches!(self, ValueType::Vec2 | ValueType::Vec3 | ValueType::Vec4)
+    }
+
+    /// Reads a scalar value as an `f64` for ordering comparisons.
+    pub fn read_scalar(&self, memory: &[u8], address: usize) -> Option<f64> {
+        let bytes = memory.get(address..)?.get(..self.size())?;
+
+        match self {
+            ValueType::U8 => Some(bytes[0] as f64),
+            ValueType::I8 => Some(bytes[0] as i8 as f64),
+            ValueType::U16 => Some(u16::from_le_bytes(array(bytes)) as f64),
+            ValueType::I16 => Some(i16::from_le_bytes(array(bytes)) as f64),
+            ValueType::U32 => Some(u32::from_le_bytes(array(bytes)) as f64),
+            ValueType::I32 => Some(i32::from_le_bytes(array(bytes)) as f64),
+            ValueType::U64 => Some(u64::from_le_bytes(array(bytes)) as f64),
+            ValueType::I64 => Some(i64::from_le_bytes(array(bytes)) as f64),
+            ValueType::F32 => Some(f32::from_le_bytes(array(bytes)) as f64),
+            ValueType::F64 => Some(f64::from_le_bytes(array(bytes))),
+            ValueType::Vec2 | ValueType::Vec3 | ValueType::Vec4 => None,
+        }
+    }
+
+    /// Parses user text into the little endian bytes of this type. Vectors are
+    /// written as comma separated components, optionally in parentheses.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        let text = text.trim();
+
+        Some(match self {
+            ValueType::U8 => text.parse::<u8>().ok()?.to_le_bytes().to_vec(),
+            ValueType::I8 => text.parse::<i8>().ok()?.to_le_bytes().to_vec(),
+            ValueType::U16 => text.parse::<u16>().ok()?.to_le_bytes().to_vec(),
+            ValueType::I16 => text.parse::<i16>().ok()?.to_le_bytes().to_vec(),
+            ValueType::U32 => text.parse::<u32>().ok()?.to_le_bytes().to_vec(),
+            ValueType::I32 => text.parse::<i32>().ok()?.to_le_bytes().to_vec(),
+            ValueType::U64 => text.parse::<u64>().ok()?.to_le_bytes().to_vec(),
+            ValueType::I64 => text.parse::<i64>().ok()?.to_le_bytes().to_vec(),
+            ValueType::F32 => text.parse::<f32>().ok()?.to_le_bytes().to_vec(),
+            ValueType::F64 => text.parse::<f64>().ok()?.to_le_bytes().to_vec(),
+            ValueType::Vec2 | ValueType::Vec3 | ValueType::Vec4 => {
+                let inner = text.trim_start_matches('(').trim_end_matches(')');
+                let components = inner
+                    .split(',')
+                    .map(|component| component.trim().parse::<f32>().ok())
+                    .collect::<Option<Vec<_>>>()?;
+                if components.len() * 4 != self.size() {
+                    return None;
+                }
+                components
+                    .iter()
+                    .flat_map(|component| component.to_le_bytes())
+                    .collect()
+            }
+        })
+    }
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes[..N].try_into().unwrap()
+}
+
+/// Parses an address typed by the user, either decimal or `0x` prefixed hex.
+pub fn parse_address(text: &str) -> Option<usize> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    fn sample(value_type: ValueType) -> (&'static str, &'static str) {
+        // Text to encode, then how it reads back
+        match value_type {
+            ValueType::U8 => ("200", "200"),
+            ValueType::I8 => ("-100", "-100"),
+            ValueType::U16 => ("60000", "60000"),
+            ValueType::I16 => ("-30000", "-30000"),
+            ValueType::U32 => ("4000000000", "4000000000"),
+            ValueType::I32 => (" -2000000000 ", "-2000000000"),
+            ValueType::U64 => ("18000000000000000000", "18000000000000000000"),
+            ValueType::I64 => ("-9000000000000000000", "-9000000000000000000"),
+            ValueType::F32 => ("1.5", "1.5"),
+            ValueType::F64 => ("-0.1", "-0.1"),
+            ValueType::Vec2 => ("(1, -2)", "(1, -2)"),
+            ValueType::Vec3 => ("0.5,2,3", "(0.5, 2, 3)"),
+            ValueType::Vec4 => ("(0, 0.25, 1, 8)", "(0, 0.25, 1, 8)"),
+        }
+    }
+
+    #[test]
+    fn every_type_round_trips() {
+        for value_type in ValueType::iter() {
+            let (text, expected) = sample(value_type);
+            let bytes = value_type.encode(text).unwrap();
+            assert_eq!(bytes.len(), value_type.size(), "{value_type:?}");
+
+            // Unaligned, with a byte on either side
+            let mut memory = vec![0xAA; bytes.len() + 2];
+            memory[1..=bytes.len()].copy_from_slice(&bytes);
+            assert_eq!(value_type.read(&memory, 1).unwrap(), expected);
+
+            let scalar = value_type.read_scalar(&memory, 1);
+            assert_eq!(value_type.is_scalar(), scalar.is_some(), "{value_type:?}");
+            if let Some(scalar) = scalar {
+                assert_eq!(scalar, expected.parse::<f64>().unwrap(), "{value_type:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn encodes_little_endian() {
+        assert_eq!(ValueType::U16.encode("258"), Some(vec![2, 1]));
+        assert_eq!(
+            ValueType::I32.encode("-2"),
+            Some(vec![0xFE, 0xFF, 0xFF, 0xFF])
+        );
+        assert_eq!(
+            ValueType::F32.encode("1"),
+            Some(vec![0x00, 0x00, 0x80, 0x3F])
+        );
+        assert_eq!(
+            ValueType::Vec2.encode("1, 2").unwrap(),
+            [1.0f32.to_le_bytes(), 2.0f32.to_le_bytes()].concat()
+        );
+        assert_eq!(
+            ValueType::F64.read_scalar(&0.5f64.to_le_bytes(), 0),
+            Some(0.5)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(ValueType::U8.encode("256"), None);
+        assert_eq!(ValueType::U32.encode("-1"), None);
+        assert_eq!(ValueType::I16.encode("ten"), None);
+        assert_eq!(ValueType::Vec3.encode("(1, 2)"), None);
+        assert_eq!(ValueType::I32.read(&[0; 3], 0), None);
+        assert_eq!(ValueType::U16.read_scalar(&[0; 4], 3), None);
+    }
+}
@@ -0,0 +1,306 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::cast_slice;
+use hashbrown::HashMap;
+use nethercade_core::{Asset, AssetData, Rom};
+use wasmtime::{Caller, Linker};
+
+use crate::graphics::pipeline::Pipeline;
+
+use super::{DrawContextState, ProfiledLinker, PushedAudio, WasmContexts};
+
+/// Gives the guest access to the assets bundled in its ROM. Ids are indices
+/// into the ROM's asset list.
+pub struct AssetContext {
+    assets: Arc<[Asset]>,
+    /// Sounds already decoded for `push_sound_asset`, by id.
+    decoded_sounds: HashMap<usize, Arc<Box<[f32]>>>,
+}
+
+impl AssetContext {
+    pub fn new(rom: &Rom) -> Self {
+        Self {
+            assets: rom.assets.clone(),
+            decoded_sounds: HashMap::new(),
+        }
+    }
+
+    pub fn link(linker: &mut Linker<WasmContexts>) {
+        linker.func_profiled("asset_id", asset_id).unwrap();
+        linker.func_profiled("asset_len", asset_len).unwrap();
+        linker.func_profiled("read_asset", read_asset).unwrap();
+        linker
+            .func_profiled("load_texture_asset", load_texture_asset)
+            .unwrap();
+        linker
+            .func_profiled("load_mesh_asset", load_mesh_asset)
+            .unwrap();
+        linker
+            .func_profiled("push_sound_asset", push_sound_asset)
+            .unwrap();
+        linker
+            .func_profiled("sound_loop_start", sound_loop_start)
+            .unwrap();
+        linker
+            .func_profiled("sound_loop_end", sound_loop_end)
+            .unwrap();
+    }
+
+    fn get(&self, id: i32) -> Option<&Asset> {
+        Self::find(&self.assets, id)
+    }
+
+    fn find(assets: &[Asset], id: i32) -> Option<&Asset> {
+        let asset = assets.get(usize::try_from(id).ok()?);
+        if asset.is_none() {
+            println!("Invalid asset id {id}");
+        }
+        asset
+    }
+
+    /// Size in bytes of what [`Self::bytes`] returns, without decoding.
+    fn len(asset: &Asset) -> usize {
+        match &asset.data {
+            AssetData::Sound { samples, .. } => samples.len() * size_of::<f32>(),
+            _ => Self::bytes(asset).len(),
+        }
+    }
+
+    /// The bytes `read_asset` copies out. Textures give their pixels, meshes
+    /// their vertices and sounds their decoded samples.
+    fn bytes(asset: &Asset) -> Cow<'_, [u8]> {
+        match &asset.data {
+            AssetData::Texture { pixels, .. } => Cow::Borrowed(pixels),
+            AssetData::Mesh { vertices, .. } => Cow::Borrowed(cast_slice(vertices)),
+            AssetData::Sound {
+                channels, samples, ..
+            } => Cow::Owned(cast_slice(&samples.decode(*channels)).to_vec()),
+            AssetData::Raw(bytes) => Cow::Borrowed(bytes),
+        }
+    }
+
+    /// Loop points of a sound asset, or `None` for anything else.
+    fn loop_points(&self, id: i32) -> Option<(u32, u32)> {
+        match &self.get(id)?.data {
+            AssetData::Sound { loop_points, .. } => *loop_points,
+            _ => None,
+        }
+    }
+}
+
+/// `asset_id(name_ptr, name_len)` with a UTF-8 name. Returns -1 if the ROM
+/// has no asset with that name.
+fn asset_id(mut caller: Caller<WasmContexts>, name_ptr: i32, name_len: i32) -> i32 {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let (data, store) = mem.data_and_store_mut(&mut caller);
+
+    let Some(name) = data
+        .get(name_ptr as u32 as usize..)
+        .and_then(|arr| arr.get(..name_len as u32 as usize))
+    else {
+        println!("Called asset_id with an out of bounds name.");
+        return -1;
+    };
+
+    store
+        .assets
+        .assets
+        .iter()
+        .position(|asset| asset.name.as_bytes() == name)
+        .map_or(-1, |id| id as i32)
+}
+
+/// Size in bytes of what `read_asset` would copy, or -1 for an invalid id or
+/// a size that doesn't fit an `i32`.
+fn asset_len(caller: Caller<WasmContexts>, id: i32) -> i32 {
+    caller
+        .data()
+        .assets
+        .get(id)
+        .and_then(|asset| i32::try_from(AssetContext::len(asset)).ok())
+        .unwrap_or(-1)
+}
+
+/// Copies up to `len` bytes of an asset to `ptr`, returning how many were
+/// copied or -1 on failure.
+fn read_asset(mut caller: Caller<WasmContexts>, id: i32, ptr: i32, len: i32) -> i32 {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let (data, store) = mem.data_and_store_mut(&mut caller);
+
+    let Some(asset) = store.assets.get(id) else {
+        return -1;
+    };
+    let bytes = AssetContext::bytes(asset);
+    let count = bytes.len().min(len.max(0) as usize);
+
+    let Some(target) = data
+        .get_mut(ptr as u32 as usize..)
+        .and_then(|arr| arr.get_mut(..count))
+    else {
+        println!("Called read_asset with an out of bounds buffer.");
+        return -1;
+    };
+
+    target.copy_from_slice(&bytes[..count]);
+    count as i32
+}
+
+/// Uploads a texture asset, returning an id for `set_texture`.
+fn load_texture_asset(mut caller: Caller<WasmContexts>, id: i32) -> i32 {
+    if caller.data().draw_3d.state != DrawContextState::Init {
+        println!("Called load_texture_asset outside of init.");
+        return -1;
+    }
+
+    let store = caller.data_mut();
+    let Some(asset) = store.assets.get(id) else {
+        return -1;
+    };
+
+    let AssetData::Texture {
+        width,
+        height,
+        has_alpha,
+        pixels,
+    } = &asset.data
+    else {
+        println!(
+            "Asset {:?} is a {}, not a texture.",
+            asset.name,
+            asset.data.kind_name()
+        );
+        return -1;
+    };
+
+    let channels = if *has_alpha { 4 } else { 3 };
+    if pixels.len() != *width as usize * *height as usize * channels {
+        println!(
+            "Texture asset {:?} has the wrong amount of pixels.",
+            asset.name
+        );
+        return -1;
+    }
+
+    store
+        .draw_3d
+        .load_texture(pixels, *width, *height, *has_alpha)
+}
+
+/// Uploads a mesh asset, returning an id for `draw_static_mesh`, or for
+/// `draw_static_mesh_indexed` if the mesh has indices.
+fn load_mesh_asset(mut caller: Caller<WasmContexts>, id: i32) -> i32 {
+    if caller.data().draw_3d.state != DrawContextState::Init {
+        println!("Called load_mesh_asset outside of init.");
+        return -1;
+    }
+
+    let store = caller.data_mut();
+    let Some(asset) = store.assets.get(id) else {
+        return -1;
+    };
+
+    let AssetData::Mesh {
+        pipeline,
+        vertices,
+        indices,
+    } = &asset.data
+    else {
+        println!(
+            "Asset {:?} is a {}, not a mesh.",
+            asset.name,
+            asset.data.kind_name()
+        );
+        return -1;
+    };
+
+    let Ok(pipeline) = Pipeline::try_from(*pipeline) else {
+        println!("Mesh asset {:?} has an invalid pipeline.", asset.name);
+        return -1;
+    };
+
+    let attribute_count = pipeline.floats_per_vertex();
+    if !vertices.len().is_multiple_of(attribute_count) {
+        println!(
+            "Mesh asset {:?} has {} floats, which don't fit {pipeline:?} vertices of {attribute_count}.",
+            asset.name,
+            vertices.len()
+        );
+        return -1;
+    }
+    let vertex_count = vertices.len() / attribute_count;
+    if let Some(index) = indices
+        .iter()
+        .flatten()
+        .find(|&&index| index as usize >= vertex_count)
+    {
+        println!(
+            "Mesh asset {:?} has index {index} past its {vertex_count} vertices.",
+            asset.name
+        );
+        return -1;
+    }
+
+    match indices {
+        Some(indices) => store
+            .draw_3d
+            .load_static_mesh_indexed(vertices, indices, pipeline),
+        None => store.draw_3d.load_static_mesh(vertices, pipeline),
+    }
+}
+
+/// Plays a sound asset as if its samples were passed to `push_audio`.
+fn push_sound_asset(mut caller: Caller<WasmContexts>, id: i32) {
+    let store = caller.data_mut();
+    // Keeps the asset borrowed while the decode cache is updated
+    let assets = store.assets.assets.clone();
+    let Some(asset) = AssetContext::find(&assets, id) else {
+        return;
+    };
+
+    let AssetData::Sound {
+        channels,
+        sample_rate,
+        samples,
+        ..
+    } = &asset.data
+    else {
+        println!(
+            "Asset {:?} is a {}, not a sound.",
+            asset.name,
+            asset.data.kind_name()
+        );
+        return;
+    };
+
+    let (channels, sample_rate) = (*channels, *sample_rate);
+    let data = store
+        .assets
+        .decoded_sounds
+        .entry(id as usize)
+        .or_insert_with(|| Arc::new(samples.decode(channels).into_boxed_slice()))
+        .clone();
+
+    store.audio.pushed_audio.push(PushedAudio {
+        channels,
+        data,
+        sample_rate,
+    });
+}
+
+/// First frame of a sound asset's loop, or -1 if it doesn't loop.
+fn sound_loop_start(caller: Caller<WasmContexts>, id: i32) -> i32 {
+    caller
+        .data()
+        .assets
+        .loop_points(id)
+        .map_or(-1, |(start, _)| start as i32)
+}
+
+/// Frame after the end of a sound asset's loop, or -1 if it doesn't loop.
+fn sound_loop_end(caller: Caller<WasmContexts>, id: i32) -> i32 {
+    caller
+        .data()
+        .assets
+        .loop_points(id)
+        .map_or(-1, |(_, end)| end as i32)
+}
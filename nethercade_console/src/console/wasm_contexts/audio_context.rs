@@ -3,7 +3,7 @@ use std::sync::Arc;
 use bytemuck::cast_slice;
 use wasmtime::{Caller, Linker};
 
-use super::WasmContexts;
+use super::{ProfiledLinker, WasmContexts};
 
 pub struct AudioContext {
     pub pushed_audio: Vec<PushedAudio>,
@@ -24,7 +24,7 @@ impl AudioContext {
     }
 
     pub fn link(linker: &mut Linker<WasmContexts>) {
-        linker.func_wrap("env", "push_audio", push_audio).unwrap();
+        linker.func_profiled("push_audio", push_audio).unwrap();
     }
 
     fn push_audio(&mut self, channels: u16, data: &[f32], sample_rate: u32) {
@@ -3,6 +3,8 @@ use wasmtime::{Caller, Linker};
 
 use crate::console::WasmContexts;
 
+use super::ProfiledLinker;
+
 pub struct DataContext {
     resolution: Resolution,
     frame_rate: FrameRate,
@@ -18,22 +20,22 @@ impl DataContext {
 
     pub fn link(linker: &mut Linker<WasmContexts>) {
         linker
-            .func_wrap("env", "width", |caller: Caller<WasmContexts>| {
+            .func_profiled("width", |caller: Caller<WasmContexts>| {
                 caller.data().data.width()
             })
             .unwrap();
         linker
-            .func_wrap("env", "height", |caller: Caller<WasmContexts>| {
+            .func_profiled("height", |caller: Caller<WasmContexts>| {
                 caller.data().data.height()
             })
             .unwrap();
         linker
-            .func_wrap("env", "fps", |caller: Caller<WasmContexts>| {
+            .func_profiled("fps", |caller: Caller<WasmContexts>| {
                 caller.data().data.fps()
             })
             .unwrap();
         linker
-            .func_wrap("env", "frame_time", |caller: Caller<WasmContexts>| {
+            .func_profiled("frame_time", |caller: Caller<WasmContexts>| {
                 caller.data().data.frame_time()
             })
             .unwrap();
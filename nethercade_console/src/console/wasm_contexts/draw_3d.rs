@@ -7,10 +7,10 @@ use wasmtime::{Caller, Linker};
 use crate::graphics::{
     VirtualGpu,
     pipeline::Pipeline,
-    virtual_render_pass::{Command, VirtualRenderPass},
+    virtual_render_pass::{Command, RenderPassStats, VirtualRenderPass},
 };
 
-use super::WasmContexts;
+use super::{ProfiledLinker, WasmContexts};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum DrawContextState {
@@ -21,66 +21,79 @@ pub enum DrawContextState {
 
 pub struct Draw3dContext {
     pub vrp: VirtualRenderPass,
-    pub vgpu: Rc<RefCell<VirtualGpu>>,
+    /// `None` when running headless. Draw calls are then ignored and loads
+    /// hand out ids without uploading anything.
+    pub vgpu: Option<Rc<RefCell<VirtualGpu>>>,
+    headless_loads: HeadlessLoads,
     pub state: DrawContextState,
+    /// Stats of the most recently submitted frame.
+    pub last_stats: RenderPassStats,
+}
+
+/// Resource counts used to hand out ids when there is no GPU.
+#[derive(Default)]
+struct HeadlessLoads {
+    textures: usize,
+    meshes: usize,
+    indexed_meshes: usize,
 }
 
 impl Draw3dContext {
-    pub fn new(vgpu: Rc<RefCell<VirtualGpu>>) -> Self {
+    pub fn new(vgpu: Option<Rc<RefCell<VirtualGpu>>>) -> Self {
         Self {
             vrp: VirtualRenderPass::new(),
             vgpu,
+            headless_loads: HeadlessLoads::default(),
             state: DrawContextState::Invalid,
+            last_stats: RenderPassStats::default(),
         }
     }
 
     pub fn link(linker: &mut Linker<WasmContexts>) {
         // Drawing
         linker
-            .func_wrap("env", "draw_tri_list", draw_tri_list)
+            .func_profiled("draw_tri_list", draw_tri_list)
             .unwrap();
         linker
-            .func_wrap("env", "draw_tri_list_indexed", draw_tri_list_indexed)
+            .func_profiled("draw_tri_list_indexed", draw_tri_list_indexed)
             .unwrap();
         linker
-            .func_wrap("env", "push_model_matrix", push_model_matrix)
+            .func_profiled("push_model_matrix", push_model_matrix)
             .unwrap();
         linker
-            .func_wrap("env", "push_proj_matrix", push_proj_matrix)
+            .func_profiled("push_proj_matrix", push_proj_matrix)
             .unwrap();
         linker
-            .func_wrap("env", "push_view_matrix_pos", push_view_matrix_pos)
+            .func_profiled("push_view_matrix_pos", push_view_matrix_pos)
             .unwrap();
         linker
-            .func_wrap("env", "draw_static_mesh", draw_static_mesh)
+            .func_profiled("draw_static_mesh", draw_static_mesh)
             .unwrap();
         linker
-            .func_wrap("env", "draw_static_mesh_indexed", draw_static_mesh_indexed)
+            .func_profiled("draw_static_mesh_indexed", draw_static_mesh_indexed)
             .unwrap();
-        linker.func_wrap("env", "draw_sprite", draw_sprite).unwrap();
-        linker.func_wrap("env", "set_texture", set_texture).unwrap();
-        linker.func_wrap("env", "set_matcap", set_matcap).unwrap();
+        linker.func_profiled("draw_sprite", draw_sprite).unwrap();
+        linker.func_profiled("set_texture", set_texture).unwrap();
+        linker.func_profiled("set_matcap", set_matcap).unwrap();
         linker
-            .func_wrap("env", "clear_textures", clear_textures)
+            .func_profiled("clear_textures", clear_textures)
             .unwrap();
         linker
-            .func_wrap("env", "set_winding_order", set_winding_order)
+            .func_profiled("set_winding_order", set_winding_order)
             .unwrap();
 
         // Loading
+        linker.func_profiled("load_texture", load_texture).unwrap();
         linker
-            .func_wrap("env", "load_texture", load_texture)
-            .unwrap();
-        linker
-            .func_wrap("env", "load_static_mesh", load_static_mesh)
+            .func_profiled("load_static_mesh", load_static_mesh)
             .unwrap();
         linker
-            .func_wrap("env", "load_static_mesh_indexed", load_static_mesh_indexed)
+            .func_profiled("load_static_mesh_indexed", load_static_mesh_indexed)
             .unwrap();
     }
 
     pub fn draw_tri_list(&mut self, data: &[f32], pipeline: Pipeline) {
-        let attribute_count = pipeline.get_attribute_count();
+        let attribute_count = pipeline.floats_per_vertex();
         let total_attributes = data.len();
         let vertex_count = total_attributes / attribute_count;
 
@@ -89,8 +102,12 @@ impl Draw3dContext {
             return;
         }
 
-        self.vgpu.borrow().queue.write_buffer(
-            &self.vgpu.borrow().immediate_renderer.vertex_buffer,
+        let Some(vgpu) = &self.vgpu else {
+            return;
+        };
+
+        vgpu.borrow().queue.write_buffer(
+            &vgpu.borrow().immediate_renderer.vertex_buffer,
             self.vrp.immediate_buffer_last_index,
             bytemuck::cast_slice(data),
         );
@@ -106,49 +123,55 @@ impl Draw3dContext {
     }
 
     pub fn push_model_matrix(&mut self, model: Mat4) {
+        let Some(vgpu) = &self.vgpu else {
+            return;
+        };
+
         let offset_model = self.vrp.model_matrix_count * size_of::<Mat4>() as u64;
-        self.vgpu.borrow().queue.write_buffer(
-            &self.vgpu.borrow().immediate_renderer.model_buffer,
+        vgpu.borrow().queue.write_buffer(
+            &vgpu.borrow().immediate_renderer.model_buffer,
             offset_model,
             bytes_of(&model),
         );
-        self.vrp.push_model_matrix(
-            &self.vgpu.borrow().instance_buffer,
-            &self.vgpu.borrow().queue,
-        );
+        self.vrp
+            .push_model_matrix(&vgpu.borrow().instance_buffer, &vgpu.borrow().queue);
     }
 
     pub fn push_view_matrix_pos(&mut self, view: Mat4, pos: Vec3) {
+        let Some(vgpu) = &self.vgpu else {
+            return;
+        };
+
         let offset_view = self.vrp.view_pos_count * size_of::<Mat4>() as u64;
-        self.vgpu.borrow().queue.write_buffer(
-            &self.vgpu.borrow().immediate_renderer.view_buffer,
+        vgpu.borrow().queue.write_buffer(
+            &vgpu.borrow().immediate_renderer.view_buffer,
             offset_view,
             bytes_of(&view),
         );
         // Wrong type to correctly pad here
         let offset_pos = self.vrp.view_pos_count * size_of::<Vec4>() as u64;
-        self.vgpu.borrow().queue.write_buffer(
-            &self.vgpu.borrow().immediate_renderer.camera_pos_buffer,
+        vgpu.borrow().queue.write_buffer(
+            &vgpu.borrow().immediate_renderer.camera_pos_buffer,
             offset_pos,
             bytes_of(&pos),
         );
-        self.vrp.push_view_pos(
-            &self.vgpu.borrow().instance_buffer,
-            &self.vgpu.borrow().queue,
-        );
+        self.vrp
+            .push_view_pos(&vgpu.borrow().instance_buffer, &vgpu.borrow().queue);
     }
 
     pub fn push_projection_matrix(&mut self, proj: Mat4) {
+        let Some(vgpu) = &self.vgpu else {
+            return;
+        };
+
         let offset_proj = self.vrp.projection_matrix_count * size_of::<Mat4>() as u64;
-        self.vgpu.borrow().queue.write_buffer(
-            &self.vgpu.borrow().immediate_renderer.proj_buffer,
+        vgpu.borrow().queue.write_buffer(
+            &vgpu.borrow().immediate_renderer.proj_buffer,
             offset_proj,
             bytes_of(&proj),
         );
-        self.vrp.push_proj_matrix(
-            &self.vgpu.borrow().instance_buffer,
-            &self.vgpu.borrow().queue,
-        );
+        self.vrp
+            .push_proj_matrix(&vgpu.borrow().instance_buffer, &vgpu.borrow().queue);
     }
 
     pub fn draw_static_mesh(&mut self, index: usize) {
@@ -185,29 +208,47 @@ impl Draw3dContext {
         self.vrp.commands.push(Command::SetWindingOrder(clockwise))
     }
 
-    fn load_texture(&mut self, data: &[u8], width: u32, height: u32, has_alpha: bool) -> i32 {
-        self.vgpu
-            .borrow_mut()
+    pub fn load_texture(&mut self, data: &[u8], width: u32, height: u32, has_alpha: bool) -> i32 {
+        let Some(vgpu) = &self.vgpu else {
+            self.headless_loads.textures += 1;
+            return self.headless_loads.textures as i32 - 1;
+        };
+
+        vgpu.borrow_mut()
             .load_texture_raw(data, width, height, has_alpha) as i32
     }
 
-    fn load_static_mesh(&mut self, data: &[f32], pipeline: Pipeline) -> i32 {
-        self.vgpu.borrow_mut().load_static_mesh(data, pipeline) as i32
+    pub fn load_static_mesh(&mut self, data: &[f32], pipeline: Pipeline) -> i32 {
+        let Some(vgpu) = &self.vgpu else {
+            self.headless_loads.meshes += 1;
+            return self.headless_loads.meshes as i32 - 1;
+        };
+
+        vgpu.borrow_mut().load_static_mesh(data, pipeline) as i32
     }
 
-    fn load_static_mesh_indexed(
+    pub fn load_static_mesh_indexed(
         &mut self,
         data: &[f32],
         indices: &[u16],
         pipeline: Pipeline,
     ) -> i32 {
-        self.vgpu
-            .borrow_mut()
+        let Some(vgpu) = &self.vgpu else {
+            self.headless_loads.indexed_meshes += 1;
+            return self.headless_loads.indexed_meshes as i32 - 1;
+        };
+
+        vgpu.borrow_mut()
             .load_static_mesh_indexed(data, indices, pipeline) as i32
     }
 
     pub fn render(&mut self) {
-        self.vgpu.borrow_mut().render(&self.vrp);
+        let Some(vgpu) = &self.vgpu else {
+            return;
+        };
+
+        self.last_stats = self.vrp.stats(&vgpu.borrow());
+        vgpu.borrow_mut().render(&self.vrp);
     }
 }
 
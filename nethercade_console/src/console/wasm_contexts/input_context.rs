@@ -1,6 +1,8 @@
 use paste::paste;
 
 use crate::console::WasmContexts;
+
+use super::ProfiledLinker;
 use crate::console::input::{ButtonCode, InputState, MOUSE_INVALID_BIT, PlayerInputEntry};
 
 use wasmtime::{Caller, Linker};
@@ -37,32 +39,32 @@ macro_rules! derive_generate_input_api {
             impl InputContext {
                 pub fn link(linker: &mut Linker<WasmContexts>) {
                     $(linker
-                        .func_wrap("env", stringify!([<button_ $btn_name _pressed>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<button_ $btn_name _pressed>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<button_ $btn_name _pressed>](p)
                         })
                         .unwrap();
 
                     linker
-                        .func_wrap("env", stringify!([<button_ $btn_name _released>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<button_ $btn_name _released>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<button_ $btn_name _released>](p)
                         })
                         .unwrap();
 
                     linker
-                        .func_wrap("env", stringify!([<button_ $btn_name _held>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<button_ $btn_name _held>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<button_ $btn_name _held>](p)
                         })
                         .unwrap();
                     )*
 
                     $(linker
-                        .func_wrap("env", stringify!([<analog_ $anlg_name _x>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<analog_ $anlg_name _x>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<analog_ $anlg_name _x>](p)
                         })
                         .unwrap();
 
                     linker
-                        .func_wrap("env", stringify!([<analog_ $anlg_name _y>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<analog_ $anlg_name _y>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<analog_ $anlg_name _y>](p)
                         })
                         .unwrap();
@@ -70,7 +72,7 @@ macro_rules! derive_generate_input_api {
 
                     $(
                         linker
-                        .func_wrap("env", stringify!([<trigger_ $trg_name>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<trigger_ $trg_name>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<trigger_ $trg_name>](p)
                         })
                         .unwrap();
@@ -78,19 +80,19 @@ macro_rules! derive_generate_input_api {
 
                     $(
                         linker
-                        .func_wrap("env", stringify!([<mouse_ $mbtn_name _pressed>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<mouse_ $mbtn_name _pressed>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<mouse_ $mbtn_name _pressed>](p)
                         })
                         .unwrap();
 
                         linker
-                        .func_wrap("env", stringify!([<mouse_ $mbtn_name _released>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<mouse_ $mbtn_name _released>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<mouse_ $mbtn_name _released>](p)
                         })
                         .unwrap();
 
                         linker
-                        .func_wrap("env", stringify!([<mouse_ $mbtn_name _held>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<mouse_ $mbtn_name _held>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<mouse_ $mbtn_name _held>](p)
                         })
                         .unwrap();
@@ -98,13 +100,13 @@ macro_rules! derive_generate_input_api {
 
                     $(
                         linker
-                        .func_wrap("env", stringify!([<mouse_ $maxis_name _pos>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<mouse_ $maxis_name _pos>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<mouse_ $maxis_name _pos>](p)
                         })
                         .unwrap();
 
                         linker
-                        .func_wrap("env", stringify!([<mouse_ $maxis_name _delta>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<mouse_ $maxis_name _delta>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<mouse_ $maxis_name _delta>](p)
                         })
                         .unwrap();
@@ -112,26 +114,26 @@ macro_rules! derive_generate_input_api {
 
                     $(
                         linker
-                        .func_wrap("env", stringify!([<mouse_wheel_ $mwheel_name>]), |caller: Caller<WasmContexts>, p: i32| {
+                        .func_profiled(stringify!([<mouse_wheel_ $mwheel_name>]), |caller: Caller<WasmContexts>, p: i32| {
                             caller.data().input.[<mouse_wheel_ $mwheel_name>](p)
                         })
                         .unwrap();
                     )*
 
                     linker
-                    .func_wrap("env", "raw_mouse_state", |caller: Caller<WasmContexts>, p: i32| {
+                    .func_profiled("raw_mouse_state", |caller: Caller<WasmContexts>, p: i32| {
                         caller.data().input.raw_mouse_state(p)
                     })
                     .unwrap();
 
                     linker
-                    .func_wrap("env", "raw_input_state", |caller: Caller<WasmContexts>, p: i32| {
+                    .func_profiled("raw_input_state", |caller: Caller<WasmContexts>, p: i32| {
                         caller.data().input.raw_input_state(p)
                     })
                     .unwrap();
 
                     linker
-                    .func_wrap("env", "lock_mouse", |mut caller: Caller<WasmContexts>, p: i32| {
+                    .func_profiled("lock_mouse", |mut caller: Caller<WasmContexts>, p: i32| {
                         caller.data_mut().input.lock_mouse(p)
                     })
                     .unwrap();
@@ -0,0 +1,241 @@
+use wasmtime::{Caller, Linker};
+
+use super::{ProfiledLinker, WasmContexts};
+
+/// Software math shared by every host so results are bit-identical across
+/// platforms and guest toolchains. Anything that feeds game state during
+/// netplay should go through these instead of the guest's own libm.
+pub struct MathContext;
+
+type Mat4 = [[f32; 4]; 4];
+type Vec3 = [f32; 3];
+
+impl MathContext {
+    pub fn link(linker: &mut Linker<WasmContexts>) {
+        linker.func_profiled("sin", libm::sinf).unwrap();
+        linker.func_profiled("cos", libm::cosf).unwrap();
+        linker.func_profiled("tan", libm::tanf).unwrap();
+        linker.func_profiled("atan2", libm::atan2f).unwrap();
+        linker.func_profiled("sqrt", libm::sqrtf).unwrap();
+        linker.func_profiled("exp", libm::expf).unwrap();
+        linker.func_profiled("log", libm::logf).unwrap();
+        linker.func_profiled("pow", libm::powf).unwrap();
+
+        linker.func_profiled("perspective", perspective).unwrap();
+        linker.func_profiled("look_at", look_at).unwrap();
+        linker.func_profiled("mat4_mul", mat4_mul).unwrap();
+    }
+}
+
+/// Right handed perspective projection with reversed depth (near maps to 1,
+/// far to 0) to match the console's depth buffer.
+fn perspective_matrix(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    let h = libm::cosf(0.5 * fov_y) / libm::sinf(0.5 * fov_y);
+    let w = h / aspect;
+    let r = near / (far - near);
+
+    [
+        [w, 0.0, 0.0, 0.0],
+        [0.0, h, 0.0, 0.0],
+        [0.0, 0.0, r, -1.0],
+        [0.0, 0.0, r * far, 0.0],
+    ]
+}
+
+/// Right handed view matrix, equivalent to `glam::Mat4::look_at_rh`.
+fn look_at_matrix(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+    let f = normalize(sub(target, eye));
+    let s = normalize(cross(f, up));
+    let u = cross(s, f);
+
+    [
+        [s[0], u[0], -f[0], 0.0],
+        [s[1], u[1], -f[1], 0.0],
+        [s[2], u[2], -f[2], 0.0],
+        [-dot(eye, s), -dot(eye, u), dot(eye, f), 1.0],
+    ]
+}
+
+/// Column major `a * b`, summed in a fixed order so no platform can fuse or
+/// reorder the operations.
+fn mul_matrix(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [[0.0; 4]; 4];
+    for col in 0..4 {
+        for row in 0..4 {
+            let mut sum = 0.0;
+            for k in 0..4 {
+                sum += a[k][row] * b[col][k];
+            }
+            out[col][row] = sum;
+        }
+    }
+    out
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: Vec3) -> Vec3 {
+    let inv_len = 1.0 / libm::sqrtf(dot(v, v));
+    [v[0] * inv_len, v[1] * inv_len, v[2] * inv_len]
+}
+
+fn read_vec3(data: &[u8], ptr: i32) -> Result<Vec3, wasmtime::Error> {
+    read_floats(data, ptr)
+}
+
+fn read_matrix(data: &[u8], ptr: i32) -> Result<Mat4, wasmtime::Error> {
+    let floats: [f32; 16] = read_floats(data, ptr)?;
+    Ok(std::array::from_fn(|col| {
+        std::array::from_fn(|row| floats[col * 4 + row])
+    }))
+}
+
+fn read_floats<const N: usize>(data: &[u8], ptr: i32) -> Result<[f32; N], wasmtime::Error> {
+    match data
+        .get(ptr as u32 as usize..)
+        .and_then(|arr| arr.get(..N * size_of::<f32>()))
+    {
+        Some(bytes) => Ok(std::array::from_fn(|i| {
+            f32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap())
+        })),
+        None => Err(wasmtime::Error::msg("invalid data")),
+    }
+}
+
+fn write_matrix(data: &mut [u8], ptr: i32, matrix: &Mat4) -> Result<(), wasmtime::Error> {
+    match data
+        .get_mut(ptr as u32 as usize..)
+        .and_then(|arr| arr.get_mut(..size_of::<Mat4>()))
+    {
+        Some(bytes) => {
+            for (chunk, value) in bytes.chunks_exact_mut(4).zip(matrix.as_flattened()) {
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+            Ok(())
+        }
+        None => Err(wasmtime::Error::msg("invalid data")),
+    }
+}
+
+fn perspective(
+    mut caller: Caller<WasmContexts>,
+    fov_y: f32,
+    aspect: f32,
+    near: f32,
+    far: f32,
+    out_ptr: i32,
+) -> Result<(), wasmtime::Error> {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let data = mem.data_mut(&mut caller);
+    write_matrix(data, out_ptr, &perspective_matrix(fov_y, aspect, near, far))
+}
+
+fn look_at(
+    mut caller: Caller<WasmContexts>,
+    eye_ptr: i32,
+    target_ptr: i32,
+    up_ptr: i32,
+    out_ptr: i32,
+) -> Result<(), wasmtime::Error> {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let data = mem.data_mut(&mut caller);
+    let eye = read_vec3(data, eye_ptr)?;
+    let target = read_vec3(data, target_ptr)?;
+    let up = read_vec3(data, up_ptr)?;
+    write_matrix(data, out_ptr, &look_at_matrix(eye, target, up))
+}
+
+fn mat4_mul(
+    mut caller: Caller<WasmContexts>,
+    a_ptr: i32,
+    b_ptr: i32,
+    out_ptr: i32,
+) -> Result<(), wasmtime::Error> {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let data = mem.data_mut(&mut caller);
+    let a = read_matrix(data, a_ptr)?;
+    let b = read_matrix(data, b_ptr)?;
+    write_matrix(data, out_ptr, &mul_matrix(&a, &b))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4 as GlamMat4, Vec3 as GlamVec3};
+
+    use super::*;
+
+    fn assert_close(actual: &Mat4, expected: GlamMat4) {
+        let expected = expected.to_cols_array_2d();
+        for (actual, expected) in actual.as_flattened().iter().zip(expected.as_flattened()) {
+            assert!(
+                (actual - expected).abs() <= 1e-5 * expected.abs().max(1.0),
+                "{actual} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn perspective_matches_glam() {
+        let (fov_y, aspect, near, far) = (1.2, 16.0 / 9.0, 0.1, 500.0);
+        // Reversed depth is a regular projection with near and far swapped
+        assert_close(
+            &perspective_matrix(fov_y, aspect, near, far),
+            GlamMat4::perspective_rh(fov_y, aspect, far, near),
+        );
+
+        // and tends to the infinite one as far grows
+        assert_close(
+            &perspective_matrix(fov_y, aspect, near, 1e9),
+            GlamMat4::perspective_infinite_reverse_rh(fov_y, aspect, near),
+        );
+    }
+
+    #[test]
+    fn look_at_matches_glam() {
+        let (eye, target, up) = ([3.0, 2.0, -5.0], [0.5, 0.0, 1.0], [0.0, 1.0, 0.0]);
+        assert_close(
+            &look_at_matrix(eye, target, up),
+            GlamMat4::look_at_rh(
+                GlamVec3::from(eye),
+                GlamVec3::from(target),
+                GlamVec3::from(up),
+            ),
+        );
+    }
+
+    #[test]
+    fn mul_matches_glam() {
+        let a = GlamMat4::from_cols_array(&std::array::from_fn(|i| i as f32 * 0.5 - 3.0));
+        let b = GlamMat4::from_cols_array(&std::array::from_fn(|i| (i * i) as f32 * 0.25));
+        assert_close(
+            &mul_matrix(&a.to_cols_array_2d(), &b.to_cols_array_2d()),
+            a * b,
+        );
+    }
+
+    #[test]
+    fn matrices_are_written_little_endian() {
+        let matrix: Mat4 =
+            std::array::from_fn(|col| std::array::from_fn(|row| (col * 4 + row) as f32));
+        let mut data = vec![0; 4 + size_of::<Mat4>()];
+        write_matrix(&mut data, 4, &matrix).unwrap();
+
+        assert_eq!(data[8..12], 1.0f32.to_le_bytes());
+        assert_eq!(read_matrix(&data, 4).unwrap(), matrix);
+        assert!(write_matrix(&mut data, 8, &matrix).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use std::{cell::RefCell, rc::Rc};
 
-use nethercade_core::Rom;
+use nethercade_core::{Rom, console_spec::MAX_MEMORY_SIZE};
+
+mod asset_context;
+use asset_context::AssetContext;
 
 mod audio_context;
 pub use audio_context::PushedAudio;
@@ -16,13 +19,24 @@ use draw_3d::*;
 mod input_context;
 use input_context::InputContext;
 
+mod math_context;
+use math_context::MathContext;
+
+mod panic_context;
+pub use panic_context::GuestAbort;
+use panic_context::PanicContext;
+
+mod profiler_context;
+use profiler_context::ProfiledLinker;
+pub use profiler_context::{FrameTimings, ImportStats, Phase, ProfilerContext};
+
 mod random_context;
 use random_context::RandomContext;
 
 mod text_context;
 use text_context::TextContext;
 
-use wasmtime::Linker;
+use wasmtime::{Linker, StoreLimits, StoreLimitsBuilder};
 
 use crate::graphics::VirtualGpu;
 
@@ -31,15 +45,19 @@ pub struct WasmContexts {
     pub draw_3d: Draw3dContext,
     pub input: InputContext,
     pub audio: AudioContext,
+    pub assets: AssetContext,
     pub random: RandomContext,
     pub _text: TextContext,
+    pub profiler: ProfilerContext,
+    /// Keeps guest memory within what the console allocates.
+    pub limits: StoreLimits,
     // TODO: Add Multiplayer Context
 }
 
 impl WasmContexts {
     pub fn new(
         rom: &Rom,
-        vgpu: Rc<RefCell<VirtualGpu>>,
+        vgpu: Option<Rc<RefCell<VirtualGpu>>>,
         num_player: usize,
         random_seed: u64,
     ) -> Self {
@@ -48,8 +66,13 @@ impl WasmContexts {
             draw_3d: Draw3dContext::new(vgpu),
             input: InputContext::new(num_player),
             audio: AudioContext::new(),
+            assets: AssetContext::new(rom),
             random: RandomContext::new(random_seed),
             _text: TextContext {},
+            profiler: ProfilerContext::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_SIZE)
+                .build(),
         }
     }
 
@@ -58,7 +81,76 @@ impl WasmContexts {
         Draw3dContext::link(linker);
         InputContext::link(linker);
         AudioContext::link(linker);
+        AssetContext::link(linker);
         RandomContext::link(linker);
         TextContext::link(linker);
+        PanicContext::link(linker);
+        MathContext::link(linker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nethercade_core::{
+        Rom,
+        console_spec::{IMPORT_MODULE, IMPORTS, ImportSpec, MAX_MEMORY_SIZE, WasmType},
+    };
+    use wasmtime::{Engine, Extern, Linker, Store, ValType};
+
+    use super::WasmContexts;
+
+    fn to_wasm_type(ty: &ValType) -> Option<WasmType> {
+        match ty {
+            ValType::I32 => Some(WasmType::I32),
+            ValType::I64 => Some(WasmType::I64),
+            ValType::F32 => Some(WasmType::F32),
+            ValType::F64 => Some(WasmType::F64),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_linker_matches_console_spec() {
+        let engine = Engine::default();
+        let mut linker = Linker::new(&engine);
+        WasmContexts::link(&mut linker);
+        let contexts = WasmContexts::new(&Rom::from_code(&[]), None, 1, 0);
+        let mut store = Store::new(&engine, contexts);
+
+        let linked = linker.iter(&mut store).collect::<Vec<_>>();
+        assert_eq!(linked.len(), IMPORTS.len());
+
+        for (module, name, item) in linked {
+            assert_eq!(module, IMPORT_MODULE);
+            let spec = ImportSpec::find(name).unwrap_or_else(|| panic!("{name} isn't in the spec"));
+            let Extern::Func(func) = item else {
+                panic!("{name} isn't a function");
+            };
+
+            let ty = func.ty(&store);
+            let params = ty.params().map(|ty| to_wasm_type(&ty)).collect::<Vec<_>>();
+            let results = ty.results().map(|ty| to_wasm_type(&ty)).collect::<Vec<_>>();
+            let expected_params = spec.params.iter().copied().map(Some).collect::<Vec<_>>();
+            let expected_results = spec.results.iter().copied().map(Some).collect::<Vec<_>>();
+            assert_eq!(params, expected_params, "{name} params");
+            assert_eq!(results, expected_results, "{name} results");
+        }
+    }
+
+    #[test]
+    fn guest_memory_stays_within_the_limit() {
+        // Traps in init unless growing past the limit fails
+        let guest = format!(
+            r#"(module
+              (memory (export "memory") 1)
+              (func (export "init")
+                (if (i32.ne (memory.grow (i32.const {})) (i32.const -1))
+                  (then unreachable)))
+              (func (export "update"))
+              (func (export "render")))"#,
+            MAX_MEMORY_SIZE / (64 * 1024)
+        );
+        let rom = Rom::from_code(&wat::parse_str(guest).unwrap());
+        assert!(crate::Emulator::new(rom, None, 1, 0).is_ok());
     }
 }
@@ -0,0 +1,209 @@
+use std::fmt;
+
+use wasmtime::{Caller, Linker};
+
+use super::{ProfiledLinker, WasmContexts};
+
+pub struct PanicContext;
+
+/// Raised when the guest calls `abort` or `panic`. Returned from the import as
+/// an error, which traps and unwinds out of the current wasm call.
+#[derive(Debug, Clone)]
+pub struct GuestAbort {
+    pub message: String,
+    pub location: Option<SourceLocation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for GuestAbort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} at {location}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for GuestAbort {}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+impl PanicContext {
+    pub fn link(linker: &mut Linker<WasmContexts>) {
+        linker.func_profiled("abort", abort).unwrap();
+        linker.func_profiled("panic", panic).unwrap();
+    }
+}
+
+/// AssemblyScript's `abort(message, fileName, lineNumber, columnNumber)`.
+/// Strings are UTF-16 with their byte length stored in the four bytes before
+/// the pointer. Either pointer may be null.
+fn abort(
+    mut caller: Caller<WasmContexts>,
+    msg_ptr: i32,
+    file_ptr: i32,
+    line: i32,
+    column: i32,
+) -> Result<(), wasmtime::Error> {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let data = mem.data(&caller);
+
+    let message =
+        read_assemblyscript_string(data, msg_ptr).unwrap_or_else(|| String::from("abort called"));
+    let location = read_assemblyscript_string(data, file_ptr).map(|file| SourceLocation {
+        file,
+        line: line as u32,
+        column: column as u32,
+    });
+
+    Err(wasmtime::Error::new(GuestAbort { message, location }))
+}
+
+/// `panic(msg_ptr, msg_len, file_ptr, file_len, line, column)` with UTF-8
+/// strings. A zero `file_len` means no location is known.
+fn panic(
+    mut caller: Caller<WasmContexts>,
+    msg_ptr: i32,
+    msg_len: i32,
+    file_ptr: i32,
+    file_len: i32,
+    line: i32,
+    column: i32,
+) -> Result<(), wasmtime::Error> {
+    let mem = caller.get_export("memory").unwrap().into_memory().unwrap();
+    let data = mem.data(&caller);
+
+    let message =
+        read_utf8_string(data, msg_ptr, msg_len).unwrap_or_else(|| String::from("panic called"));
+    let location = if file_len == 0 {
+        None
+    } else {
+        read_utf8_string(data, file_ptr, file_len).map(|file| SourceLocation {
+            file,
+            line: line as u32,
+            column: column as u32,
+        })
+    };
+
+    Err(wasmtime::Error::new(GuestAbort { message, location }))
+}
+
+fn read_utf8_string(data: &[u8], ptr: i32, len: i32) -> Option<String> {
+    let bytes = data
+        .get(ptr as u32 as usize..)
+        .and_then(|arr| arr.get(..len as u32 as usize))?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn read_assemblyscript_string(data: &[u8], ptr: i32) -> Option<String> {
+    let ptr = ptr as u32 as usize;
+    if ptr < 4 {
+        return None;
+    }
+
+    let len = data.get(ptr - 4..ptr)?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let bytes = data.get(ptr..)?.get(..len)?;
+
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    Some(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod tests {
+    use nethercade_core::Rom;
+
+    use super::*;
+
+    /// AssemblyScript string with its byte length before `ptr`.
+    fn assemblyscript_memory(ptr: usize, length: u32, bytes: &[u8]) -> Vec<u8> {
+        let mut data = vec![0; ptr + bytes.len()];
+        data[ptr - 4..ptr].copy_from_slice(&length.to_le_bytes());
+        data[ptr..].copy_from_slice(bytes);
+        data
+    }
+
+    #[test]
+    fn reads_valid_strings() {
+        assert_eq!(read_utf8_string(b"xxhello", 2, 5).as_deref(), Some("hello"));
+
+        let utf16 = "hé"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let data = assemblyscript_memory(8, 4, &utf16);
+        assert_eq!(read_assemblyscript_string(&data, 8).as_deref(), Some("hé"));
+    }
+
+    #[test]
+    fn out_of_bounds_strings_read_nothing() {
+        let data = [0; 16];
+        for (ptr, len) in [(12, 5), (17, 0), (-1, 1), (0, -1), (i32::MAX, i32::MAX)] {
+            assert_eq!(read_utf8_string(&data, ptr, len), None, "{ptr}, {len}");
+        }
+
+        // Null, a length that doesn't fit before the pointer, past the end
+        // and a length running past the end
+        let data = assemblyscript_memory(8, 100, &[0; 8]);
+        for ptr in [0, 3, 17, -4] {
+            assert_eq!(read_assemblyscript_string(&data, ptr), None, "{ptr}");
+        }
+        assert_eq!(read_assemblyscript_string(&data, 8), None);
+    }
+
+    #[test]
+    fn malformed_strings_are_replaced_not_rejected() {
+        // The trailing half of a code unit is dropped
+        let data = assemblyscript_memory(4, 3, &[b'h', 0, b'i']);
+        assert_eq!(read_assemblyscript_string(&data, 4).as_deref(), Some("h"));
+
+        assert_eq!(
+            read_utf8_string(&[b'o', 0xFF, b'k'], 0, 3).as_deref(),
+            Some("o\u{FFFD}k")
+        );
+    }
+
+    #[test]
+    fn bad_strings_still_stop_the_guest() {
+        let calls = [
+            // Invalid UTF-8 message, out of bounds file
+            "(call $panic (i32.const 16) (i32.const 2) (i32.const -1) (i32.const 8) (i32.const 1) (i32.const 2))",
+            // Out of bounds message, odd UTF-16 file length
+            "(call $abort (i32.const -1) (i32.const 24) (i32.const 1) (i32.const 2))",
+        ];
+        let expected = [("\u{FFFD}\u{FFFD}", None), ("abort called", Some("h:1:2"))];
+
+        for (call, (message, location)) in calls.iter().zip(expected) {
+            let guest = format!(
+                r#"(module
+                  (import "env" "panic" (func $panic (param i32 i32 i32 i32 i32 i32)))
+                  (import "env" "abort" (func $abort (param i32 i32 i32 i32)))
+                  (memory (export "memory") 1)
+                  (data (i32.const 16) "\ff\fe")
+                  (data (i32.const 20) "\03\00\00\00h\00i")
+                  (func (export "init") {call})
+                  (func (export "update"))
+                  (func (export "render")))"#
+            );
+            let rom = Rom::from_code(&wat::parse_str(guest).unwrap());
+            let Err(report) = crate::Emulator::new(rom, None, 1, 0) else {
+                panic!("{call} didn't stop the guest");
+            };
+            assert_eq!(report.message, message);
+            assert_eq!(report.location.as_deref(), location);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use wasmtime::{
+    CallHook, Caller, Engine, GuestProfiler, Linker, Module, StoreContextMut, UpdateDeadline,
+    WasmRet, WasmTy,
+};
+
+use super::WasmContexts;
+
+const PROFILES_DIRECTORY: &str = "profiles";
+const HISTORY_LENGTH: usize = 120;
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Host side timings for one rendered frame.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FrameTimings {
+    pub updates: u32,
+    pub update: Duration,
+    pub render: Duration,
+    /// Time spent turning the frame's draw calls into GPU commands.
+    pub submit: Duration,
+    pub snapshot: Duration,
+    pub restore: Duration,
+    /// Size of the last save state taken this frame.
+    pub snapshot_bytes: usize,
+    /// Frames simulated again because of a rollback.
+    pub rollback_frames: u32,
+    /// Time spent inside host imports, included in `update` and `render`.
+    /// Only measured while import statistics are collected.
+    pub host: Duration,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Phase {
+    Update,
+    Render,
+    Submit,
+    Snapshot,
+    Restore,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ImportStats {
+    pub calls: u64,
+    pub time: Duration,
+}
+
+struct Recording {
+    profiler: GuestProfiler,
+    first_frame: u32,
+    frames_left: u32,
+    last_sample: Instant,
+    ticker_running: Arc<AtomicBool>,
+}
+
+/// Measures where frame time goes: guest code, host imports or GPU submission.
+/// Host imports are only timed while enabled with
+/// [`GameInstance::set_profiling`](crate::console::GameInstance::set_profiling).
+pub struct ProfilerContext {
+    enabled: bool,
+    /// Whether [`Self::call_hook`] was installed on the store. Hooks can't be
+    /// removed, so it stays installed once profiling was first turned on.
+    pub(crate) call_hook_installed: bool,
+    current: FrameTimings,
+    pub history: VecDeque<FrameTimings>,
+
+    pub imports: HashMap<&'static str, ImportStats>,
+    /// Number of frames the import statistics were collected over.
+    pub import_frames: u32,
+    current_import: Option<&'static str>,
+    host_call_start: Option<Instant>,
+
+    recording: Option<Recording>,
+}
+
+impl ProfilerContext {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            call_hook_installed: false,
+            current: FrameTimings::default(),
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+            imports: HashMap::new(),
+            import_frames: 0,
+            current_import: None,
+            host_call_start: None,
+            recording: None,
+        }
+    }
+
+    pub fn record(&mut self, phase: Phase, time: Duration) {
+        let timings = &mut self.current;
+        match phase {
+            Phase::Update => {
+                timings.updates += 1;
+                timings.update += time;
+            }
+            Phase::Render => timings.render += time,
+            Phase::Submit => timings.submit += time,
+            Phase::Snapshot => timings.snapshot += time,
+            Phase::Restore => timings.restore += time,
+        }
+    }
+
+    pub fn record_snapshot_size(&mut self, bytes: usize) {
+        self.current.snapshot_bytes = bytes;
+    }
+
+    pub fn record_rollback(&mut self, frames: u32) {
+        self.current.rollback_frames += frames;
+    }
+
+    /// Closes the current frame. Called once per rendered frame.
+    pub fn end_frame(&mut self, frame: u32) {
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(std::mem::take(&mut self.current));
+
+        if self.enabled {
+            self.import_frames += 1;
+        }
+
+        if let Some(recording) = &mut self.recording {
+            recording.frames_left = recording.frames_left.saturating_sub(1);
+            if recording.frames_left == 0 {
+                self.finish_recording(frame);
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.host_call_start = None;
+    }
+
+    pub fn reset_imports(&mut self) {
+        self.imports.clear();
+        self.import_frames = 0;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Starts sampling the guest stack for the next `frames` rendered frames.
+    /// The result is written to `profiles/` in the Firefox profiler format.
+    pub fn start_recording(&mut self, engine: &Engine, module: &Module, frame: u32, frames: u32) {
+        if self.recording.is_some() || frames == 0 {
+            return;
+        }
+
+        let profiler = GuestProfiler::new(
+            "nethercade",
+            SAMPLE_INTERVAL,
+            vec![(String::from("game"), module.clone())],
+        );
+
+        // Epoch ticks are what interrupt the guest so a sample can be taken
+        let ticker_running = Arc::new(AtomicBool::new(true));
+        let running = ticker_running.clone();
+        let engine = engine.clone();
+        std::thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                std::thread::sleep(SAMPLE_INTERVAL);
+                engine.increment_epoch();
+            }
+        });
+
+        println!("Recording guest profile for {frames} frames");
+        self.recording = Some(Recording {
+            profiler,
+            first_frame: frame,
+            frames_left: frames,
+            last_sample: Instant::now(),
+            ticker_running,
+        });
+    }
+
+    fn finish_recording(&mut self, frame: u32) {
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+        recording.ticker_running.store(false, Ordering::Relaxed);
+
+        if let Err(e) = std::fs::create_dir_all(PROFILES_DIRECTORY) {
+            println!("Error creating {PROFILES_DIRECTORY}: {e}");
+            return;
+        }
+
+        let path = PathBuf::from(PROFILES_DIRECTORY)
+            .join(format!("frames_{}-{frame}.json", recording.first_frame));
+        let result = File::create(&path)
+            .map_err(wasmtime::Error::from)
+            .and_then(|file| recording.profiler.finish(file));
+
+        match result {
+            Ok(()) => println!(
+                "Saved guest profile to: {} (open with https://profiler.firefox.com)",
+                path.display()
+            ),
+            Err(e) => println!("Error writing {}: {e}", path.display()),
+        }
+    }
+
+    /// Installed with `Store::call_hook` once profiling is first turned on.
+    /// Times host calls and attributes them to the import that was entered.
+    pub fn call_hook(
+        mut store: StoreContextMut<WasmContexts>,
+        kind: CallHook,
+    ) -> Result<(), wasmtime::Error> {
+        let profiler = &store.data().profiler;
+        if !profiler.enabled && profiler.recording.is_none() {
+            return Ok(());
+        }
+
+        if let Some(mut recording) = store.data_mut().profiler.recording.take() {
+            recording.profiler.call_hook(&store, kind);
+            store.data_mut().profiler.recording = Some(recording);
+        }
+
+        let profiler = &mut store.data_mut().profiler;
+        match kind {
+            CallHook::CallingHost => profiler.host_call_start = Some(Instant::now()),
+            CallHook::ReturningFromHost => {
+                let Some(start) = profiler.host_call_start.take() else {
+                    return Ok(());
+                };
+                let time = start.elapsed();
+                profiler.current.host += time;
+
+                let import = profiler.current_import.take();
+                if let Some(name) = import.filter(|_| profiler.enabled) {
+                    let stats = profiler.imports.entry(name).or_default();
+                    stats.calls += 1;
+                    stats.time += time;
+                }
+            }
+            CallHook::CallingWasm | CallHook::ReturningFromWasm => {}
+        }
+
+        Ok(())
+    }
+
+    /// Installed with `Store::epoch_deadline_callback`. Samples the guest
+    /// stack while a recording is running.
+    pub fn epoch_callback(
+        mut store: StoreContextMut<WasmContexts>,
+    ) -> Result<UpdateDeadline, wasmtime::Error> {
+        if let Some(mut recording) = store.data_mut().profiler.recording.take() {
+            let now = Instant::now();
+            recording
+                .profiler
+                .sample(&store, now - recording.last_sample);
+            recording.last_sample = now;
+            store.data_mut().profiler.recording = Some(recording);
+        }
+
+        Ok(UpdateDeadline::Continue(1))
+    }
+}
+
+impl Drop for ProfilerContext {
+    fn drop(&mut self) {
+        if let Some(recording) = &self.recording {
+            recording.ticker_running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Links host functions as `env` imports which report their name to the
+/// profiler when called.
+pub trait ProfiledLinker {
+    fn func_profiled<Params, Results>(
+        &mut self,
+        name: &'static str,
+        func: impl ProfiledFunc<Params, Results>,
+    ) -> Result<&mut Self, wasmtime::Error>;
+}
+
+impl ProfiledLinker for Linker<WasmContexts> {
+    fn func_profiled<Params, Results>(
+        &mut self,
+        name: &'static str,
+        func: impl ProfiledFunc<Params, Results>,
+    ) -> Result<&mut Self, wasmtime::Error> {
+        func.link(self, name)?;
+        Ok(self)
+    }
+}
+
+pub trait ProfiledFunc<Params, Results> {
+    fn link(
+        self,
+        linker: &mut Linker<WasmContexts>,
+        name: &'static str,
+    ) -> Result<(), wasmtime::Error>;
+}
+
+// Marker types keeping the two blanket impls below from overlapping
+pub struct WithCaller;
+pub struct WithoutCaller;
+
+macro_rules! impl_profiled_func {
+    ($($arg:ident)*) => {
+        #[allow(non_snake_case)]
+        impl<F, R, $($arg,)*> ProfiledFunc<(WithCaller, $($arg,)*), R> for F
+        where
+            F: Fn(Caller<'_, WasmContexts>, $($arg),*) -> R + Send + Sync + 'static,
+            $($arg: WasmTy,)*
+            R: WasmRet,
+        {
+            fn link(
+                self,
+                linker: &mut Linker<WasmContexts>,
+                name: &'static str,
+            ) -> Result<(), wasmtime::Error> {
+                linker.func_wrap(
+                    "env",
+                    name,
+                    move |mut caller: Caller<'_, WasmContexts>, $($arg: $arg),*| -> R {
+                        caller.data_mut().profiler.current_import = Some(name);
+                        self(caller, $($arg),*)
+                    },
+                )?;
+                Ok(())
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<F, R, $($arg,)*> ProfiledFunc<(WithoutCaller, $($arg,)*), R> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            $($arg: WasmTy,)*
+            R: WasmRet,
+        {
+            fn link(
+                self,
+                linker: &mut Linker<WasmContexts>,
+                name: &'static str,
+            ) -> Result<(), wasmtime::Error> {
+                linker.func_wrap(
+                    "env",
+                    name,
+                    move |mut caller: Caller<'_, WasmContexts>, $($arg: $arg),*| -> R {
+                        caller.data_mut().profiler.current_import = Some(name);
+                        self($($arg),*)
+                    },
+                )?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_profiled_func!();
+impl_profiled_func!(A1);
+impl_profiled_func!(A1 A2);
+impl_profiled_func!(A1 A2 A3);
+impl_profiled_func!(A1 A2 A3 A4);
+impl_profiled_func!(A1 A2 A3 A4 A5);
+impl_profiled_func!(A1 A2 A3 A4 A5 A6);
+impl_profiled_func!(A1 A2 A3 A4 A5 A6 A7);
+impl_profiled_func!(A1 A2 A3 A4 A5 A6 A7 A8);
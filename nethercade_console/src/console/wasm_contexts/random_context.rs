@@ -1,7 +1,7 @@
 use fastrand::Rng;
 use wasmtime::{Caller, Linker};
 
-use super::WasmContexts;
+use super::{ProfiledLinker, WasmContexts};
 
 #[derive(Clone)]
 pub struct RandomContext {
@@ -16,18 +16,25 @@ impl RandomContext {
     }
 
     pub fn link(linker: &mut Linker<WasmContexts>) {
-        linker.func_wrap("env", "set_seed", set_seed).unwrap();
+        linker.func_profiled("set_seed", set_seed).unwrap();
         linker
-            .func_wrap("env", "random_int_range", random_int_range)
+            .func_profiled("random_int_range", random_int_range)
             .unwrap();
+        linker.func_profiled("random_float", random_float).unwrap();
         linker
-            .func_wrap("env", "random_float", random_float)
-            .unwrap();
-        linker
-            .func_wrap("env", "random_float_range", random_float_range)
+            .func_profiled("random_float_range", random_float_range)
             .unwrap();
     }
 
+    /// The generator's internal state, saved alongside guest memory.
+    pub fn state(&self) -> u64 {
+        self.shared_rng.get_seed()
+    }
+
+    pub fn restore_state(&mut self, state: u64) {
+        self.shared_rng.seed(state);
+    }
+
     fn set_seed(&mut self, seed: i64) {
         self.shared_rng.seed(seed as u64);
     }
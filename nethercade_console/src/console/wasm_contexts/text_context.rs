@@ -1,14 +1,14 @@
 use wasmtime::{Caller, Linker};
 
-use super::WasmContexts;
+use super::{ProfiledLinker, WasmContexts};
 
 pub struct TextContext;
 
 impl TextContext {
     pub fn link(linker: &mut Linker<WasmContexts>) {
-        linker.func_wrap("env", "console_log", console_log).unwrap();
+        linker.func_profiled("console_log", console_log).unwrap();
         linker
-            .func_wrap("env", "console_log_utf16", console_log_utf16)
+            .func_profiled("console_log_utf16", console_log_utf16)
             .unwrap();
     }
 }
@@ -1,26 +1,30 @@
 use std::{
-    ffi::OsStr,
-    io::Read,
+    path::Path,
     time::{Duration, Instant},
 };
 
-use eframe::egui::{self, Sense, ViewportCommand};
+use eframe::egui::{self, Key, Sense, ViewportCommand};
 use egui::{Color32, Rect, TextureId, Vec2, pos2};
 use ggrs::{P2PSession, SessionState};
 use gilrs::Gilrs;
-use nethercade_core::{ROM_FILE_EXTENSION, Rom};
-
-use crate::{
+use nethercade_console::{
+    Emulator,
     console::{
-        Console, LocalInputManager, LocalPlayerId, MouseEventCollector,
-        gui::PlayModeGui,
+        Console, DEFAULT_RANDOM_SEED, LocalInputManager, LocalPlayerId, MouseEventCollector,
+        SessionDescriptor,
+        gui::{CrashScreen, DevTools, PerformanceHud, PlayModeGui, RomDetails, display_title},
         network_session::{self, GgrsInstance},
     },
     graphics::textures::texture_sampler_descriptor,
+    hot_reload::HotReloadListener,
+    load_rom_file,
+    trust::TrustStore,
 };
+use nethercade_core::Rom;
 
 pub struct ConsoleApp {
     console: Console,
+    emulator: Option<Emulator>,
     input_manager: LocalInputManager,
     gilrs: Gilrs,
     render_texture: TextureId,
@@ -28,12 +32,22 @@ pub struct ConsoleApp {
     accumulator: Duration,
 
     play_mode: PlayModeGui,
+    selected_rom: Option<RomDetails>,
+    crash_screen: Option<CrashScreen>,
+    dev_tools: DevTools,
+    hud: PerformanceHud,
 
     session: Option<P2PSession<GgrsInstance>>,
+    hot_reload: Option<HotReloadListener>,
+    trust: TrustStore,
 }
 
 impl ConsoleApp {
-    pub fn new<'a>(cc: &'a eframe::CreationContext<'a>) -> Option<Self> {
+    pub fn new<'a>(
+        cc: &'a eframe::CreationContext<'a>,
+        hot_reload_port: Option<u16>,
+        trust: TrustStore,
+    ) -> Option<Self> {
         // TODO: Preload whatever stuff needed
         let wgpu_render_state = cc.wgpu_render_state.as_ref()?;
         let queue = wgpu_render_state.queue.clone();
@@ -52,33 +66,152 @@ impl ConsoleApp {
                 texture_sampler_descriptor(),
             );
 
+        let hot_reload = hot_reload_port.and_then(|port| match HotReloadListener::bind(port) {
+            Ok(listener) => {
+                println!("Listening for hot reloads on port {port}.");
+                Some(listener)
+            }
+            Err(e) => {
+                println!("Failed to listen for hot reloads on port {port}: {e}");
+                None
+            }
+        });
+
         Some(Self {
             console,
+            emulator: None,
             input_manager: LocalInputManager::new(),
             gilrs: Gilrs::new().unwrap(),
             render_texture,
             current_time: Instant::now(),
             accumulator: Duration::default(),
             play_mode: PlayModeGui::default(),
+            selected_rom: None,
+            crash_screen: None,
+            dev_tools: DevTools::default(),
+            hud: PerformanceHud::default(),
             session: None,
+            hot_reload,
+            trust,
         })
     }
+
+    /// Starts a session and the game for the current play mode.
+    fn start_game(&mut self, ctx: &egui::Context, rom: Rom, session_descriptor: SessionDescriptor) {
+        let session = network_session::init_session(
+            &rom,
+            session_descriptor.port,
+            &session_descriptor.player_types,
+        );
+
+        let dimensions = rom.resolution.dimensions();
+        let ppp = ctx.pixels_per_point();
+        let resolution = Vec2::new(dimensions.0 as f32 / ppp, dimensions.1 as f32 / ppp);
+        let spacing = &ctx.style().spacing;
+        let new_size = resolution
+            + spacing.window_margin.sum()
+            + spacing.item_spacing
+            + spacing.menu_margin.sum();
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(new_size));
+        ctx.send_viewport_cmd(ViewportCommand::MinInnerSize(new_size));
+        match Emulator::new(
+            rom,
+            Some(self.console.vgpu.clone()),
+            session.num_players(),
+            DEFAULT_RANDOM_SEED,
+        ) {
+            Ok(mut emulator) => {
+                // Cheats would desync remote players
+                let local = session.remote_player_handles().is_empty();
+                let game = emulator.game_mut();
+                game.load_cheats();
+                game.cheats.enabled = local;
+                game.timeline.enabled = local;
+                self.emulator = Some(emulator);
+                self.session = Some(session);
+            }
+            Err(report) => {
+                self.crash_screen = Some(CrashScreen::new(report));
+            }
+        }
+    }
+
+    /// Restarts a local game with a rebuilt ROM, or selects the ROM if
+    /// nothing is running. Networked games aren't interrupted.
+    fn hot_reload(&mut self, ctx: &egui::Context, path: &Path) {
+        if self
+            .session
+            .as_ref()
+            .is_some_and(|session| !session.remote_player_handles().is_empty())
+        {
+            println!("Ignoring hot reload of {path:?} during a networked game.");
+            return;
+        }
+
+        let Some(rom) = load_rom_file(path) else {
+            println!("Failed to hot reload {path:?}");
+            return;
+        };
+        let signer = match self.trust.admit(&rom) {
+            Ok(signer) => signer,
+            Err(e) => {
+                println!("{e}");
+                return;
+            }
+        };
+        println!("Hot reloading {path:?}");
+
+        let restart = self.emulator.is_some() || self.crash_screen.is_some();
+        self.emulator = None;
+        self.session = None;
+        self.crash_screen = None;
+        self.accumulator = Duration::default();
+
+        let session_descriptor = if restart {
+            self.play_mode.generate_session_descriptor(1)
+        } else {
+            None
+        };
+        match session_descriptor {
+            Some(session_descriptor) => self.start_game(ctx, rom, session_descriptor),
+            None => self.selected_rom = Some(RomDetails::new(ctx, rom, signer)),
+        }
+    }
 }
 
 impl eframe::App for ConsoleApp {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
         // TODO: Render a File Menu
 
+        if let Some(path) = self.hot_reload.as_ref().and_then(HotReloadListener::poll) {
+            self.hot_reload(ctx, &path);
+        }
+
+        if ctx.input(|i| i.key_pressed(Key::F12)) {
+            self.dev_tools.open = !self.dev_tools.open;
+        }
+
+        if ctx.input(|i| i.key_pressed(Key::F3)) {
+            self.hud.open = !self.hud.open;
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            match (&mut self.console.game, &mut self.session) {
-                (Some(game), Some(session)) => {
+            if let Some(crash_screen) = &self.crash_screen {
+                if crash_screen.draw(ui) {
+                    self.crash_screen = None;
+                }
+                return;
+            }
+
+            match (&mut self.emulator, &mut self.session) {
+                (Some(emulator), Some(session)) => {
                     // Pre Update Input
 
                     // Handle Keyboard
                     let held_keys = ctx.input(|i| i.keys_down.clone());
                     // Handle Mouse
                     let mouse_events = frame_mouse_input(ctx);
-                    let (width, height) = game.rom.resolution.dimensions();
+                    let (width, height) = emulator.rom().resolution.dimensions();
                     let width = width as f32 / ctx.pixels_per_point();
                     let height = height as f32 / ctx.pixels_per_point();
 
@@ -87,7 +220,12 @@ impl eframe::App for ConsoleApp {
                     let scale_y = (available.y / height).floor();
                     let scale_final = scale_x.min(scale_y);
 
-                    ctx.send_viewport_cmd(ViewportCommand::Title(format!("Scale: {scale_final}x")));
+                    let rom = emulator.rom();
+                    ctx.send_viewport_cmd(ViewportCommand::Title(format!(
+                        "{} {} - Scale: {scale_final}x",
+                        display_title(rom),
+                        rom.metadata.version
+                    )));
 
                     let (rect, response) = ui.allocate_exact_size(
                         egui::Vec2::new(width * scale_final, height * scale_final),
@@ -116,9 +254,13 @@ impl eframe::App for ConsoleApp {
                     let frame_time = new_time.duration_since(self.current_time);
                     self.current_time = new_time;
 
-                    self.accumulator += frame_time;
-                    let dt = std::time::Duration::from_secs_f32(game.rom.frame_rate.frame_time());
+                    if !self.dev_tools.paused {
+                        self.accumulator += frame_time;
+                    }
+                    let dt =
+                        std::time::Duration::from_secs_f32(emulator.rom().frame_rate.frame_time());
 
+                    let mut crash = None;
                     while self.accumulator >= dt {
                         // Update Game
                         self.accumulator -= dt;
@@ -147,13 +289,16 @@ impl eframe::App for ConsoleApp {
                         // Update internal state
                         match session.advance_frame() {
                             Ok(requests) => {
-                                game.handle_requests(requests);
+                                if let Err(report) = emulator.handle_requests(requests) {
+                                    crash = Some(report);
+                                    break;
+                                }
                             }
                             Err(e) => panic!("{}", e),
                         }
 
                         // Push audio after updating
-                        for (index, audio) in game.this_frame_audio.iter().enumerate() {
+                        for (index, audio) in emulator.take_audio().iter().enumerate() {
                             self.console.audio.append_data(
                                 index,
                                 audio.channels,
@@ -162,7 +307,19 @@ impl eframe::App for ConsoleApp {
                             );
                         }
 
-                        game.render();
+                        if let Err(report) = emulator.render() {
+                            crash = Some(report);
+                            break;
+                        }
+                        self.hud.record_frame(emulator.game_mut());
+                    }
+
+                    if let Some(report) = crash {
+                        self.crash_screen = Some(CrashScreen::new(report));
+                        self.emulator = None;
+                        self.session = None;
+                        self.accumulator = Duration::default();
+                        return;
                     }
 
                     ui.painter().image(
@@ -175,45 +332,59 @@ impl eframe::App for ConsoleApp {
                 (None, None) => {
                     self.play_mode.draw(ui);
 
-                    if ui.button("Load Rom").clicked() {
-                        if let Some(rom) = try_load_rom() {
-                            let Some(session_descriptor) =
-                                self.play_mode.generate_session_descriptor(1)
-                            else {
-                                return;
-                            };
-
-                            let session = network_session::init_session(
-                                &rom,
-                                session_descriptor.port,
-                                &session_descriptor.player_types,
-                            );
-
-                            let dimensions = rom.resolution.dimensions();
-                            let ppp = ctx.pixels_per_point();
-                            let resolution =
-                                Vec2::new(dimensions.0 as f32 / ppp, dimensions.1 as f32 / ppp);
-                            let spacing = &ctx.style().spacing;
-                            let new_size = resolution
-                                + spacing.window_margin.sum()
-                                + spacing.item_spacing
-                                + spacing.menu_margin.sum();
-                            ctx.send_viewport_cmd(ViewportCommand::InnerSize(new_size));
-                            ctx.send_viewport_cmd(ViewportCommand::MinInnerSize(new_size));
-                            let game_instance = Console::load_rom(
-                                rom,
-                                self.console.vgpu.clone(),
-                                session.num_players(),
-                            );
-                            self.console.game = Some(game_instance);
-                            self.session = Some(session);
+                    if ui.button("Load Rom").clicked()
+                        && let Some(rom) = try_load_rom()
+                    {
+                        match self.trust.admit(&rom) {
+                            Ok(signer) => {
+                                self.selected_rom = Some(RomDetails::new(ctx, rom, signer));
+                            }
+                            Err(e) => println!("{e}"),
                         }
                     }
+
+                    let Some(details) = &self.selected_rom else {
+                        return;
+                    };
+
+                    ui.separator();
+                    let started = details.draw(
+                        ui,
+                        self.play_mode.num_players(1),
+                        self.play_mode.is_networked(),
+                    );
+                    if !started {
+                        return;
+                    }
+
+                    let Some(session_descriptor) = self.play_mode.generate_session_descriptor(1)
+                    else {
+                        return;
+                    };
+                    let rom = self.selected_rom.take().unwrap().rom;
+                    self.start_game(ctx, rom, session_descriptor);
                 }
                 _ => panic!("Error state!"),
             }
         });
 
+        if let (Some(emulator), Some(session)) = (&mut self.emulator, &self.session) {
+            let game = emulator.game_mut();
+            self.hud.draw(ctx, game);
+
+            if self.dev_tools.open {
+                let can_pause = session.remote_player_handles().is_empty();
+                if let Some(report) = self.dev_tools.draw(ctx, game, can_pause) {
+                    self.crash_screen = Some(CrashScreen::new(report));
+                    self.emulator = None;
+                    self.session = None;
+                    self.accumulator = Duration::default();
+                }
+            } else {
+                game.keep_previous_memory(false);
+            }
+        }
+
         // Render continiously
         ctx.request_repaint();
     }
@@ -225,24 +396,7 @@ fn try_load_rom() -> Option<Rom> {
         .add_filter("nzrom (.nzrom), wasm (.wasm)", &["nzrom", "wasm"])
         .pick_file()?;
 
-    match path.extension().and_then(OsStr::to_str) {
-        Some(ROM_FILE_EXTENSION) => {
-            let file = std::fs::File::open(path).ok()?;
-            let mut bytes = Vec::new();
-            zstd::Decoder::new(file)
-                .ok()?
-                .read_to_end(&mut bytes)
-                .ok()?;
-            Some(bitcode::decode(&bytes).ok()?)
-        }
-        Some("wasm") => {
-            let mut file = std::fs::File::open(path).ok()?;
-            let mut bytes = Vec::new();
-            file.read_to_end(&mut bytes).ok()?;
-            Some(Rom::from_code(&bytes))
-        }
-        _ => panic!("Invalid File"),
-    }
+    load_rom_file(&path)
 }
 
 fn frame_mouse_input(ctx: &eframe::egui::Context) -> MouseEventCollector {
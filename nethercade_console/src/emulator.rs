@@ -0,0 +1,144 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use ggrs::GgrsRequest;
+use nethercade_core::Rom;
+use wasmtime::{Engine, Val};
+
+use crate::{
+    console::{
+        Console, CrashReport, GameInstance, NetworkInputState, PushedAudio, WasmConsoleState,
+        network_session::GgrsInstance,
+    },
+    graphics::VirtualGpu,
+};
+
+/// A single running game, driven by whoever hosts it. Inputs are set per
+/// player and used for every frame stepped until they change.
+pub struct Emulator {
+    game: GameInstance,
+    inputs: Box<[NetworkInputState]>,
+}
+
+impl Emulator {
+    /// Instantiates `rom` and runs its `init`. Without a `vgpu` the game runs
+    /// headless, with rendering disabled.
+    pub fn new(
+        rom: Rom,
+        vgpu: Option<Rc<RefCell<VirtualGpu>>>,
+        num_players: usize,
+        random_seed: u64,
+    ) -> Result<Self, CrashReport> {
+        Console::load_rom(rom, vgpu, num_players, random_seed).map(Self::from_game)
+    }
+
+    /// A headless instance compiled with `engine`, which may be configured
+    /// differently from the console's.
+    pub fn with_engine(
+        rom: Rom,
+        engine: &Engine,
+        num_players: usize,
+        random_seed: u64,
+    ) -> Result<Self, CrashReport> {
+        let instance_pre = Console::link(engine, &rom)?;
+        GameInstance::new(Arc::new(rom), instance_pre, None, num_players, random_seed)
+            .map(Self::from_game)
+    }
+
+    pub(crate) fn from_game(game: GameInstance) -> Self {
+        let num_players = game.num_players();
+        Self {
+            game,
+            inputs: vec![NetworkInputState::default(); num_players].into_boxed_slice(),
+        }
+    }
+
+    /// The underlying instance, for the debugging tools.
+    pub fn game(&self) -> &GameInstance {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut GameInstance {
+        &mut self.game
+    }
+
+    pub fn rom(&self) -> &Rom {
+        &self.game.rom
+    }
+
+    /// The next frame to be simulated.
+    pub fn frame(&self) -> u32 {
+        self.game.frame
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn set_input(&mut self, player: usize, input: NetworkInputState) {
+        self.inputs[player] = input;
+    }
+
+    /// Simulates and renders one frame with the current inputs.
+    pub fn step(&mut self) -> Result<(), CrashReport> {
+        let result = self
+            .game
+            .advance_frame(&self.inputs)
+            .and_then(|()| self.game.render());
+        result.map_err(|e| self.game.crash_report(&e))
+    }
+
+    /// Runs the guest's `render` again without advancing the simulation.
+    pub fn render(&mut self) -> Result<(), CrashReport> {
+        self.game.render().map_err(|e| self.game.crash_report(&e))
+    }
+
+    /// Simulates the frames requested by a ggrs session. Inputs set on the
+    /// emulator are ignored, since the session provides its own.
+    pub fn handle_requests(
+        &mut self,
+        requests: Vec<GgrsRequest<GgrsInstance>>,
+    ) -> Result<(), CrashReport> {
+        self.game
+            .handle_requests(requests)
+            .map_err(|e| self.game.crash_report(&e))
+    }
+
+    /// Audio pushed by the guest during the last simulated frame, indexed
+    /// by channel.
+    pub fn take_audio(&mut self) -> Vec<PushedAudio> {
+        std::mem::take(&mut self.game.this_frame_audio)
+    }
+
+    /// Pixels of the last rendered frame in the frame buffer's format, or
+    /// `None` when running headless.
+    pub fn read_frame_buffer(&self) -> Option<Vec<u8>> {
+        let vgpu = self.game.store.data().draw_3d.vgpu.as_ref()?;
+        let vgpu = vgpu.borrow();
+        Some(vgpu.frame_buffer.read_pixels(&vgpu.device, &vgpu.queue))
+    }
+
+    /// Width and height of the frame buffer, or `None` when running headless.
+    pub fn frame_buffer_size(&self) -> Option<(u32, u32)> {
+        let vgpu = self.game.store.data().draw_3d.vgpu.as_ref()?;
+        let size = vgpu.borrow().frame_buffer.texture.size();
+        Some((size.width, size.height))
+    }
+
+    pub fn save_state(&mut self) -> WasmConsoleState {
+        self.game.generate_save_state()
+    }
+
+    pub fn load_state(&mut self, state: &WasmConsoleState) {
+        self.game.load_save_state(state);
+    }
+
+    /// The guest's linear memory.
+    pub fn memory(&mut self) -> &[u8] {
+        self.game.memory()
+    }
+
+    /// Name and current value of every global the guest exports.
+    pub fn exported_globals(&mut self) -> Vec<(String, Val)> {
+        self.game.exported_globals()
+    }
+}
@@ -3,6 +3,7 @@ use nethercade_core::Resolution;
 use wgpu::TextureViewDescriptor;
 
 pub struct FrameBuffer {
+    pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
 }
 
@@ -20,13 +21,59 @@ impl FrameBuffer {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             label: Some("Frame Buffer Texture"),
             view_formats: &[],
         });
 
         let view = texture.create_view(&TextureViewDescriptor::default());
 
-        Self { view }
+        Self { texture, view }
+    }
+
+    /// Copies the frame buffer back from the GPU, blocking until it's done.
+    /// Rows are tightly packed in the texture's format.
+    pub fn read_pixels(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<u8> {
+        let size = self.texture.size();
+        let pixel_size = self.texture.format().block_copy_size(None).unwrap();
+        let row_size = size.width * pixel_size;
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Buffer Readback"),
+            size: (padded_row_size * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Frame Buffer Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+
+        let mapped = slice.get_mapped_range();
+        mapped
+            .chunks(padded_row_size as usize)
+            .flat_map(|row| &row[..row_size as usize])
+            .copied()
+            .collect()
     }
 }
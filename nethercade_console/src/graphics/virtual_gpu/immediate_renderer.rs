@@ -4,6 +4,9 @@ use super::{mesh, textures};
 
 // TODO: Could do something for immediate Textures
 
+/// Capacity of the vertex buffer used by `draw_tri_list`.
+pub const IMMEDIATE_BUFFER_SIZE: u64 = 1024 * 1024 * 8; // 8mb
+
 pub struct ImmediateRenderer {
     pub vertex_buffer: wgpu::Buffer,
 
@@ -19,7 +22,7 @@ pub struct ImmediateRenderer {
 impl ImmediateRenderer {
     pub fn new(device: &wgpu::Device) -> Self {
         let vertex_buffer = device.create_buffer(&mesh::vertex_buffer_descriptor(
-            1024 * 1024 * 8, // 8mb
+            IMMEDIATE_BUFFER_SIZE,
             Some("Immediate Vertex Buffer"),
         ));
 
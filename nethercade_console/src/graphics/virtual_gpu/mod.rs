@@ -1,5 +1,6 @@
 pub mod frame_buffer;
 mod immediate_renderer;
+pub use immediate_renderer::IMMEDIATE_BUFFER_SIZE;
 
 mod mesh;
 pub mod pipeline;
@@ -26,7 +26,7 @@ impl PreloadedRenderer {
         data: &[f32],
         pipeline: Pipeline,
     ) -> usize {
-        let attribute_count = pipeline.get_attribute_count();
+        let attribute_count = pipeline.floats_per_vertex();
         let total_attributes = data.len();
         let vertex_count = total_attributes / attribute_count;
         let bytes = vertex_count * attribute_count * 4;
@@ -60,7 +60,7 @@ impl PreloadedRenderer {
         indices: &[u16],
         pipeline: Pipeline,
     ) -> usize {
-        let attribute_count = pipeline.get_attribute_count();
+        let attribute_count = pipeline.floats_per_vertex();
         let total_attributes = data.len();
         let vertex_count = total_attributes / attribute_count;
         let bytes = vertex_count * attribute_count * 4;
@@ -5,9 +5,8 @@ use super::{frame_buffer, immediate_renderer, preloaded_renderer, quad_renderer,
 use eframe::wgpu::{self, PushConstantRange};
 
 use crate::MAX_PUSH_CONSTANT_SIZE;
-use crate::graphics::pipeline;
 
-use super::pipeline::Pipeline;
+use super::pipeline::{GpuPipeline, Pipeline};
 use super::textures::DepthTexture;
 use nethercade_core::Resolution;
 
@@ -200,20 +199,8 @@ fn generate_render_pipelines(
     format: wgpu::TextureFormat,
     flipped: bool,
 ) -> [wgpu::RenderPipeline; 8] {
-    use pipeline::Pipeline;
-    const PIPELINES: [Pipeline; 8] = [
-        Pipeline::Color,
-        Pipeline::Uv,
-        Pipeline::ColorUv,
-        Pipeline::Quad2d,
-        Pipeline::Matcap,
-        Pipeline::MatcapColor,
-        Pipeline::MatcapUv,
-        Pipeline::MatcapColorUv,
-    ];
-
     std::array::from_fn(|i| {
-        let pipeline = PIPELINES[i];
+        let pipeline = Pipeline::ALL[i];
 
         create_render_pipeline(device, shader, layout, format, pipeline, flipped)
     })
@@ -224,7 +211,7 @@ fn create_render_pipeline(
     shader: &wgpu::ShaderModule,
     layout: &wgpu::PipelineLayout,
     format: wgpu::TextureFormat,
-    pipeline: pipeline::Pipeline,
+    pipeline: Pipeline,
     flipped: bool,
 ) -> wgpu::RenderPipeline {
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -2,7 +2,7 @@ use bytemuck::bytes_of;
 use eframe::wgpu;
 
 use super::{
-    pipeline::Pipeline,
+    pipeline::{GpuPipeline, Pipeline},
     vgpu::{TEXTURE_BIND_GROUP_INDEX, VERTEX_BUFFER_INDEX, VirtualGpu},
 };
 pub struct VirtualRenderPass {
@@ -30,6 +30,14 @@ pub enum Command {
     DrawSprite(usize),
 }
 
+/// What a render pass submitted, for the performance overlay.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RenderPassStats {
+    pub draw_commands: u32,
+    pub vertices: u64,
+    pub immediate_bytes: u64,
+}
+
 #[derive(Default)]
 struct TextureStates {
     texture_indices: [usize; 4],
@@ -97,6 +105,12 @@ impl TextureStates {
     }
 }
 
+impl Default for VirtualRenderPass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VirtualRenderPass {
     pub fn new() -> Self {
         Self {
@@ -160,6 +174,31 @@ impl VirtualRenderPass {
         self.projection_matrix_count = 0;
     }
 
+    pub fn stats(&self, gpu: &VirtualGpu) -> RenderPassStats {
+        let mut stats = RenderPassStats {
+            immediate_bytes: self.immediate_buffer_last_index,
+            ..Default::default()
+        };
+
+        for command in self.commands.iter() {
+            let vertices = match command {
+                Command::Draw(vertex_count) => *vertex_count,
+                Command::DrawStaticMesh(index) => {
+                    gpu.preloaded_renderer.meshes[*index].vertex_count
+                }
+                Command::DrawStaticMeshIndexed(index) => {
+                    gpu.preloaded_renderer.indexed_meshes[*index].index_count
+                }
+                Command::DrawSprite(_) => 6,
+                _ => continue,
+            };
+            stats.draw_commands += 1;
+            stats.vertices += vertices as u64;
+        }
+
+        stats
+    }
+
     pub fn execute(&self, rp: &mut wgpu::RenderPass, gpu: &VirtualGpu) {
         if self.instance_count == 0 {
             println!("Matrices are invalid, please set model, view, and projection.");
@@ -0,0 +1,238 @@
+use std::ops::Range;
+
+use crate::{
+    Emulator,
+    console::{CrashReport, NetworkInputState, WasmConsoleState},
+};
+
+/// Bytes per pixel of the frame buffer and of downscaled observations.
+const PIXEL_SIZE: usize = 4;
+
+/// What the agent sees after each step.
+#[derive(Debug, Clone)]
+pub enum ObservationSource {
+    /// The rendered frame, box filtered down to `width` x `height` pixels in
+    /// the frame buffer's channel order. Needs an emulator with a GPU.
+    FrameBuffer { width: u32, height: u32 },
+    /// The bytes at `observation_ptr()` with length `observation_len()`,
+    /// both exported by the guest.
+    GuestBuffer,
+    /// Copies of these ranges of guest memory, one after another.
+    MemoryRegions(Vec<Range<usize>>),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepInfo {
+    /// From the guest's `reward() -> f32` export, or 0 without one.
+    pub reward: f32,
+    /// From the guest's `done() -> i32` export, or false without one.
+    pub done: bool,
+    pub frame: u32,
+}
+
+/// Gym style environment around an emulator. Resets restore the state right
+/// after `init` and reseed the random number generator, so rollouts with
+/// the same seed and actions are reproducible.
+pub struct GymEnv {
+    emulator: Emulator,
+    source: ObservationSource,
+    initial_state: WasmConsoleState,
+}
+
+impl GymEnv {
+    /// `emulator` should have only run `init`, its state becomes the start
+    /// of every episode. Fails if frame buffer observations are asked of an
+    /// emulator without a GPU.
+    pub fn new(mut emulator: Emulator, source: ObservationSource) -> Result<Self, String> {
+        if let ObservationSource::FrameBuffer { .. } = source
+            && emulator.frame_buffer_size().is_none()
+        {
+            return Err(String::from(
+                "Frame buffer observations need an emulator with a GPU",
+            ));
+        }
+
+        let initial_state = emulator.save_state();
+        Ok(Self {
+            emulator,
+            source,
+            initial_state,
+        })
+    }
+
+    pub fn emulator(&mut self) -> &mut Emulator {
+        &mut self.emulator
+    }
+
+    /// Starts a new episode, returning the first observation.
+    pub fn reset(&mut self, seed: u64) -> Result<Vec<u8>, CrashReport> {
+        // Memory can't shrink, so a guest which grew it needs a new instance
+        if self.emulator.memory().len() != self.initial_state.memory.len() {
+            let game = self.emulator.game_mut();
+            game.reset(seed).map_err(|e| game.crash_report(&e))?;
+        }
+
+        self.emulator.load_state(&self.initial_state);
+        let game = self.emulator.game_mut();
+        game.store.data_mut().random.restore_state(seed);
+        game.timeline.clear();
+
+        for player in 0..self.emulator.num_players() {
+            self.emulator
+                .set_input(player, NetworkInputState::default());
+        }
+
+        self.emulator.render()?;
+        self.observe()
+    }
+
+    /// Simulates one frame with an action for each player.
+    pub fn step(
+        &mut self,
+        actions: &[NetworkInputState],
+    ) -> Result<(Vec<u8>, StepInfo), CrashReport> {
+        for (player, action) in actions.iter().enumerate() {
+            self.emulator.set_input(player, *action);
+        }
+        self.emulator.step()?;
+
+        let observation = self.observe()?;
+        let info = self.info()?;
+        Ok((observation, info))
+    }
+
+    pub fn observe(&mut self) -> Result<Vec<u8>, CrashReport> {
+        match &self.source {
+            ObservationSource::FrameBuffer { width, height } => {
+                let (source_width, source_height) = self.emulator.frame_buffer_size().unwrap();
+                let pixels = self.emulator.read_frame_buffer().unwrap();
+                Ok(downscale(
+                    &pixels,
+                    (source_width, source_height),
+                    (*width, *height),
+                ))
+            }
+            ObservationSource::GuestBuffer => {
+                let ptr = self.call_optional::<i32>("observation_ptr")?.unwrap_or(0);
+                let len = self.call_optional::<i32>("observation_len")?.unwrap_or(0);
+                // Out of bounds buffers, negative ones included, observe nothing
+                Ok(self
+                    .emulator
+                    .memory()
+                    .get(ptr as u32 as usize..)
+                    .and_then(|arr| arr.get(..len as u32 as usize))
+                    .map(<[u8]>::to_vec)
+                    .unwrap_or_default())
+            }
+            ObservationSource::MemoryRegions(regions) => {
+                let memory = self.emulator.memory();
+                Ok(regions
+                    .iter()
+                    .flat_map(|region| memory.get(region.clone()).unwrap_or_default())
+                    .copied()
+                    .collect())
+            }
+        }
+    }
+
+    fn info(&mut self) -> Result<StepInfo, CrashReport> {
+        Ok(StepInfo {
+            reward: self.call_optional::<f32>("reward")?.unwrap_or_default(),
+            done: self
+                .call_optional::<i32>("done")?
+                .is_some_and(|done| done != 0),
+            frame: self.emulator.frame(),
+        })
+    }
+
+    fn call_optional<R: wasmtime::WasmResults>(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<R>, CrashReport> {
+        let game = self.emulator.game_mut();
+        game.call_optional::<R>(name)
+            .transpose()
+            .map_err(|e| game.crash_report(&e))
+    }
+}
+
+/// Averages each block of source pixels into one destination pixel.
+fn downscale(pixels: &[u8], source: (u32, u32), target: (u32, u32)) -> Vec<u8> {
+    let (source_width, source_height) = (source.0 as usize, source.1 as usize);
+    let (width, height) = (target.0.max(1) as usize, target.1.max(1) as usize);
+    let mut out = vec![0; width * height * PIXEL_SIZE];
+
+    // Every destination pixel covers at least one source pixel
+    let block = |index: usize, source: usize, target: usize| {
+        let start = index * source / target;
+        start..((index + 1) * source / target).max(start + 1)
+    };
+
+    for y in 0..height {
+        let rows = block(y, source_height, height);
+        for x in 0..width {
+            let columns = block(x, source_width, width);
+
+            let mut sum = [0u32; PIXEL_SIZE];
+            let mut count = 0;
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let index = (row * source_width + column) * PIXEL_SIZE;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += pixels[index + channel] as u32;
+                    }
+                    count += 1;
+                }
+            }
+
+            let index = (y * width + x) * PIXEL_SIZE;
+            for (channel, total) in sum.iter().enumerate() {
+                out[index + channel] = (total / count) as u8;
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use nethercade_core::Rom;
+
+    use super::*;
+
+    #[test]
+    fn invalid_guest_buffers_observe_nothing() {
+        for (ptr, len) in [(-1, 4), (0, -1), (65534, 4), (i32::MAX, i32::MAX)] {
+            let guest = format!(
+                r#"(module
+                  (memory (export "memory") 1)
+                  (func (export "update"))
+                  (func (export "render"))
+                  (func (export "observation_ptr") (result i32) (i32.const {ptr}))
+                  (func (export "observation_len") (result i32) (i32.const {len})))"#
+            );
+            let rom = Rom::from_code(&wat::parse_str(guest).unwrap());
+            let emulator = Emulator::new(rom, None, 1, 0).unwrap();
+            let mut env = GymEnv::new(emulator, ObservationSource::GuestBuffer).unwrap();
+
+            assert_eq!(env.reset(0).unwrap(), Vec::<u8>::new(), "{ptr}, {len}");
+        }
+    }
+
+    #[test]
+    fn frame_buffer_observations_need_a_gpu() {
+        let guest = r#"(module
+          (memory (export "memory") 1)
+          (func (export "update"))
+          (func (export "render")))"#;
+        let rom = Rom::from_code(&wat::parse_str(guest).unwrap());
+        let emulator = Emulator::new(rom, None, 1, 0).unwrap();
+        let source = ObservationSource::FrameBuffer {
+            width: 8,
+            height: 8,
+        };
+
+        assert!(GymEnv::new(emulator, source).is_err());
+    }
+}
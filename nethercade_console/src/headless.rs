@@ -0,0 +1,236 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use nethercade_console::{
+    Emulator,
+    console::{
+        CrashReport, DEFAULT_RANDOM_SEED,
+        movie::{Movie, MovieMode, MovieSession},
+    },
+    load_rom_file,
+    trust::TrustStore,
+    verify::{self, EngineVariant},
+};
+use nethercade_core::Rom;
+
+/// Memory ranges listed when reporting a desync.
+const MAX_REPORTED_RANGES: usize = 32;
+
+/// Runs a ROM without a window, following the inputs of a movie, then
+/// prints a hash of guest memory so runs can be compared. Fails if the ROM
+/// or movie can't be loaded or the game crashes.
+pub fn run(
+    rom_path: PathBuf,
+    movie_path: Option<PathBuf>,
+    frames: Option<u32>,
+    trust: &TrustStore,
+) -> ExitCode {
+    let Some(rom) = load_rom(&rom_path, trust) else {
+        return ExitCode::FAILURE;
+    };
+
+    let movie = match movie_path.map(|path| Movie::load(&path)).transpose() {
+        Ok(movie) => movie,
+        Err(e) => {
+            println!("Failed to load movie: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match replay(rom, movie, frames) {
+        Ok((frame, hash)) => {
+            println!("Ran {frame} frames, memory hash {}", hash.to_hex());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Plays `movie` for `frames`, or its whole length, returning the frame
+/// reached and a hash of guest memory there.
+fn replay(
+    rom: Rom,
+    movie: Option<Movie>,
+    frames: Option<u32>,
+) -> Result<(u32, blake3::Hash), String> {
+    let num_players = movie.as_ref().map_or(1, |movie| movie.num_players);
+    let random_seed = movie
+        .as_ref()
+        .map_or(DEFAULT_RANDOM_SEED, |movie| movie.random_seed);
+
+    if let Some(movie) = &movie {
+        movie
+            .check(&rom, num_players)
+            .map_err(|e| format!("Movie doesn't match rom: {e}"))?;
+    }
+
+    let frames = frames
+        .or(movie.as_ref().map(Movie::len))
+        .ok_or("Either a movie or a frame count is required")?;
+
+    let mut emulator = Emulator::new(rom, None, num_players, random_seed)
+        .map_err(|report| format!("Game crashed during init: {}", report.message))?;
+    emulator.game_mut().movie = movie.map(|movie| MovieSession {
+        movie,
+        mode: MovieMode::Playing,
+    });
+
+    // Frames past the end of the movie are run with no input
+    for _ in 0..frames {
+        emulator.step().map_err(|report| crash_text(&report))?;
+    }
+
+    Ok((emulator.frame(), blake3::hash(emulator.memory())))
+}
+
+/// Plays a movie on two instances in lockstep, optionally with different
+/// engine settings, and reports the first frame where their states differ.
+/// Fails on a divergence, a crash or anything that can't be loaded.
+pub fn verify(
+    rom_path: PathBuf,
+    movie_path: PathBuf,
+    variants: [EngineVariant; 2],
+    trust: &TrustStore,
+) -> ExitCode {
+    let Some(rom) = load_rom(&rom_path, trust) else {
+        return ExitCode::FAILURE;
+    };
+
+    let movie = match Movie::load(&movie_path)
+        .and_then(|movie| movie.check(&rom, movie.num_players).map(|()| movie))
+    {
+        Ok(movie) => movie,
+        Err(e) => {
+            println!("Failed to load movie: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "Verifying {} frames with {:?} and {:?}",
+        movie.len(),
+        variants[0],
+        variants[1]
+    );
+
+    let divergence = match verify::verify_movie(&rom, &movie, variants) {
+        Ok(None) => {
+            println!("No divergence, both runs matched on every frame");
+            return ExitCode::SUCCESS;
+        }
+        Ok(Some(divergence)) => divergence,
+        Err(report) => {
+            println!("{}", crash_text(&report));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Diverged while simulating frame {}", divergence.frame);
+    if let Some((a, b)) = divergence.rng_states {
+        println!("  Random state: {a:#018x} vs {b:#018x}");
+    }
+    if divergence.previous_buttons {
+        println!("  Previous buttons differ");
+    }
+
+    let ranges = &divergence.memory_ranges;
+    println!("  {} differing memory ranges", ranges.len());
+    for range in ranges.iter().take(MAX_REPORTED_RANGES) {
+        println!(
+            "    {:#010x}..{:#010x} ({} bytes)",
+            range.start,
+            range.end,
+            range.len()
+        );
+    }
+    if ranges.len() > MAX_REPORTED_RANGES {
+        println!("    ... and {} more", ranges.len() - MAX_REPORTED_RANGES);
+    }
+    ExitCode::FAILURE
+}
+
+/// Loads a ROM if the signature policy lets it run.
+fn load_rom(path: &Path, trust: &TrustStore) -> Option<Rom> {
+    let Some(rom) = load_rom_file(path) else {
+        println!("Failed to load rom {}", path.display());
+        return None;
+    };
+
+    match trust.admit(&rom) {
+        Ok(_) => Some(rom),
+        Err(e) => {
+            println!("{e}");
+            None
+        }
+    }
+}
+
+fn crash_text(report: &CrashReport) -> String {
+    let mut text = format!("Game crashed on frame {}: {}", report.frame, report.message);
+    if let Some(location) = &report.location {
+        text += &format!("\n  at {location}");
+    }
+    if let Some(backtrace) = &report.backtrace {
+        text += &format!("\n{backtrace}");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use nethercade_console::console::{ButtonCode, NetworkInputState};
+
+    use super::*;
+
+    /// Adds 1 to the counter at address 0 each frame, or 3 while A is held.
+    const COUNTER: &str = r#"(module
+      (import "env" "button_a_held" (func $held (param i32) (result i32)))
+      (memory (export "memory") 1)
+      (func (export "init"))
+      (func (export "update")
+        (i32.store (i32.const 0)
+          (i32.add
+            (i32.load (i32.const 0))
+            (select (i32.const 3) (i32.const 1) (call $held (i32.const 0))))))
+      (func (export "render")))"#;
+
+    #[test]
+    fn replays_recorded_movies() {
+        let rom = Rom::from_code(&wat::parse_str(COUNTER).unwrap());
+        let mut emulator = Emulator::new(rom.clone(), None, 1, DEFAULT_RANDOM_SEED).unwrap();
+        emulator.game_mut().movie = Some(MovieSession {
+            movie: Movie::new(&rom, 1, DEFAULT_RANDOM_SEED),
+            mode: MovieMode::Recording,
+        });
+
+        for frame in 0..10 {
+            let mut input = NetworkInputState::default();
+            input
+                .input_state
+                .buttons
+                .set_button_state(ButtonCode::ButtonA, frame % 3 == 0);
+            emulator.set_input(0, input);
+            emulator.step().unwrap();
+        }
+        let recorded = blake3::hash(emulator.memory());
+        let movie = emulator.game_mut().movie.take().unwrap().movie;
+
+        assert_eq!(
+            replay(rom.clone(), Some(movie.clone()), None),
+            Ok((10, recorded))
+        );
+        // Without the inputs the counter ends up elsewhere
+        assert_ne!(replay(rom.clone(), None, Some(10)).unwrap().1, recorded);
+
+        let other = Rom::from_code(
+            &wat::parse_str(COUNTER.replace("(i32.const 3)", "(i32.const 2)")).unwrap(),
+        );
+        let error = replay(other, Some(movie), None).unwrap_err();
+        assert!(error.starts_with("Movie doesn't match rom"), "{error}");
+    }
+}
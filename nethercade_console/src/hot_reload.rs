@@ -0,0 +1,49 @@
+//! Lets `bundle --watch --notify` hand a running console the ROMs it
+//! rebuilds.
+
+use std::{
+    io::{self, Read},
+    net::{Ipv4Addr, TcpListener},
+    path::PathBuf,
+    time::Duration,
+};
+
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Accepts connections each sending the path of a ROM to load, followed by
+/// a newline.
+pub struct HotReloadListener {
+    listener: TcpListener,
+}
+
+impl HotReloadListener {
+    /// Only listens on localhost, so other machines can't make the console
+    /// load files.
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener })
+    }
+
+    /// The latest path sent since the last poll, without waiting for new
+    /// connections.
+    pub fn poll(&self) -> Option<PathBuf> {
+        let mut latest = None;
+        while let Ok((mut stream, _)) = self.listener.accept() {
+            let mut message = String::new();
+            let read = stream
+                .set_nonblocking(false)
+                .and_then(|()| stream.set_read_timeout(Some(READ_TIMEOUT)))
+                .and_then(|()| stream.read_to_string(&mut message));
+
+            match read {
+                Ok(_) if !message.trim().is_empty() => {
+                    latest = Some(PathBuf::from(message.trim()));
+                }
+                Ok(_) => (),
+                Err(e) => println!("Failed to read hot reload request: {e}"),
+            }
+        }
+        latest
+    }
+}
@@ -0,0 +1,86 @@
+//! Hosts Nethercade games. [`Emulator`] runs a single game for tools and
+//! tests, the console app is built on top of it. [`BatchSimulator`] runs
+//! many headless instances of one game side by side, and [`GymEnv`]
+//! wraps an emulator for reinforcement learning.
+
+use std::{ffi::OsStr, io::Read, path::Path};
+
+use nethercade_core::{
+    DEBUG_INFO_FILE_EXTENSION, DebugInfo, ROM_FILE_EXTENSION, Rom, console_spec::MAX_ASSETS_SIZE,
+};
+
+pub mod audio;
+pub mod console;
+pub mod graphics;
+
+mod emulator;
+pub use emulator::Emulator;
+
+mod batch;
+pub use batch::BatchSimulator;
+
+mod gym;
+pub use gym::{GymEnv, ObservationSource, StepInfo};
+
+pub mod hot_reload;
+pub mod trust;
+pub mod verify;
+
+pub const MAX_PUSH_CONSTANT_SIZE: u32 = 128;
+
+/// Reads either a bundled `.nzrom` or a bare `.wasm` file, along with any
+/// `.nzdbg` debug info written next to it. ROMs with more assets than the
+/// console's budget are refused.
+pub fn load_rom_file(path: &Path) -> Option<Rom> {
+    let mut rom = read_rom_file(path)?;
+    if rom.debug_info.is_none() {
+        rom.debug_info = load_debug_info_file(path, &rom);
+    }
+    Some(rom)
+}
+
+fn read_rom_file(path: &Path) -> Option<Rom> {
+    match path.extension().and_then(OsStr::to_str) {
+        Some(ROM_FILE_EXTENSION) => {
+            let bytes = std::fs::read(path).ok()?;
+            let rom = match Rom::decode(&bytes) {
+                Ok(rom) => rom,
+                Err(e) => {
+                    println!("{e}");
+                    return None;
+                }
+            };
+            let assets_size = rom.assets_size();
+            if assets_size > MAX_ASSETS_SIZE {
+                println!(
+                    "ROM assets take {assets_size} bytes, over the {MAX_ASSETS_SIZE} byte budget"
+                );
+                return None;
+            }
+            Some(rom)
+        }
+        Some("wasm") => {
+            let mut file = std::fs::File::open(path).ok()?;
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).ok()?;
+            Some(Rom::from_code(&bytes))
+        }
+        _ => None,
+    }
+}
+
+/// Debug info from a sidecar file, if it was split from this build's code.
+fn load_debug_info_file(path: &Path, rom: &Rom) -> Option<DebugInfo> {
+    let bytes = std::fs::read(path.with_extension(DEBUG_INFO_FILE_EXTENSION)).ok()?;
+    match DebugInfo::decode(&bytes) {
+        Ok(debug_info) if debug_info.code_hash == rom.hash() => Some(debug_info),
+        Ok(_) => {
+            println!("Ignoring debug info written for a different build.");
+            None
+        }
+        Err(e) => {
+            println!("Failed to load debug info: {e}");
+            None
+        }
+    }
+}
@@ -1,19 +1,101 @@
 mod console_app;
 use console_app::*;
 
-mod audio;
-mod console;
-mod graphics;
+mod headless;
 
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
 use eframe::egui;
 use eframe::wgpu;
-use nethercade_core::Resolution;
+use nethercade_console::{
+    MAX_PUSH_CONSTANT_SIZE,
+    trust::{SignaturePolicy, TrustStore},
+    verify::EngineVariant,
+};
+use nethercade_core::{HOT_RELOAD_PORT, Resolution};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Run a rom without a window, printing a hash of its memory at the end
+    #[arg(long, requires = "rom")]
+    headless: bool,
+
+    /// Play a movie twice in lockstep and report the first frame where the
+    /// runs diverge
+    #[arg(long, requires_all = ["rom", "movie"], conflicts_with = "headless")]
+    verify: bool,
+
+    /// Rom to run headless (.nzrom or .wasm)
+    #[arg(long)]
+    rom: Option<PathBuf>,
+
+    /// Input movie to play back while headless
+    #[arg(long)]
+    movie: Option<PathBuf>,
+
+    /// Frames to run while headless, defaults to the length of the movie
+    #[arg(long)]
+    frames: Option<u32>,
 
-pub const MAX_PUSH_CONSTANT_SIZE: u32 = 128;
+    /// Engine settings of the first verification run
+    #[arg(long, value_enum, default_value_t = EngineVariant::Default)]
+    engine_a: EngineVariant,
+
+    /// Engine settings of the second verification run
+    #[arg(long, value_enum, default_value_t = EngineVariant::Default)]
+    engine_b: EngineVariant,
+
+    /// Reload the game whenever `bundle --watch --notify` rebuilds it,
+    /// listening on this port or the default one
+    #[arg(long, value_name = "PORT")]
+    hot_reload: Option<Option<u16>>,
+
+    /// JSON list of `{ "name", "publicKey" }` whose signatures are trusted
+    #[arg(long, value_name = "FILE")]
+    trusted_keys: Option<PathBuf>,
+
+    /// What to do with ROMs not signed by a trusted key
+    #[arg(long, value_enum, default_value_t = SignaturePolicy::AllowUnsigned)]
+    signature_policy: SignaturePolicy,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let trust = match &args.trusted_keys {
+        Some(path) => match TrustStore::load(path, args.signature_policy) {
+            Ok(trust) => trust,
+            Err(e) => {
+                println!("{e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => TrustStore::new(args.signature_policy),
+    };
+
+    if args.headless {
+        return headless::run(
+            args.rom.expect("clap requires --rom"),
+            args.movie,
+            args.frames,
+            &trust,
+        );
+    }
+
+    if args.verify {
+        return headless::verify(
+            args.rom.expect("clap requires --rom"),
+            args.movie.expect("clap requires --movie"),
+            [args.engine_a, args.engine_b],
+            &trust,
+        );
+    }
 
-fn main() {
     println!("Launching Console...");
 
+    let hot_reload_port = args.hot_reload.map(|port| port.unwrap_or(HOT_RELOAD_PORT));
+
     let dimensions = Resolution::Compact.dimensions();
 
     let device_descriptor = std::sync::Arc::new(|_: &wgpu::Adapter| {
@@ -48,11 +130,28 @@ fn main() {
         "Nethercade Z",
         options,
         Box::new(|cc| {
-            let app = ConsoleApp::new(cc).unwrap();
+            let app = ConsoleApp::new(cc, hot_reload_port, trust).unwrap();
             Ok(Box::new(app))
         }),
     )
     .unwrap();
 
     println!("Console closing.");
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_modes_require_their_files() {
+        let parse = |args: &[&str]| Args::try_parse_from([&["console"], args].concat());
+
+        assert!(parse(&["--headless"]).is_err());
+        assert!(parse(&["--headless", "--rom", "game.nzrom"]).is_ok());
+        assert!(parse(&["--verify", "--rom", "game.nzrom"]).is_err());
+        assert!(parse(&["--verify", "--movie", "game.nzmovie"]).is_err());
+        assert!(parse(&["--verify", "--rom", "game.nzrom", "--movie", "game.nzmovie"]).is_ok());
+    }
 }
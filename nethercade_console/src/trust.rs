@@ -0,0 +1,185 @@
+//! Decides which ROMs a console runs, from who signed them. Cabinets set a
+//! list of trusted keys and require every ROM to be signed by one of them.
+
+use std::path::Path;
+
+use nethercade_core::{Rom, key_from_hex, key_to_hex};
+use serde::Deserialize;
+
+/// What happens to ROMs not signed by a trusted key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SignaturePolicy {
+    /// Run them without comment.
+    #[default]
+    AllowUnsigned,
+    /// Run them, but point out who signed them, if anyone.
+    Warn,
+    /// Refuse to run them.
+    Require,
+}
+
+/// An entry in the trusted keys file, a JSON list of these.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrustedKeyConfig {
+    name: String,
+    /// Hex encoded, as written by `bundle keygen`.
+    public_key: String,
+}
+
+/// Who signed a ROM, as far as the console can tell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signer {
+    Unsigned,
+    /// Signed by a trusted key, named as in the trusted keys file.
+    Trusted(String),
+    /// Signed by a key the console doesn't know, under the name the ROM gives.
+    Untrusted {
+        signer: String,
+        public_key: String,
+    },
+}
+
+impl Signer {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Unsigned => String::from("Unsigned"),
+            Self::Trusted(name) => format!("Signed by {name}"),
+            Self::Untrusted { signer, public_key } => {
+                format!("Signed by {signer:?} with untrusted key {public_key}")
+            }
+        }
+    }
+
+    pub fn is_trusted(&self) -> bool {
+        matches!(self, Self::Trusted(_))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TrustStore {
+    pub policy: SignaturePolicy,
+    keys: Vec<(String, [u8; 32])>,
+}
+
+impl TrustStore {
+    pub fn new(policy: SignaturePolicy) -> Self {
+        Self {
+            policy,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Reads trusted keys from a JSON list of `{ "name", "publicKey" }`.
+    pub fn load(path: &Path, policy: SignaturePolicy) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Trusted keys {path:?}: {e}"))?;
+        let configs = sonic_rs::from_slice::<Vec<TrustedKeyConfig>>(&bytes)
+            .map_err(|e| format!("Trusted keys {path:?}: {e}"))?;
+
+        let keys = configs
+            .into_iter()
+            .map(|config| {
+                key_from_hex(&config.public_key)
+                    .map(|key| (config.name.clone(), key))
+                    .map_err(|e| format!("Trusted key {:?}: {e}", config.name))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { policy, keys })
+    }
+
+    pub fn signer(&self, rom: &Rom) -> Signer {
+        let Some(signature) = &rom.signature else {
+            return Signer::Unsigned;
+        };
+
+        match self
+            .keys
+            .iter()
+            .find(|(_, key)| *key == signature.public_key)
+        {
+            Some((name, _)) => Signer::Trusted(name.clone()),
+            None => Signer::Untrusted {
+                signer: signature.signer.clone(),
+                public_key: key_to_hex(&signature.public_key),
+            },
+        }
+    }
+
+    /// Applies the policy, returning who signed the ROM if it may run.
+    pub fn admit(&self, rom: &Rom) -> Result<Signer, String> {
+        let signer = self.signer(rom);
+        if signer.is_trusted() {
+            return Ok(signer);
+        }
+
+        match self.policy {
+            SignaturePolicy::AllowUnsigned => Ok(signer),
+            SignaturePolicy::Warn => {
+                println!(
+                    "Warning: running a ROM without a trusted signature. {}",
+                    signer.label()
+                );
+                Ok(signer)
+            }
+            SignaturePolicy::Require => Err(format!(
+                "Refusing to run a ROM without a trusted signature. {}",
+                signer.label()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+
+    fn rom_signed_by(key: Option<&SigningKey>) -> Rom {
+        let mut container = Rom::from_code(&[]).to_container();
+        if let Some(key) = key {
+            container.sign(key, "Studio");
+        }
+        Rom::from_container(&container).unwrap()
+    }
+
+    #[test]
+    fn admits_by_policy() {
+        let trusted = SigningKey::from_bytes(&[1; 32]);
+        let untrusted = SigningKey::from_bytes(&[2; 32]);
+        let roms = [
+            rom_signed_by(Some(&trusted)),
+            rom_signed_by(Some(&untrusted)),
+            rom_signed_by(None),
+        ];
+
+        for policy in [
+            SignaturePolicy::AllowUnsigned,
+            SignaturePolicy::Warn,
+            SignaturePolicy::Require,
+        ] {
+            let store = TrustStore {
+                policy,
+                keys: vec![(String::from("Arcade"), trusted.verifying_key().to_bytes())],
+            };
+
+            let [trusted_rom, untrusted_rom, unsigned_rom] = &roms;
+            assert_eq!(
+                store.admit(trusted_rom),
+                Ok(Signer::Trusted(String::from("Arcade")))
+            );
+
+            let untrusted_signer = Signer::Untrusted {
+                signer: String::from("Studio"),
+                public_key: key_to_hex(&untrusted.verifying_key().to_bytes()),
+            };
+            if policy == SignaturePolicy::Require {
+                assert!(store.admit(untrusted_rom).is_err());
+                assert!(store.admit(unsigned_rom).is_err());
+            } else {
+                assert_eq!(store.admit(untrusted_rom), Ok(untrusted_signer));
+                assert_eq!(store.admit(unsigned_rom), Ok(Signer::Unsigned));
+            }
+        }
+    }
+}
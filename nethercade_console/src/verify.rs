@@ -0,0 +1,160 @@
+use std::ops::Range;
+
+use nethercade_core::Rom;
+use wasmtime::{Config, Engine, InstanceAllocationStrategy, OptLevel, PoolingAllocationConfig};
+
+use crate::{
+    Emulator,
+    console::{
+        Console, CrashReport, WasmConsoleState,
+        movie::{Movie, MovieMode, MovieSession},
+    },
+};
+
+/// Differences closer together than this are reported as one range.
+const RANGE_MERGE_DISTANCE: usize = 16;
+
+/// Engine settings a replay can be checked across.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EngineVariant {
+    /// The configuration the console runs games with.
+    Default,
+    /// Instances allocated from a pool, as the batch simulator does.
+    Pooling,
+    /// Cranelift optimizations turned off.
+    Unoptimized,
+    /// NaNs canonicalized, which some hosts need to agree on float results.
+    NanCanonicalization,
+}
+
+impl EngineVariant {
+    pub fn config(self) -> Config {
+        let mut config = Console::engine_config(false);
+        match self {
+            Self::Default => {}
+            Self::Pooling => {
+                config.allocation_strategy(InstanceAllocationStrategy::Pooling(
+                    PoolingAllocationConfig::default(),
+                ));
+            }
+            Self::Unoptimized => {
+                config.cranelift_opt_level(OptLevel::None);
+            }
+            Self::NanCanonicalization => {
+                config.cranelift_nan_canonicalization(true);
+            }
+        }
+        config
+    }
+}
+
+/// The first frame where the two runs disagreed.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// The frame whose simulation produced different states.
+    pub frame: u32,
+    /// Byte ranges of guest memory which differ.
+    pub memory_ranges: Vec<Range<usize>>,
+    /// Random number generator states, if they differ.
+    pub rng_states: Option<(u64, u64)>,
+    /// Whether the buttons held on the previous frame differ.
+    pub previous_buttons: bool,
+}
+
+/// Plays `movie` on two headless instances in lockstep, comparing hashes of
+/// their memory and host state after every frame. Returns the first
+/// divergence, or `None` if the runs matched until the end of the movie.
+pub fn verify_movie(
+    rom: &Rom,
+    movie: &Movie,
+    variants: [EngineVariant; 2],
+) -> Result<Option<Divergence>, CrashReport> {
+    let [a, b] = variants.map(|variant| {
+        let engine = Engine::new(&variant.config()).unwrap();
+        Emulator::with_engine(rom.clone(), &engine, movie.num_players, movie.random_seed)
+    });
+    let (mut a, mut b) = (a?, b?);
+
+    for emulator in [&mut a, &mut b] {
+        emulator.game_mut().movie = Some(MovieSession {
+            movie: movie.clone(),
+            mode: MovieMode::Playing,
+        });
+    }
+
+    for frame in 0..movie.len() {
+        a.step()?;
+        b.step()?;
+
+        let (state_a, state_b) = (a.save_state(), b.save_state());
+        if state_hash(&state_a) != state_hash(&state_b) {
+            return Ok(Some(Divergence {
+                frame,
+                memory_ranges: diff_ranges(&state_a.memory, &state_b.memory),
+                rng_states: (state_a.rng_state != state_b.rng_state)
+                    .then_some((state_a.rng_state, state_b.rng_state)),
+                previous_buttons: state_a.previous_buttons != state_b.previous_buttons,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn state_hash(state: &WasmConsoleState) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&state.memory);
+    hasher.update(&state.frame.to_le_bytes());
+    hasher.update(&state.rng_state.to_le_bytes());
+    hasher.update(bytemuck::cast_slice(&state.previous_buttons));
+    hasher.finalize()
+}
+
+fn diff_ranges(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let differing = a
+        .iter()
+        .zip(b)
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(index, _)| index);
+
+    for index in differing {
+        match ranges.last_mut() {
+            Some(last) if index - last.end < RANGE_MERGE_DISTANCE => last.end = index + 1,
+            _ => ranges.push(index..index + 1),
+        }
+    }
+
+    // One memory grew more than the other
+    if a.len() != b.len() {
+        ranges.push(a.len().min(b.len())..a.len().max(b.len()));
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_memory_into_ranges() {
+        let a = vec![0; 64];
+        assert_eq!(diff_ranges(&a, &a), []);
+
+        let mut b = a.clone();
+        // Adjacent bytes, and one close enough to merge with them
+        b[2] = 1;
+        b[3] = 1;
+        b[10] = 1;
+        // Far enough from the rest to stay apart
+        b[40] = 1;
+        assert_eq!(diff_ranges(&a, &b), [2..11, 40..41]);
+
+        // Grown memory is reported after the differing bytes
+        b.extend([0; 16]);
+        assert_eq!(diff_ranges(&a, &b), [2..11, 40..41, 64..80]);
+        assert_eq!(diff_ranges(&b, &a), [2..11, 40..41, 64..80]);
+    }
+}
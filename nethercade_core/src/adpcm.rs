@@ -0,0 +1,134 @@
+//! IMA ADPCM, storing 16 bit samples in 4 bits each. Channels are coded
+//! independently and interleaved per sample, two nibbles to a byte with the
+//! first sample in the low nibble.
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+#[derive(Default, Clone, Copy)]
+struct ChannelState {
+    predictor: i32,
+    index: i32,
+}
+
+impl ChannelState {
+    fn encode(&mut self, sample: i16) -> u8 {
+        let step = STEP_TABLE[self.index as usize];
+        let mut diff = sample as i32 - self.predictor;
+        let mut nibble = 0;
+        if diff < 0 {
+            nibble = 8;
+            diff = -diff;
+        }
+
+        let mut threshold = step;
+        for bit in [4, 2, 1] {
+            if diff >= threshold {
+                nibble |= bit;
+                diff -= threshold;
+            }
+            threshold >>= 1;
+        }
+
+        // Track the decoder's state so errors don't accumulate
+        self.decode(nibble);
+        nibble
+    }
+
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let step = STEP_TABLE[self.index as usize];
+        let mut diff = step >> 3;
+        if nibble & 4 != 0 {
+            diff += step;
+        }
+        if nibble & 2 != 0 {
+            diff += step >> 1;
+        }
+        if nibble & 1 != 0 {
+            diff += step >> 2;
+        }
+        if nibble & 8 != 0 {
+            diff = -diff;
+        }
+
+        self.predictor = (self.predictor + diff).clamp(i16::MIN as i32, i16::MAX as i32);
+        self.index = (self.index + INDEX_TABLE[nibble as usize]).clamp(0, 88);
+        self.predictor as i16
+    }
+}
+
+/// Compresses interleaved samples in `[-1, 1]`.
+pub fn encode(samples: &[f32], channels: u16) -> Vec<u8> {
+    let channels = channels.max(1);
+    let mut states = vec![ChannelState::default(); channels as usize];
+    let mut bytes = Vec::with_capacity(samples.len().div_ceil(2));
+
+    for (pair_index, pair) in samples.chunks(2).enumerate() {
+        let mut byte = 0;
+        for (offset, sample) in pair.iter().enumerate() {
+            let channel = (pair_index * 2 + offset) % channels as usize;
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            byte |= states[channel].encode(sample) << (offset * 4);
+        }
+        bytes.push(byte);
+    }
+
+    bytes
+}
+
+/// Expands `sample_count` interleaved samples back to `[-1, 1]`, or fewer
+/// if `bytes` runs out.
+pub fn decode(bytes: &[u8], channels: u16, sample_count: usize) -> Vec<f32> {
+    let channels = channels.max(1);
+    let mut states = vec![ChannelState::default(); channels as usize];
+
+    (0..sample_count.min(bytes.len() * 2))
+        .map(|index| {
+            let nibble = (bytes[index / 2] >> ((index % 2) * 4)) & 0xF;
+            let channel = index % channels as usize;
+            states[channel].decode(nibble) as f32 / i16::MAX as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_within_tolerance() {
+        let channels = 2;
+        let samples = (0..4000)
+            .flat_map(|frame| {
+                let t = frame as f32 / 44100.0;
+                let wave = (t * 440.0 * std::f32::consts::TAU).sin() * 0.5;
+                [wave, -wave]
+            })
+            .collect::<Vec<_>>();
+
+        let bytes = encode(&samples, channels);
+        assert_eq!(bytes.len(), samples.len() / 2);
+
+        let decoded = decode(&bytes, channels, samples.len());
+        assert_eq!(decoded.len(), samples.len());
+        // The step size needs a few samples to adapt to the signal
+        for (index, (a, b)) in samples.iter().zip(&decoded).enumerate().skip(64) {
+            assert!((a - b).abs() < 0.02, "sample {index}: {a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn decodes_only_what_the_bytes_hold() {
+        let bytes = encode(&[0.1, 0.2, 0.3], 1);
+        assert_eq!(bytes.len(), 2);
+        assert_eq!(decode(&bytes, 1, 3).len(), 3);
+        assert_eq!(decode(&bytes, 1, 10).len(), 4);
+    }
+}
@@ -0,0 +1,87 @@
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use super::adpcm;
+
+/// Sample rate every sound asset is stored at.
+pub const SOUND_SAMPLE_RATE: u32 = 44_100;
+
+/// A named blob carried in a ROM's assets section. The console decodes and
+/// uploads these directly, so they never need to live in guest memory.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct Asset {
+    pub name: String,
+    pub data: AssetData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub enum AssetData {
+    /// RGB8 pixels, or RGBA8 when `has_alpha` is set.
+    Texture {
+        width: u32,
+        height: u32,
+        has_alpha: bool,
+        pixels: Vec<u8>,
+    },
+    /// Interleaved vertex attributes laid out for `pipeline`, with indices
+    /// when the mesh is drawn indexed.
+    Mesh {
+        pipeline: i32,
+        vertices: Vec<f32>,
+        indices: Option<Vec<u16>>,
+    },
+    /// Interleaved samples for `push_audio`. Loop points are in frames,
+    /// with the end exclusive.
+    Sound {
+        channels: u16,
+        sample_rate: u32,
+        samples: SoundSamples,
+        loop_points: Option<(u32, u32)>,
+    },
+    /// Anything else, read by the guest as is.
+    Raw(Vec<u8>),
+}
+
+impl AssetData {
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Texture { .. } => "texture",
+            Self::Mesh { .. } => "mesh",
+            Self::Sound { .. } => "sound",
+            Self::Raw(_) => "raw",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub enum SoundSamples {
+    Pcm(Vec<f32>),
+    /// IMA ADPCM, decoded by the console when the sound is used.
+    Adpcm {
+        sample_count: u32,
+        data: Vec<u8>,
+    },
+}
+
+impl SoundSamples {
+    /// Number of interleaved samples once decoded.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Pcm(samples) => samples.len(),
+            Self::Adpcm { sample_count, .. } => *sample_count as usize,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn decode(&self, channels: u16) -> Vec<f32> {
+        match self {
+            Self::Pcm(samples) => samples.clone(),
+            Self::Adpcm { sample_count, data } => {
+                adpcm::decode(data, channels, *sample_count as usize)
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! What the console offers a game, so tools can check a game against it
+//! without running it.
+
+use std::fmt;
+
+/// Module every import is expected to come from.
+pub const IMPORT_MODULE: &str = "env";
+
+/// Exports called by the console, all taking and returning nothing.
+pub const REQUIRED_EXPORTS: [&str; 3] = ["init", "update", "render"];
+
+/// Largest guest memory the console will allocate.
+pub const MAX_MEMORY_SIZE: usize = 256 * 1024 * 1024;
+
+/// Most bytes of encoded assets a ROM may carry.
+pub const MAX_ASSETS_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl fmt::Display for WasmType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImportSpec {
+    pub name: &'static str,
+    pub params: &'static [WasmType],
+    pub results: &'static [WasmType],
+}
+
+impl ImportSpec {
+    pub fn find(name: &str) -> Option<&'static Self> {
+        IMPORTS.iter().find(|import| import.name == name)
+    }
+
+    /// Like `(i32, f32) -> i32`.
+    pub fn signature(&self) -> String {
+        signature(self.params, self.results)
+    }
+}
+
+/// Formats a function type, like `(i32, f32) -> i32`.
+pub fn signature<T: fmt::Display>(params: &[T], results: &[T]) -> String {
+    let list = |types: &[T]| {
+        types
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match results {
+        [] => format!("({})", list(params)),
+        [result] => format!("({}) -> {result}", list(params)),
+        _ => format!("({}) -> ({})", list(params), list(results)),
+    }
+}
+
+use WasmType::{F32, I32, I64};
+
+const fn import(
+    name: &'static str,
+    params: &'static [WasmType],
+    results: &'static [WasmType],
+) -> ImportSpec {
+    ImportSpec {
+        name,
+        params,
+        results,
+    }
+}
+
+/// Every function the console links into a game.
+pub const IMPORTS: &[ImportSpec] = &[
+    // Data
+    import("width", &[], &[I32]),
+    import("height", &[], &[I32]),
+    import("fps", &[], &[I32]),
+    import("frame_time", &[], &[F32]),
+    // Drawing
+    import("draw_tri_list", &[I32, I32, I32], &[]),
+    import("draw_tri_list_indexed", &[I32, I32, I32, I32, I32], &[]),
+    import("push_model_matrix", &[I32], &[]),
+    import("push_view_matrix_pos", &[I32, I32], &[]),
+    import("push_proj_matrix", &[I32], &[]),
+    import("draw_static_mesh", &[I32], &[]),
+    import("draw_static_mesh_indexed", &[I32], &[]),
+    import("draw_sprite", &[I32], &[]),
+    import("set_texture", &[I32, I32, I32], &[]),
+    import("set_matcap", &[I32, I32, I32], &[]),
+    import("clear_textures", &[], &[]),
+    import("set_winding_order", &[I32], &[]),
+    import("load_texture", &[I32, I32, I32, I32], &[I32]),
+    import("load_static_mesh", &[I32, I32, I32], &[I32]),
+    import(
+        "load_static_mesh_indexed",
+        &[I32, I32, I32, I32, I32],
+        &[I32],
+    ),
+    // Input
+    import("button_a_pressed", &[I32], &[I32]),
+    import("button_a_released", &[I32], &[I32]),
+    import("button_a_held", &[I32], &[I32]),
+    import("button_b_pressed", &[I32], &[I32]),
+    import("button_b_released", &[I32], &[I32]),
+    import("button_b_held", &[I32], &[I32]),
+    import("button_c_pressed", &[I32], &[I32]),
+    import("button_c_released", &[I32], &[I32]),
+    import("button_c_held", &[I32], &[I32]),
+    import("button_d_pressed", &[I32], &[I32]),
+    import("button_d_released", &[I32], &[I32]),
+    import("button_d_held", &[I32], &[I32]),
+    import("button_up_pressed", &[I32], &[I32]),
+    import("button_up_released", &[I32], &[I32]),
+    import("button_up_held", &[I32], &[I32]),
+    import("button_down_pressed", &[I32], &[I32]),
+    import("button_down_released", &[I32], &[I32]),
+    import("button_down_held", &[I32], &[I32]),
+    import("button_left_pressed", &[I32], &[I32]),
+    import("button_left_released", &[I32], &[I32]),
+    import("button_left_held", &[I32], &[I32]),
+    import("button_right_pressed", &[I32], &[I32]),
+    import("button_right_released", &[I32], &[I32]),
+    import("button_right_held", &[I32], &[I32]),
+    import("button_start_pressed", &[I32], &[I32]),
+    import("button_start_released", &[I32], &[I32]),
+    import("button_start_held", &[I32], &[I32]),
+    import("button_select_pressed", &[I32], &[I32]),
+    import("button_select_released", &[I32], &[I32]),
+    import("button_select_held", &[I32], &[I32]),
+    import("button_left_shoulder_pressed", &[I32], &[I32]),
+    import("button_left_shoulder_released", &[I32], &[I32]),
+    import("button_left_shoulder_held", &[I32], &[I32]),
+    import("button_right_shoulder_pressed", &[I32], &[I32]),
+    import("button_right_shoulder_released", &[I32], &[I32]),
+    import("button_right_shoulder_held", &[I32], &[I32]),
+    import("button_left_stick_pressed", &[I32], &[I32]),
+    import("button_left_stick_released", &[I32], &[I32]),
+    import("button_left_stick_held", &[I32], &[I32]),
+    import("button_right_stick_pressed", &[I32], &[I32]),
+    import("button_right_stick_released", &[I32], &[I32]),
+    import("button_right_stick_held", &[I32], &[I32]),
+    import("button_left_trigger_pressed", &[I32], &[I32]),
+    import("button_left_trigger_released", &[I32], &[I32]),
+    import("button_left_trigger_held", &[I32], &[I32]),
+    import("button_right_trigger_pressed", &[I32], &[I32]),
+    import("button_right_trigger_released", &[I32], &[I32]),
+    import("button_right_trigger_held", &[I32], &[I32]),
+    import("analog_left_x", &[I32], &[F32]),
+    import("analog_left_y", &[I32], &[F32]),
+    import("analog_right_x", &[I32], &[F32]),
+    import("analog_right_y", &[I32], &[F32]),
+    import("trigger_left", &[I32], &[F32]),
+    import("trigger_right", &[I32], &[F32]),
+    import("mouse_left_pressed", &[I32], &[I32]),
+    import("mouse_left_released", &[I32], &[I32]),
+    import("mouse_left_held", &[I32], &[I32]),
+    import("mouse_right_pressed", &[I32], &[I32]),
+    import("mouse_right_released", &[I32], &[I32]),
+    import("mouse_right_held", &[I32], &[I32]),
+    import("mouse_middle_pressed", &[I32], &[I32]),
+    import("mouse_middle_released", &[I32], &[I32]),
+    import("mouse_middle_held", &[I32], &[I32]),
+    import("mouse_x_pos", &[I32], &[I32]),
+    import("mouse_x_delta", &[I32], &[I32]),
+    import("mouse_y_pos", &[I32], &[I32]),
+    import("mouse_y_delta", &[I32], &[I32]),
+    import("mouse_wheel_up", &[I32], &[I32]),
+    import("mouse_wheel_down", &[I32], &[I32]),
+    import("mouse_wheel_left", &[I32], &[I32]),
+    import("mouse_wheel_right", &[I32], &[I32]),
+    import("raw_mouse_state", &[I32], &[I64]),
+    import("raw_input_state", &[I32], &[I64]),
+    import("lock_mouse", &[I32], &[]),
+    // Audio
+    import("push_audio", &[I32, I32, I32, I32], &[]),
+    // Assets
+    import("asset_id", &[I32, I32], &[I32]),
+    import("asset_len", &[I32], &[I32]),
+    import("read_asset", &[I32, I32, I32], &[I32]),
+    import("load_texture_asset", &[I32], &[I32]),
+    import("load_mesh_asset", &[I32], &[I32]),
+    import("push_sound_asset", &[I32], &[]),
+    import("sound_loop_start", &[I32], &[I32]),
+    import("sound_loop_end", &[I32], &[I32]),
+    // Random
+    import("set_seed", &[I64], &[]),
+    import("random_int_range", &[I32, I32], &[I32]),
+    import("random_float", &[], &[F32]),
+    import("random_float_range", &[F32, F32], &[F32]),
+    // Text
+    import("console_log", &[I32, I32], &[]),
+    import("console_log_utf16", &[I32, I32], &[]),
+    // Panics
+    import("abort", &[I32, I32, I32, I32], &[]),
+    import("panic", &[I32, I32, I32, I32, I32, I32], &[]),
+    // Math
+    import("sin", &[F32], &[F32]),
+    import("cos", &[F32], &[F32]),
+    import("tan", &[F32], &[F32]),
+    import("atan2", &[F32, F32], &[F32]),
+    import("sqrt", &[F32], &[F32]),
+    import("exp", &[F32], &[F32]),
+    import("log", &[F32], &[F32]),
+    import("pow", &[F32, F32], &[F32]),
+    import("perspective", &[F32, F32, F32, F32, I32], &[]),
+    import("look_at", &[I32, I32, I32, I32], &[]),
+    import("mat4_mul", &[I32, I32, I32], &[]),
+];
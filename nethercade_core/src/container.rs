@@ -0,0 +1,477 @@
+use std::{fmt, io::Read};
+
+use crate::console_spec::MAX_ASSETS_SIZE;
+
+/// First bytes of every ROM container. The line endings and EOF byte catch
+/// files mangled by text mode transfers.
+pub const ROM_MAGIC: [u8; 8] = *b"NZROM\r\n\x1a";
+/// Version written by this build.
+pub const ROM_FORMAT_VERSION: u16 = 2;
+
+const HEADER_SIZE: usize = 14;
+const ENTRY_SIZE: usize = 56;
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// Set on sections whose data is zstd compressed.
+const FLAG_COMPRESSED: u32 = 1;
+
+/// Most bytes the code, metadata and debug info sections may decompress to.
+pub const MAX_SECTION_SIZE: usize = 64 * 1024 * 1024;
+/// Most bytes the small fixed sections may decompress to.
+const MAX_SMALL_SECTION_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionKind {
+    /// Settings the console needs before running the game.
+    Header,
+    /// The wasm module.
+    Code,
+    Metadata,
+    Assets,
+    DebugInfo,
+    /// An ed25519 signature over the other sections.
+    Signature,
+    /// Written by a newer bundler, skipped when loading.
+    Unknown(u32),
+}
+
+impl SectionKind {
+    pub fn id(self) -> u32 {
+        match self {
+            Self::Header => 0,
+            Self::Code => 1,
+            Self::Metadata => 2,
+            Self::Assets => 3,
+            Self::DebugInfo => 4,
+            Self::Signature => 5,
+            Self::Unknown(id) => id,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Self {
+        match id {
+            0 => Self::Header,
+            1 => Self::Code,
+            2 => Self::Metadata,
+            3 => Self::Assets,
+            4 => Self::DebugInfo,
+            5 => Self::Signature,
+            id => Self::Unknown(id),
+        }
+    }
+
+    /// Most bytes a section of this kind may decompress to, checked while
+    /// decompressing so a crafted file can't expand to gigabytes first.
+    pub fn max_size(self) -> usize {
+        match self {
+            Self::Header | Self::Signature => MAX_SMALL_SECTION_SIZE,
+            Self::Assets => MAX_ASSETS_SIZE,
+            Self::Code | Self::Metadata | Self::DebugInfo | Self::Unknown(_) => MAX_SECTION_SIZE,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RomError {
+    /// Written by a newer bundler in a way this build can't read.
+    UnsupportedVersion {
+        version: u16,
+        min_reader_version: u16,
+    },
+    /// The file ends before the data its header describes.
+    Truncated,
+    ChecksumMismatch(SectionKind),
+    MissingSection(SectionKind),
+    /// A section's contents couldn't be decoded.
+    InvalidSection {
+        kind: SectionKind,
+        reason: String,
+    },
+    /// The signature doesn't match the contents, which were changed after
+    /// signing.
+    InvalidSignature,
+    /// Not a container, and not a ROM from before containers either.
+    InvalidLegacyRom(String),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion {
+                version,
+                min_reader_version,
+            } => write!(
+                f,
+                "ROM format version {version} needs a console supporting version {min_reader_version}, this one supports {ROM_FORMAT_VERSION}"
+            ),
+            Self::Truncated => write!(f, "ROM file is truncated"),
+            Self::ChecksumMismatch(kind) => {
+                write!(f, "{kind:?} section is corrupted, checksum mismatch")
+            }
+            Self::MissingSection(kind) => write!(f, "ROM has no {kind:?} section"),
+            Self::InvalidSection { kind, reason } => {
+                write!(f, "{kind:?} section is invalid: {reason}")
+            }
+            Self::InvalidSignature => {
+                write!(
+                    f,
+                    "ROM signature doesn't match, it was changed after signing"
+                )
+            }
+            Self::InvalidLegacyRom(reason) => write!(f, "Not a valid ROM file: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RomError {}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub kind: SectionKind,
+    /// Uncompressed contents.
+    pub data: Vec<u8>,
+    /// Whether the data is zstd compressed in the file.
+    pub compressed: bool,
+}
+
+/// Where a section is stored, as listed in a container's section table.
+#[derive(Debug, Clone)]
+pub struct SectionEntry {
+    pub kind: SectionKind,
+    pub compressed: bool,
+    pub offset: u64,
+    /// Length in the file, after compression.
+    pub stored_length: u64,
+    /// blake3 hash of the stored bytes.
+    pub checksum: blake3::Hash,
+}
+
+/// A container's header, read without touching its sections.
+#[derive(Debug, Clone)]
+pub struct ContainerTable {
+    pub version: u16,
+    pub min_reader_version: u16,
+    pub entries: Vec<SectionEntry>,
+}
+
+/// A ROM file: a fixed header followed by a table of typed sections, each
+/// with a blake3 checksum.
+///
+/// | Offset | Size | Field                          |
+/// |--------|------|--------------------------------|
+/// | 0      | 8    | [`ROM_MAGIC`]                  |
+/// | 8      | 2    | format version                  |
+/// | 10     | 2    | minimum version able to read it |
+/// | 12     | 2    | section count                   |
+/// | 14     | 56n  | section table                   |
+///
+/// Each table entry holds the section kind and flags as `u32`s, the offset
+/// and stored length as `u64`s and the checksum of the stored bytes. All
+/// integers are little endian.
+#[derive(Debug, Clone, Default)]
+pub struct RomContainer {
+    pub sections: Vec<Section>,
+}
+
+impl RomContainer {
+    pub fn is_container(bytes: &[u8]) -> bool {
+        bytes.starts_with(&ROM_MAGIC)
+    }
+
+    pub fn section(&self, kind: SectionKind) -> Option<&[u8]> {
+        self.sections
+            .iter()
+            .find(|section| section.kind == kind)
+            .map(|section| section.data.as_slice())
+    }
+
+    /// Adds a section, replacing any existing one of the same kind.
+    pub fn set_section(&mut self, kind: SectionKind, data: Vec<u8>, compressed: bool) {
+        self.sections.retain(|section| section.kind != kind);
+        self.sections.push(Section {
+            kind,
+            data,
+            compressed,
+        });
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let stored = self
+            .sections
+            .iter()
+            .map(|section| {
+                if section.compressed {
+                    zstd::bulk::compress(&section.data, COMPRESSION_LEVEL).unwrap()
+                } else {
+                    section.data.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&ROM_MAGIC);
+        bytes.extend_from_slice(&ROM_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&ROM_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.sections.len() as u16).to_le_bytes());
+
+        let mut offset = (HEADER_SIZE + ENTRY_SIZE * self.sections.len()) as u64;
+        for (section, data) in self.sections.iter().zip(&stored) {
+            let flags = if section.compressed {
+                FLAG_COMPRESSED
+            } else {
+                0
+            };
+            bytes.extend_from_slice(&section.kind.id().to_le_bytes());
+            bytes.extend_from_slice(&flags.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(blake3::hash(data).as_bytes());
+            offset += data.len() as u64;
+        }
+
+        for data in stored {
+            bytes.extend_from_slice(&data);
+        }
+
+        bytes
+    }
+
+    /// Reads the header and section table, whatever version wrote them.
+    pub fn read_table(bytes: &[u8]) -> Result<ContainerTable, RomError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(ROM_MAGIC.len())? != ROM_MAGIC {
+            return Err(RomError::InvalidLegacyRom(String::from("bad magic")));
+        }
+
+        let version = reader.u16()?;
+        let min_reader_version = reader.u16()?;
+        let count = reader.u16()?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let kind = SectionKind::from_id(reader.u32()?);
+            let flags = reader.u32()?;
+            let offset = reader.u64()?;
+            let stored_length = reader.u64()?;
+            let checksum = reader.take(blake3::OUT_LEN)?;
+
+            entries.push(SectionEntry {
+                kind,
+                compressed: flags & FLAG_COMPRESSED != 0,
+                offset,
+                stored_length,
+                checksum: blake3::Hash::from_bytes(checksum.try_into().unwrap()),
+            });
+        }
+
+        Ok(ContainerTable {
+            version,
+            min_reader_version,
+            entries,
+        })
+    }
+
+    /// Reads a container, verifying the checksum of every section.
+    pub fn decode(bytes: &[u8]) -> Result<Self, RomError> {
+        let table = Self::read_table(bytes)?;
+        if table.min_reader_version > ROM_FORMAT_VERSION {
+            return Err(RomError::UnsupportedVersion {
+                version: table.version,
+                min_reader_version: table.min_reader_version,
+            });
+        }
+
+        let mut sections = Vec::with_capacity(table.entries.len());
+        for entry in table.entries {
+            let kind = entry.kind;
+            let stored = usize::try_from(entry.offset)
+                .ok()
+                .zip(usize::try_from(entry.stored_length).ok())
+                .and_then(|(offset, length)| bytes.get(offset..offset.checked_add(length)?))
+                .ok_or(RomError::Truncated)?;
+            if blake3::hash(stored) != entry.checksum {
+                return Err(RomError::ChecksumMismatch(kind));
+            }
+
+            let data = if entry.compressed {
+                decompress(stored, kind.max_size())
+                    .map_err(|reason| RomError::InvalidSection { kind, reason })?
+            } else {
+                stored.to_vec()
+            };
+
+            sections.push(Section {
+                kind,
+                data,
+                compressed: entry.compressed,
+            });
+        }
+
+        Ok(Self { sections })
+    }
+}
+
+/// Decompresses zstd `data`, failing once the output passes `limit` bytes.
+pub(crate) fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let decoder = zstd::stream::read::Decoder::new(data).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    decoder
+        .take(limit as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() > limit {
+        return Err(format!("decompresses to more than {limit} bytes"));
+    }
+    Ok(bytes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], RomError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or(RomError::Truncated)?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, RomError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, RomError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, RomError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcode::Encode;
+
+    use super::*;
+    use crate::{FrameRate, Resolution, Rom};
+
+    fn test_container() -> RomContainer {
+        let mut container = RomContainer::default();
+        container.set_section(SectionKind::Header, vec![1, 2, 3], false);
+        container.set_section(SectionKind::Code, vec![0x42; 1000], true);
+        container
+    }
+
+    #[test]
+    fn round_trips() {
+        let bytes = test_container().encode();
+        assert!(RomContainer::is_container(&bytes));
+
+        let decoded = RomContainer::decode(&bytes).unwrap();
+        assert_eq!(decoded.sections.len(), 2);
+        assert_eq!(decoded.section(SectionKind::Header), Some(&[1, 2, 3][..]));
+        assert_eq!(decoded.section(SectionKind::Code), Some(&[0x42; 1000][..]));
+        assert!(decoded.sections[1].compressed);
+    }
+
+    #[test]
+    fn skips_unknown_sections() {
+        let rom = Rom::from_code(b"\0asm\x01\0\0\0");
+        let mut container = rom.to_container();
+        container.set_section(SectionKind::Unknown(99), vec![7; 16], true);
+
+        let bytes = container.encode();
+        let table = RomContainer::read_table(&bytes).unwrap();
+        assert!(
+            table
+                .entries
+                .iter()
+                .any(|entry| entry.kind == SectionKind::Unknown(99))
+        );
+
+        let decoded = Rom::decode(&bytes).unwrap();
+        assert_eq!(decoded.code, rom.code);
+    }
+
+    #[test]
+    fn rejects_newer_min_reader_version() {
+        let mut bytes = test_container().encode();
+        bytes[10..12].copy_from_slice(&(ROM_FORMAT_VERSION + 1).to_le_bytes());
+
+        assert!(matches!(
+            RomContainer::decode(&bytes),
+            Err(RomError::UnsupportedVersion { min_reader_version, .. })
+                if min_reader_version == ROM_FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let bytes = test_container().encode();
+        // Cut inside the section table, then inside the last section
+        for length in [HEADER_SIZE + 10, bytes.len() - 1] {
+            assert!(matches!(
+                RomContainer::decode(&bytes[..length]),
+                Err(RomError::Truncated)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_flipped_bytes() {
+        let mut bytes = test_container().encode();
+        let code = &RomContainer::read_table(&bytes).unwrap().entries[1];
+        bytes[code.offset as usize] ^= 1;
+
+        assert!(matches!(
+            RomContainer::decode(&bytes),
+            Err(RomError::ChecksumMismatch(SectionKind::Code))
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_sections() {
+        let mut container = RomContainer::default();
+        let limit = SectionKind::Header.max_size();
+        container.set_section(SectionKind::Header, vec![0; limit], true);
+        assert!(RomContainer::decode(&container.encode()).is_ok());
+
+        container.set_section(SectionKind::Header, vec![0; limit + 1], true);
+        assert!(matches!(
+            RomContainer::decode(&container.encode()),
+            Err(RomError::InvalidSection {
+                kind: SectionKind::Header,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn decodes_legacy_roms() {
+        /// Matches the layout of ROMs from before containers.
+        #[derive(Encode)]
+        struct LegacyRom {
+            code: Box<[u8]>,
+            resolution: Resolution,
+            frame_rate: FrameRate,
+        }
+
+        let legacy = LegacyRom {
+            code: Box::new([1, 2, 3, 4]),
+            resolution: Resolution::Retro,
+            frame_rate: FrameRate::Cinematic,
+        };
+        let bytes = zstd::bulk::compress(&bitcode::encode(&legacy), 3).unwrap();
+        assert!(!RomContainer::is_container(&bytes));
+
+        let rom = Rom::decode(&bytes).unwrap();
+        assert_eq!(&*rom.code, &[1, 2, 3, 4]);
+        assert_eq!(rom.resolution, Resolution::Retro);
+        assert_eq!(rom.frame_rate, FrameRate::Cinematic);
+        assert!(rom.assets.is_empty() && rom.signature.is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use super::{RomContainer, RomError, SectionKind};
+
+/// Extension of debug info files written next to a ROM.
+pub const DEBUG_INFO_FILE_EXTENSION: &str = "nzdbg";
+
+/// Custom sections moved out of a ROM's code by the bundler, like the name
+/// section and DWARF. They don't change how the game runs, only how its
+/// backtraces read.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct DebugInfo {
+    /// [`Rom::hash`](super::Rom::hash) of the code these were taken from.
+    pub code_hash: String,
+    pub sections: Vec<CustomSection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct CustomSection {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl DebugInfo {
+    /// The contents of a `.nzdbg` file, a container holding only a debug
+    /// info section.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut container = RomContainer::default();
+        container.set_section(SectionKind::DebugInfo, bitcode::encode(self), true);
+        container.encode()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, RomError> {
+        let container = RomContainer::decode(bytes)?;
+        let section = container
+            .section(SectionKind::DebugInfo)
+            .ok_or(RomError::MissingSection(SectionKind::DebugInfo))?;
+        bitcode::decode(section).map_err(|e| RomError::InvalidSection {
+            kind: SectionKind::DebugInfo,
+            reason: e.to_string(),
+        })
+    }
+
+    /// `code` with the sections appended, which is valid anywhere after the
+    /// module header.
+    pub fn attach(&self, code: &[u8]) -> Vec<u8> {
+        let mut attached = code.to_vec();
+        for section in &self.sections {
+            let mut contents = Vec::with_capacity(section.name.len() + section.data.len() + 5);
+            write_leb128(&mut contents, section.name.len() as u32);
+            contents.extend_from_slice(section.name.as_bytes());
+            contents.extend_from_slice(&section.data);
+
+            attached.push(0);
+            write_leb128(&mut attached, contents.len() as u32);
+            attached.extend_from_slice(&contents);
+        }
+        attached
+    }
+}
+
+fn write_leb128(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
@@ -7,4 +7,30 @@ pub use rom::*;
 mod frame_rate;
 pub use frame_rate::*;
 
+mod metadata;
+pub use metadata::*;
+
+pub mod adpcm;
+
+mod asset;
+pub use asset::*;
+
+mod container;
+pub use container::*;
+
+mod debug_info;
+pub use debug_info::*;
+
+mod signature;
+pub use signature::*;
+
+mod pipeline;
+pub use pipeline::*;
+
+pub mod console_spec;
+
 pub const ROM_FILE_EXTENSION: &str = "nzrom";
+
+/// Localhost port a console listens on for the bundler to ask it to reload
+/// a rebuilt ROM.
+pub const HOT_RELOAD_PORT: u16 = 7878;
@@ -0,0 +1,334 @@
+use std::{fmt, str::FromStr};
+
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Most players a session can have.
+pub const MAX_PLAYERS: u8 = 4;
+
+pub const MAX_TITLE_LENGTH: usize = 64;
+pub const MAX_AUTHOR_LENGTH: usize = 64;
+pub const MAX_DESCRIPTION_LENGTH: usize = 1024;
+pub const MAX_GENRES: usize = 8;
+pub const MAX_GENRE_LENGTH: usize = 24;
+/// Icons are square, with sides up to this many pixels.
+pub const MAX_ICON_SIZE: u32 = 128;
+pub const MAX_COVER_SIZE: (u32, u32) = (640, 360);
+
+/// Information shown to players when choosing a game. None of it affects
+/// how the game runs.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct RomMetadata {
+    pub title: String,
+    pub author: String,
+    pub version: RomVersion,
+    pub description: String,
+    pub min_players: u8,
+    pub max_players: u8,
+    /// Whether the game stays in sync when played over the network.
+    pub netplay: bool,
+    pub genres: Vec<String>,
+    pub icon: Option<RomImage>,
+    pub cover: Option<RomImage>,
+}
+
+impl Default for RomMetadata {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            author: String::new(),
+            version: RomVersion::default(),
+            description: String::new(),
+            min_players: 1,
+            max_players: 1,
+            netplay: false,
+            genres: Vec::new(),
+            icon: None,
+            cover: None,
+        }
+    }
+}
+
+impl RomMetadata {
+    pub fn supports_players(&self, num_players: usize) -> bool {
+        (self.min_players as usize..=self.max_players as usize).contains(&num_players)
+    }
+
+    /// Checks the metadata against the console's limits, returning every
+    /// problem found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.title.trim().is_empty() {
+            errors.push(String::from("Title is empty"));
+        }
+        check_length(&mut errors, "Title", &self.title, MAX_TITLE_LENGTH);
+        check_length(&mut errors, "Author", &self.author, MAX_AUTHOR_LENGTH);
+        check_length(
+            &mut errors,
+            "Description",
+            &self.description,
+            MAX_DESCRIPTION_LENGTH,
+        );
+
+        if self.min_players == 0 {
+            errors.push(String::from("Minimum players must be at least 1"));
+        }
+        if self.min_players > self.max_players {
+            errors.push(format!(
+                "Minimum players ({}) is more than maximum players ({})",
+                self.min_players, self.max_players
+            ));
+        }
+        if self.max_players > MAX_PLAYERS {
+            errors.push(format!(
+                "Maximum players ({}) is more than the console supports ({MAX_PLAYERS})",
+                self.max_players
+            ));
+        }
+
+        if self.genres.len() > MAX_GENRES {
+            errors.push(format!(
+                "{} genres given, at most {MAX_GENRES} are allowed",
+                self.genres.len()
+            ));
+        }
+        for genre in &self.genres {
+            if genre.trim().is_empty() {
+                errors.push(String::from("Genres can't be empty"));
+            }
+            check_length(&mut errors, "Genre", genre, MAX_GENRE_LENGTH);
+        }
+
+        if let Some(icon) = &self.icon {
+            if icon.width != icon.height {
+                errors.push(format!(
+                    "Icon must be square, got {}x{}",
+                    icon.width, icon.height
+                ));
+            }
+            if icon.width > MAX_ICON_SIZE || icon.height > MAX_ICON_SIZE {
+                errors.push(format!(
+                    "Icon is {}x{}, at most {MAX_ICON_SIZE}x{MAX_ICON_SIZE} is allowed",
+                    icon.width, icon.height
+                ));
+            }
+            icon.check(&mut errors, "Icon");
+        }
+
+        if let Some(cover) = &self.cover {
+            let (max_width, max_height) = MAX_COVER_SIZE;
+            if cover.width > max_width || cover.height > max_height {
+                errors.push(format!(
+                    "Cover is {}x{}, at most {max_width}x{max_height} is allowed",
+                    cover.width, cover.height
+                ));
+            }
+            cover.check(&mut errors, "Cover");
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Player counts in a readable form, like "1-4 players".
+    pub fn players_label(&self) -> String {
+        match (self.min_players, self.max_players) {
+            (1, 1) => String::from("1 player"),
+            (min, max) if min == max => format!("{min} players"),
+            (min, max) => format!("{min}-{max} players"),
+        }
+    }
+}
+
+/// A semantic version, `major.minor.patch`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct RomVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl fmt::Display for RomVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for RomVersion {
+    type Err = String;
+
+    /// Parses `major.minor.patch`. Pre-release and build suffixes aren't
+    /// stored, so they're rejected rather than silently dropped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(start) = s.find(['-', '+']) {
+            return Err(format!(
+                "Invalid version {s:?}: suffixes like {:?} aren't supported, expected major.minor.patch",
+                &s[start..]
+            ));
+        }
+
+        let parts = s
+            .split('.')
+            .map(|part| {
+                if part.is_empty() || !part.bytes().all(|digit| digit.is_ascii_digit()) {
+                    return Err(format!("Invalid version {s:?}: {part:?} isn't a number"));
+                }
+                part.parse::<u32>()
+                    .map_err(|e| format!("Invalid version {s:?}: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match parts[..] {
+            [major, minor, patch] => Ok(Self {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(format!("Invalid version {s:?}: expected major.minor.patch")),
+        }
+    }
+}
+
+/// An uncompressed RGBA8 image.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct RomImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl RomImage {
+    fn check(&self, errors: &mut Vec<String>, name: &str) {
+        if self.width == 0 || self.height == 0 {
+            errors.push(format!("{name} is empty"));
+        }
+        if self.rgba.len() != self.width as usize * self.height as usize * 4 {
+            errors.push(format!(
+                "{name} has {} bytes of pixel data, expected {}",
+                self.rgba.len(),
+                self.width as usize * self.height as usize * 4
+            ));
+        }
+    }
+}
+
+fn check_length(errors: &mut Vec<String>, name: &str, value: &str, max: usize) {
+    let length = value.chars().count();
+    if length > max {
+        errors.push(format!(
+            "{name} is {length} characters long, at most {max} are allowed"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(
+            "1.20.3".parse(),
+            Ok(RomVersion {
+                major: 1,
+                minor: 20,
+                patch: 3
+            })
+        );
+        assert_eq!(
+            "1.20.3".parse::<RomVersion>().unwrap().to_string(),
+            "1.20.3"
+        );
+
+        for invalid in [
+            "",
+            "1",
+            "1.2",
+            "1.2.3.4",
+            "1..3",
+            "a.b.c",
+            "+1.2.3",
+            "1.2.99999999999",
+        ] {
+            assert!(invalid.parse::<RomVersion>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_version_suffixes() {
+        for suffixed in ["1.0.0-beta.1", "1.0.0+abc", "1.0.0-rc.1+build.5"] {
+            let error = suffixed.parse::<RomVersion>().unwrap_err();
+            assert!(error.contains("suffixes"), "{error}");
+        }
+    }
+
+    fn valid_metadata() -> RomMetadata {
+        RomMetadata {
+            title: String::from("Game"),
+            icon: Some(RomImage {
+                width: 2,
+                height: 2,
+                rgba: vec![0; 16],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_valid_metadata() {
+        assert_eq!(valid_metadata().validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let metadata = RomMetadata {
+            title: String::from(" "),
+            author: "a".repeat(MAX_AUTHOR_LENGTH + 1),
+            min_players: 3,
+            max_players: MAX_PLAYERS + 1,
+            genres: vec![String::new()],
+            icon: Some(RomImage {
+                width: 2,
+                height: 1,
+                rgba: vec![0; 4],
+            }),
+            cover: Some(RomImage {
+                width: MAX_COVER_SIZE.0 + 1,
+                height: 1,
+                rgba: vec![0; (MAX_COVER_SIZE.0 + 1) as usize * 4],
+            }),
+            ..valid_metadata()
+        };
+
+        let errors = metadata.validate().unwrap_err();
+        for expected in [
+            "Title is empty",
+            "Author is",
+            "Maximum players",
+            "Genres can't be empty",
+            "Icon must be square",
+            "Icon has 4 bytes",
+            "Cover is",
+        ] {
+            assert!(
+                errors.iter().any(|error| error.starts_with(expected)),
+                "{expected:?} missing from {errors:?}"
+            );
+        }
+        assert_eq!(errors.len(), 7, "{errors:?}");
+
+        let metadata = RomMetadata {
+            min_players: 0,
+            max_players: 0,
+            ..valid_metadata()
+        };
+        assert_eq!(
+            metadata.validate(),
+            Err(vec![String::from("Minimum players must be at least 1")])
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+/// The console's pipelines, numbered as in the README. Each decides which
+/// attributes a vertex has, as consecutive `f32`s in the order position,
+/// color, uv, normal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum Pipeline {
+    Color,
+    Uv,
+    ColorUv,
+    Quad2d,
+    Matcap,
+    MatcapColor,
+    MatcapUv,
+    MatcapColorUv,
+}
+
+impl Pipeline {
+    pub const ALL: [Self; 8] = [
+        Self::Color,
+        Self::Uv,
+        Self::ColorUv,
+        Self::Quad2d,
+        Self::Matcap,
+        Self::MatcapColor,
+        Self::MatcapUv,
+        Self::MatcapColorUv,
+    ];
+
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|index| Self::ALL.get(index))
+            .copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Color => "color",
+            Self::Uv => "uv",
+            Self::ColorUv => "color uv",
+            Self::Quad2d => "quad 2d",
+            Self::Matcap => "matcap",
+            Self::MatcapColor => "matcap color",
+            Self::MatcapUv => "matcap uv",
+            Self::MatcapColorUv => "matcap color uv",
+        }
+    }
+
+    /// The pipeline using exactly these attributes.
+    pub fn from_attributes(color: bool, uv: bool, normals: bool) -> Self {
+        match (color, uv, normals) {
+            (true, true, true) => Self::MatcapColorUv,
+            (true, false, true) => Self::MatcapColor,
+            (false, true, true) => Self::MatcapUv,
+            (false, false, true) => Self::Matcap,
+            (true, true, false) => Self::ColorUv,
+            (false, true, false) => Self::Uv,
+            // Position only meshes are drawn in a flat color
+            (_, false, false) => Self::Color,
+        }
+    }
+
+    pub fn has_color(self) -> bool {
+        matches!(
+            self,
+            Self::Color | Self::ColorUv | Self::MatcapColor | Self::MatcapColorUv
+        )
+    }
+
+    pub fn has_uv(self) -> bool {
+        matches!(
+            self,
+            Self::Uv | Self::ColorUv | Self::Quad2d | Self::MatcapUv | Self::MatcapColorUv
+        )
+    }
+
+    pub fn has_normals(self) -> bool {
+        matches!(
+            self,
+            Self::Matcap | Self::MatcapColor | Self::MatcapUv | Self::MatcapColorUv
+        )
+    }
+
+    pub fn floats_per_vertex(self) -> usize {
+        3 + self.has_color() as usize * 3
+            + self.has_uv() as usize * 2
+            + self.has_normals() as usize * 3
+    }
+}
+
+impl TryFrom<i32> for Pipeline {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Self::from_id(value).ok_or("Invalid Pipeline")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_round_trip() {
+        for (id, pipeline) in Pipeline::ALL.into_iter().enumerate() {
+            assert_eq!(pipeline.id(), id as i32);
+            assert_eq!(Pipeline::from_id(id as i32), Some(pipeline));
+        }
+        assert_eq!(Pipeline::from_id(-1), None);
+        assert_eq!(Pipeline::from_id(8), None);
+    }
+
+    #[test]
+    fn attributes_pick_their_pipeline() {
+        for pipeline in Pipeline::ALL {
+            if pipeline == Pipeline::Quad2d {
+                continue;
+            }
+            let (color, uv, normals) = (
+                pipeline.has_color(),
+                pipeline.has_uv(),
+                pipeline.has_normals(),
+            );
+            assert_eq!(Pipeline::from_attributes(color, uv, normals), pipeline);
+        }
+        assert_eq!(Pipeline::MatcapColorUv.floats_per_vertex(), 11);
+        assert_eq!(Pipeline::Quad2d.floats_per_vertex(), 5);
+    }
+}
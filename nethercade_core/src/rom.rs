@@ -1,13 +1,42 @@
+use std::{borrow::Cow, sync::Arc};
+
 use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use super::{FrameRate, Resolution};
+use super::{
+    Asset, DebugInfo, FrameRate, MAX_SECTION_SIZE, Resolution, RomContainer, RomError, RomMetadata,
+    RomSignature, SectionKind, container::decompress,
+};
 
-#[derive(Debug, Clone, Deserialize, Serialize, Encode, Decode)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Rom {
     pub code: Box<[u8]>,
     pub resolution: Resolution,
     pub frame_rate: FrameRate,
+    pub metadata: RomMetadata,
+    /// Shared so instances of the same game don't each hold a copy.
+    pub assets: Arc<[Asset]>,
+    /// Sections stripped from `code`, kept to name functions in backtraces.
+    pub debug_info: Option<DebugInfo>,
+    /// Verified against the other sections when decoded. Kept as is when
+    /// encoding, so it only stays valid if nothing else changed.
+    pub signature: Option<RomSignature>,
+}
+
+/// Contents of the header section.
+#[derive(Encode, Decode)]
+struct RomHeader {
+    resolution: Resolution,
+    frame_rate: FrameRate,
+}
+
+/// ROMs from before the container format were this struct bitcode encoded
+/// and zstd compressed.
+#[derive(Decode)]
+struct LegacyRom {
+    code: Box<[u8]>,
+    resolution: Resolution,
+    frame_rate: FrameRate,
 }
 
 impl Rom {
@@ -16,6 +45,148 @@ impl Rom {
             code: code.to_vec().into_boxed_slice(),
             resolution: Resolution::default(),
             frame_rate: FrameRate::default(),
+            metadata: RomMetadata::default(),
+            assets: Arc::default(),
+            debug_info: None,
+            signature: None,
+        }
+    }
+
+    /// Hex encoded hash of the game code, used to identify a ROM across builds
+    /// that only change settings.
+    pub fn hash(&self) -> String {
+        blake3::hash(&self.code).to_hex().to_string()
+    }
+
+    /// The code to compile, with any debug info put back so backtraces can
+    /// name functions.
+    pub fn symbolicated_code(&self) -> Cow<'_, [u8]> {
+        match &self.debug_info {
+            Some(debug_info) => Cow::Owned(debug_info.attach(&self.code)),
+            None => Cow::Borrowed(&self.code),
+        }
+    }
+
+    /// Index of the asset called `name`, as handed to the guest.
+    pub fn asset_id(&self, name: &str) -> Option<usize> {
+        self.assets.iter().position(|asset| asset.name == name)
+    }
+
+    /// Bytes the assets take encoded, before compression, which is what
+    /// [`MAX_ASSETS_SIZE`](crate::console_spec::MAX_ASSETS_SIZE) limits.
+    pub fn assets_size(&self) -> usize {
+        if self.assets.is_empty() {
+            0
+        } else {
+            bitcode::encode(&*self.assets).len()
+        }
+    }
+
+    /// The contents of a `.nzrom` file.
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_container().encode()
+    }
+
+    pub fn to_container(&self) -> RomContainer {
+        let header = RomHeader {
+            resolution: self.resolution,
+            frame_rate: self.frame_rate,
+        };
+
+        let mut container = RomContainer::default();
+        container.set_section(SectionKind::Header, bitcode::encode(&header), false);
+        container.set_section(SectionKind::Code, self.code.to_vec(), true);
+        container.set_section(SectionKind::Metadata, bitcode::encode(&self.metadata), true);
+        if !self.assets.is_empty() {
+            container.set_section(SectionKind::Assets, bitcode::encode(&*self.assets), true);
+        }
+        if let Some(debug_info) = &self.debug_info {
+            container.set_section(SectionKind::DebugInfo, bitcode::encode(debug_info), true);
+        }
+        if let Some(signature) = &self.signature {
+            container.set_section(SectionKind::Signature, bitcode::encode(signature), false);
         }
+        container
+    }
+
+    /// Reads a `.nzrom` file, either a container or the older format.
+    pub fn decode(bytes: &[u8]) -> Result<Self, RomError> {
+        if RomContainer::is_container(bytes) {
+            Self::from_container(&RomContainer::decode(bytes)?)
+        } else {
+            Self::decode_legacy(bytes)
+        }
+    }
+
+    pub fn from_container(container: &RomContainer) -> Result<Self, RomError> {
+        let header = container
+            .section(SectionKind::Header)
+            .ok_or(RomError::MissingSection(SectionKind::Header))?;
+        let header =
+            bitcode::decode::<RomHeader>(header).map_err(|e| RomError::InvalidSection {
+                kind: SectionKind::Header,
+                reason: e.to_string(),
+            })?;
+
+        let code = container
+            .section(SectionKind::Code)
+            .ok_or(RomError::MissingSection(SectionKind::Code))?;
+
+        // Optional, containers written before metadata existed don't have it
+        let metadata = match container.section(SectionKind::Metadata) {
+            Some(metadata) => bitcode::decode(metadata).map_err(|e| RomError::InvalidSection {
+                kind: SectionKind::Metadata,
+                reason: e.to_string(),
+            })?,
+            None => RomMetadata::default(),
+        };
+
+        let assets = match container.section(SectionKind::Assets) {
+            Some(assets) => bitcode::decode::<Vec<Asset>>(assets)
+                .map_err(|e| RomError::InvalidSection {
+                    kind: SectionKind::Assets,
+                    reason: e.to_string(),
+                })?
+                .into(),
+            None => Arc::default(),
+        };
+
+        let debug_info = match container.section(SectionKind::DebugInfo) {
+            Some(debug_info) => {
+                Some(
+                    bitcode::decode(debug_info).map_err(|e| RomError::InvalidSection {
+                        kind: SectionKind::DebugInfo,
+                        reason: e.to_string(),
+                    })?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            code: code.into(),
+            resolution: header.resolution,
+            frame_rate: header.frame_rate,
+            metadata,
+            assets,
+            debug_info,
+            signature: container.verified_signature()?,
+        })
+    }
+
+    fn decode_legacy(bytes: &[u8]) -> Result<Self, RomError> {
+        let bytes = decompress(bytes, MAX_SECTION_SIZE).map_err(RomError::InvalidLegacyRom)?;
+        let rom = bitcode::decode::<LegacyRom>(&bytes)
+            .map_err(|e| RomError::InvalidLegacyRom(e.to_string()))?;
+
+        Ok(Self {
+            code: rom.code,
+            resolution: rom.resolution,
+            frame_rate: rom.frame_rate,
+            metadata: RomMetadata::default(),
+            assets: Arc::default(),
+            debug_info: None,
+            signature: None,
+        })
     }
 }
@@ -0,0 +1,180 @@
+use bitcode::{Decode, Encode};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use super::{RomContainer, RomError, SectionKind};
+
+/// Who built a ROM, stored in its signature section. It covers the contents
+/// of every other section, so changing any of them breaks it.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct RomSignature {
+    /// Name the signer gave, only meaningful once `public_key` is trusted.
+    pub signer: String,
+    pub public_key: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+impl RomSignature {
+    /// What gets signed: a hash of each section but the signature, in a
+    /// fixed order, and the signer's name.
+    fn message(container: &RomContainer, signer: &str) -> blake3::Hash {
+        let mut sections = container
+            .sections
+            .iter()
+            .filter(|section| section.kind != SectionKind::Signature)
+            .collect::<Vec<_>>();
+        sections.sort_by_key(|section| section.kind.id());
+
+        let mut hasher = blake3::Hasher::new();
+        for section in sections {
+            hasher.update(&section.kind.id().to_le_bytes());
+            hasher.update(blake3::hash(&section.data).as_bytes());
+        }
+        hasher.update(signer.as_bytes());
+        hasher.finalize()
+    }
+}
+
+impl RomContainer {
+    /// Signs the current sections, replacing any earlier signature. Sections
+    /// changed afterwards invalidate it.
+    pub fn sign(&mut self, key: &SigningKey, signer: &str) {
+        self.sections
+            .retain(|section| section.kind != SectionKind::Signature);
+        let message = RomSignature::message(self, signer);
+        let signature = RomSignature {
+            signer: signer.to_string(),
+            public_key: key.verifying_key().to_bytes(),
+            signature: key.sign(message.as_bytes()).to_bytes().to_vec(),
+        };
+        self.set_section(SectionKind::Signature, bitcode::encode(&signature), false);
+    }
+
+    /// The signature, checked against the other sections. Whether its key
+    /// is trusted is up to the caller.
+    pub fn verified_signature(&self) -> Result<Option<RomSignature>, RomError> {
+        let Some(section) = self.section(SectionKind::Signature) else {
+            return Ok(None);
+        };
+        let signature =
+            bitcode::decode::<RomSignature>(section).map_err(|e| RomError::InvalidSection {
+                kind: SectionKind::Signature,
+                reason: e.to_string(),
+            })?;
+
+        let key = VerifyingKey::from_bytes(&signature.public_key)
+            .map_err(|_| RomError::InvalidSignature)?;
+        let ed25519 =
+            Signature::from_slice(&signature.signature).map_err(|_| RomError::InvalidSignature)?;
+        let message = RomSignature::message(self, &signature.signer);
+        key.verify_strict(message.as_bytes(), &ed25519)
+            .map_err(|_| RomError::InvalidSignature)?;
+
+        Ok(Some(signature))
+    }
+}
+
+/// Hex encoding used for key files and for listing trusted keys.
+pub fn key_to_hex(key: &[u8; 32]) -> String {
+    key.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Reads a hex encoded key, ignoring surrounding whitespace.
+pub fn key_from_hex(hex: &str) -> Result<[u8; 32], String> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return Err(String::from("expected 64 hex digits"));
+    }
+
+    let mut key = [0; 32];
+    for (byte, digits) in key.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let digits = std::str::from_utf8(digits).unwrap();
+        *byte = u8::from_str_radix(digits, 16).unwrap();
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_container(key: &SigningKey) -> RomContainer {
+        let mut container = RomContainer::default();
+        container.set_section(SectionKind::Header, vec![1, 2, 3], false);
+        container.set_section(SectionKind::Code, vec![4, 5, 6], true);
+        container.sign(key, "Tester");
+        container
+    }
+
+    /// Replaces the signature section with an edited copy.
+    fn edit_signature(container: &mut RomContainer, edit: impl FnOnce(&mut RomSignature)) {
+        let section = container.section(SectionKind::Signature).unwrap();
+        let mut signature = bitcode::decode::<RomSignature>(section).unwrap();
+        edit(&mut signature);
+        container.set_section(SectionKind::Signature, bitcode::encode(&signature), false);
+    }
+
+    #[test]
+    fn verifies_signed_containers() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let container = signed_container(&key);
+
+        let signature = container.verified_signature().unwrap().unwrap();
+        assert_eq!(signature.signer, "Tester");
+        assert_eq!(signature.public_key, key.verifying_key().to_bytes());
+
+        // And after a trip through the file format
+        let decoded = RomContainer::decode(&container.encode()).unwrap();
+        assert!(decoded.verified_signature().unwrap().is_some());
+    }
+
+    #[test]
+    fn unsigned_containers_have_no_signature() {
+        let container = RomContainer::default();
+        assert!(container.verified_signature().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_tampered_sections() {
+        let mut container = signed_container(&SigningKey::from_bytes(&[7; 32]));
+        container.set_section(SectionKind::Code, vec![4, 5, 7], true);
+
+        assert!(matches!(
+            container.verified_signature(),
+            Err(RomError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn rejects_changed_signer() {
+        let mut container = signed_container(&SigningKey::from_bytes(&[7; 32]));
+        edit_signature(&mut container, |signature| {
+            signature.signer = String::from("Someone else")
+        });
+
+        assert!(matches!(
+            container.verified_signature(),
+            Err(RomError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn rejects_swapped_public_key() {
+        let mut container = signed_container(&SigningKey::from_bytes(&[7; 32]));
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes();
+        edit_signature(&mut container, |signature| signature.public_key = other);
+
+        assert!(matches!(
+            container.verified_signature(),
+            Err(RomError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn keys_round_trip_through_hex() {
+        let key = [0xab; 32];
+        assert_eq!(key_from_hex(&format!(" {}\n", key_to_hex(&key))), Ok(key));
+        assert!(key_from_hex("ab").is_err());
+        assert!(key_from_hex(&format!("+{}", &key_to_hex(&key)[1..])).is_err());
+    }
+}